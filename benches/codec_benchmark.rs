@@ -68,8 +68,8 @@ fn criterion_benchmark(c: &mut Criterion) {
     let elements = 10 * 1024;
     let symbol_size = 512;
     let mut data: Vec<u8> = vec![0; elements];
-    for i in 0..elements {
-        data[i] = rand::thread_rng().gen();
+    for element in &mut data {
+        *element = rand::thread_rng().gen();
     }
 
     let encode_data = data.clone();
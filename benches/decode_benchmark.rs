@@ -16,16 +16,15 @@ fn benchmark(symbol_size: u16, overhead: f64) -> u64 {
     for &symbol_count in SYMBOL_COUNTS.iter() {
         let elements = symbol_count * symbol_size as usize;
         let mut data: Vec<u8> = vec![0; elements];
-        for i in 0..elements {
-            data[i] = rand::thread_rng().gen();
+        for element in &mut data {
+            *element = rand::thread_rng().gen();
         }
 
         let iterations = TARGET_TOTAL_BYTES / elements;
         let config = ObjectTransmissionInformation::new(0, symbol_size, 0, 1, 1);
         let encoder = SourceBlockEncoder::new2(1, &config, &data);
         let elements_and_overhead = (symbol_count as f64 * (1.0 + overhead)) as u32;
-        let mut packets =
-            encoder.repair_packets(0, (iterations as u32 * elements_and_overhead) as u32);
+        let mut packets = encoder.repair_packets(0, iterations as u32 * elements_and_overhead);
         let now = Instant::now();
         for _ in 0..iterations {
             let mut decoder = SourceBlockDecoder::new2(1, &config, elements as u64);
@@ -45,7 +44,43 @@ fn benchmark(symbol_size: u16, overhead: f64) -> u64 {
                  throughput);
     }
 
-    return black_box_value;
+    black_box_value
+}
+
+// Decodes from the source symbols only, which never requires solving the constraint matrix
+fn benchmark_no_loss(symbol_size: u16) -> u64 {
+    let mut black_box_value = 0;
+    for &symbol_count in SYMBOL_COUNTS.iter() {
+        let elements = symbol_count * symbol_size as usize;
+        let mut data: Vec<u8> = vec![0; elements];
+        for element in &mut data {
+            *element = rand::thread_rng().gen();
+        }
+
+        let iterations = TARGET_TOTAL_BYTES / elements;
+        let config = ObjectTransmissionInformation::new(0, symbol_size, 0, 1, 1);
+        let encoder = SourceBlockEncoder::new2(1, &config, &data);
+        let packets = encoder.source_packets();
+        let now = Instant::now();
+        for _ in 0..iterations {
+            let mut decoder = SourceBlockDecoder::new2(1, &config, elements as u64);
+            if let Some(result) = decoder.decode(packets.clone()) {
+                black_box_value += result[0] as u64;
+            }
+        }
+        let elapsed = now.elapsed();
+        let elapsed = elapsed.as_secs() as f64 + elapsed.subsec_millis() as f64 * 0.001;
+        let throughput = (elements * iterations * 8) as f64 / 1024.0 / 1024.0 / elapsed;
+        println!(
+            "symbol count = {}, decoded {} MB in {:.3}secs from source symbols only, throughput: {:.1}Mbit/s",
+            symbol_count,
+            elements * iterations / 1024 / 1024,
+            elapsed,
+            throughput
+        );
+    }
+
+    black_box_value
 }
 
 fn main() {
@@ -54,4 +89,6 @@ fn main() {
     black_box(benchmark(symbol_size, 0.0));
     println!();
     black_box(benchmark(symbol_size, 0.05));
+    println!();
+    black_box(benchmark_no_loss(symbol_size));
 }
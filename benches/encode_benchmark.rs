@@ -16,8 +16,8 @@ fn benchmark(symbol_size: u16, pre_plan: bool) -> u64 {
     for symbol_count in SYMBOL_COUNTS.iter() {
         let elements = symbol_count * symbol_size as usize;
         let mut data: Vec<u8> = vec![0; elements];
-        for i in 0..elements {
-            data[i] = rand::thread_rng().gen();
+        for element in &mut data {
+            *element = rand::thread_rng().gen();
         }
 
         let plan = if pre_plan {
@@ -49,7 +49,7 @@ fn benchmark(symbol_size: u16, pre_plan: bool) -> u64 {
             throughput
         );
     }
-    return black_box_value;
+    black_box_value
 }
 
 fn main() {
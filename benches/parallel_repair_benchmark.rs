@@ -0,0 +1,63 @@
+use rand::Rng;
+use raptorq::{ObjectTransmissionInformation, SourceBlockEncoder};
+use std::time::{Duration, Instant};
+
+const SYMBOL_SIZE: u16 = 1280;
+const SYMBOL_COUNT: usize = 10000;
+const REPAIR_SYMBOLS: u32 = 100_000;
+
+fn black_box(value: u64) {
+    if value == rand::thread_rng().gen() {
+        println!("{}", value);
+    }
+}
+
+fn seconds(elapsed: Duration) -> f64 {
+    elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9
+}
+
+// Generates many repair symbols of a single source block, serially and across the rayon thread
+// pool. Each repair symbol is computed independently once the intermediate symbols have been solved
+// for, so any speedup depends on the number of cores. On a single core this only measures the
+// overhead of the thread pool.
+fn main() {
+    let mut black_box_value = 0;
+    let elements = SYMBOL_COUNT * SYMBOL_SIZE as usize;
+    let mut data: Vec<u8> = vec![0; elements];
+    for element in &mut data {
+        *element = rand::thread_rng().gen();
+    }
+
+    let config = ObjectTransmissionInformation::new(0, SYMBOL_SIZE, 0, 1, 1);
+    let encoder = SourceBlockEncoder::new2(1, &config, &data);
+    let megabytes = REPAIR_SYMBOLS as f64 * SYMBOL_SIZE as f64 / 1024.0 / 1024.0;
+    println!(
+        "symbol count = {}, generating {} repair symbols ({:.0} MB) on {} threads",
+        SYMBOL_COUNT,
+        REPAIR_SYMBOLS,
+        megabytes,
+        rayon::current_num_threads()
+    );
+
+    let now = Instant::now();
+    let packets = encoder.repair_packets(0, REPAIR_SYMBOLS);
+    let serial = seconds(now.elapsed());
+    black_box_value += packets[0].data()[0] as u64;
+    println!(
+        "serial: {:.3}secs, throughput: {:.1}Mbit/s",
+        serial,
+        megabytes * 8.0 / serial
+    );
+
+    let now = Instant::now();
+    let packets = encoder.par_repair_packets(0, REPAIR_SYMBOLS);
+    let parallel = seconds(now.elapsed());
+    black_box_value += packets[0].data()[0] as u64;
+    println!(
+        "parallel: {:.3}secs, throughput: {:.1}Mbit/s, speedup: {:.2}x",
+        parallel,
+        megabytes * 8.0 / parallel,
+        serial / parallel
+    );
+    black_box(black_box_value);
+}
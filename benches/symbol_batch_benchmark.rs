@@ -0,0 +1,63 @@
+use rand::Rng;
+use raptorq::{ObjectTransmissionInformation, SourceBlockEncoder};
+use std::time::{Duration, Instant};
+
+const SYMBOL_SIZE: u16 = 1280;
+const SYMBOL_COUNT: usize = 10000;
+const BATCH_SIZES: [u32; 5] = [1, 10, 100, 1000, 10000];
+
+fn black_box(value: u64) {
+    if value == rand::thread_rng().gen() {
+        println!("{}", value);
+    }
+}
+
+fn seconds(elapsed: Duration) -> f64 {
+    elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9
+}
+
+// The intermediate symbols are solved for once, when the encoder is constructed. Generating a
+// batch of symbols afterwards only costs a constant amount of work per symbol, so the time per
+// symbol should stay flat as the batch size grows, and far below the time of the solve.
+fn main() {
+    let mut black_box_value = 0;
+    let elements = SYMBOL_COUNT * SYMBOL_SIZE as usize;
+    let mut data: Vec<u8> = vec![0; elements];
+    for element in &mut data {
+        *element = rand::thread_rng().gen();
+    }
+
+    let config = ObjectTransmissionInformation::new(0, SYMBOL_SIZE, 0, 1, 1);
+    let now = Instant::now();
+    let encoder = SourceBlockEncoder::new2(1, &config, &data);
+    let solve = seconds(now.elapsed());
+    println!(
+        "symbol count = {}, solved for intermediate symbols in {:.3}secs",
+        SYMBOL_COUNT, solve
+    );
+
+    let repair_start = encoder.repair_esi_range().start;
+    for &batch_size in BATCH_SIZES.iter() {
+        // Alternate between source and repair symbols
+        let esis: Vec<u32> = (0..batch_size)
+            .map(|i| {
+                if i % 2 == 0 {
+                    i / 2
+                } else {
+                    repair_start + i / 2
+                }
+            })
+            .collect();
+        let now = Instant::now();
+        let packets = encoder.packets_for_esis(&esis);
+        let elapsed = seconds(now.elapsed());
+        black_box_value += packets[0].data()[0] as u64;
+        println!(
+            "batch size = {}, generated in {:.6}secs, {:.1}us per symbol",
+            batch_size,
+            elapsed,
+            elapsed * 1e6 / batch_size as f64
+        );
+    }
+    black_box(black_box_value);
+}
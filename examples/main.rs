@@ -5,8 +5,8 @@ use raptorq::{Decoder, Encoder, EncodingPacket};
 fn main() {
     // Generate some random data to send
     let mut data: Vec<u8> = vec![0; 10_000];
-    for i in 0..data.len() {
-        data[i] = rand::thread_rng().gen();
+    for element in &mut data {
+        *element = rand::thread_rng().gen();
     }
 
     // Create the Encoder, with an MTU of 1400 (common for Ethernet)
@@ -35,7 +35,7 @@ fn main() {
     let mut result = None;
     while !packets.is_empty() {
         result = decoder.decode(EncodingPacket::deserialize(&packets.pop().unwrap()));
-        if result != None {
+        if result.is_some() {
             break;
         }
     }
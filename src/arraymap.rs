@@ -4,7 +4,7 @@ use std::ops::Range;
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
 // Map<u16, Vec<u32>>
 pub struct ImmutableListMap {
-    // offset of std::u32::MAX indicates that the key is not present
+    // offset of u32::MAX indicates that the key is not present
     offsets: Vec<u32>,
     values: Vec<u32>,
 }
@@ -21,6 +21,7 @@ impl ImmutableListMap {
         &self.values[start..end]
     }
 
+    #[allow(dead_code)]
     pub fn size_in_bytes(&self) -> usize {
         let mut bytes = size_of::<Self>();
         bytes += size_of::<u32>() * self.offsets.len();
@@ -50,9 +51,9 @@ impl ImmutableListMapBuilder {
     pub fn build(self) -> ImmutableListMap {
         let mut entries = self.entries;
         entries.sort_unstable_by_key(|x| x.0);
-        assert!(entries.len() < std::u32::MAX as usize);
+        assert!(entries.len() < u32::MAX as usize);
         assert!(!entries.is_empty());
-        let mut offsets = vec![std::u32::MAX; self.num_keys];
+        let mut offsets = vec![u32::MAX; self.num_keys];
         let mut last_key = entries[0].0;
         offsets[last_key as usize] = 0;
         let mut values = vec![];
@@ -64,7 +65,7 @@ impl ImmutableListMapBuilder {
             values.push(*value);
         }
         for i in (0..offsets.len()).rev() {
-            if offsets[i] == std::u32::MAX {
+            if offsets[i] == u32::MAX {
                 if i == offsets.len() - 1 {
                     offsets[i] = entries.len() as u32;
                 } else {
@@ -1,10 +1,138 @@
 use crate::rng::rand;
 use crate::systematic_constants::{
-    MAX_SOURCE_SYMBOLS_PER_BLOCK, SYSTEMATIC_INDICES_AND_PARAMETERS,
+    num_intermediate_symbols, MAX_SOURCE_SYMBOLS_PER_BLOCK, SYSTEMATIC_INDICES_AND_PARAMETERS,
 };
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 use std::cmp::min;
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+/// Error returned when deserializing malformed data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input was not the expected length
+    InvalidLength { expected: usize, actual: usize },
+    /// The input was written with an unsupported format version
+    UnsupportedVersion(u8),
+    /// A reserved field contained a non-zero value
+    NonZeroReserved(u8),
+    /// The symbol alignment was zero
+    ZeroAlignment,
+    /// The number of sub-blocks was zero
+    ZeroSubBlocks,
+    /// The number of source blocks was zero
+    ZeroSourceBlocks,
+    /// The symbol size was not a non-zero multiple of the symbol alignment
+    InvalidSymbolSize(u16),
+    /// A source block would contain more source symbols than are supported
+    TooManySourceSymbols(u64),
+    /// The input was a different kind of message than expected
+    UnexpectedMessageType(u8),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidLength { expected, actual } => {
+                write!(f, "expected {} bytes, but found {}", expected, actual)
+            }
+            ParseError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            ParseError::NonZeroReserved(value) => {
+                write!(f, "reserved field must be zero, but found {}", value)
+            }
+            ParseError::ZeroAlignment => write!(f, "symbol alignment must be non-zero"),
+            ParseError::ZeroSubBlocks => write!(f, "number of sub-blocks must be non-zero"),
+            ParseError::ZeroSourceBlocks => write!(f, "number of source blocks must be non-zero"),
+            ParseError::InvalidSymbolSize(symbol_size) => write!(
+                f,
+                "symbol size {} is not a non-zero multiple of the alignment",
+                symbol_size
+            ),
+            ParseError::TooManySourceSymbols(symbols) => write!(
+                f,
+                "{} source symbols per block required, but at most {} are allowed",
+                symbols, MAX_SOURCE_SYMBOLS_PER_BLOCK
+            ),
+            ParseError::UnexpectedMessageType(message_type) => {
+                write!(f, "unexpected message type {}", message_type)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Error returned when no valid encoding parameters exist for the requested configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParameterError {
+    /// The object would need more source blocks than the 8-bit source block number allows
+    TooManySourceBlocks(u64),
+    /// The symbol size was not a non-zero multiple of the symbol alignment
+    InvalidAlignment { symbol_size: u16, alignment: u8 },
+    /// A source block would contain more source symbols than the 24-bit ESI space, after leaving
+    /// room for repair symbols, or the parameter table, allows
+    TooManySourceSymbols(u64),
+    /// The symbol size required to span the object does not fit in the 16-bit symbol size field
+    SymbolSizeTooLarge(u64),
+    /// The object was required to span zero packets, or zero source blocks
+    ZeroLimit,
+    /// The symbol size was zero
+    ZeroSymbolSize,
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterError::TooManySourceBlocks(blocks) => {
+                write!(
+                    f,
+                    "{} source blocks required, but at most {} are allowed",
+                    blocks, MAX_SOURCE_BLOCKS
+                )
+            }
+            ParameterError::InvalidAlignment {
+                symbol_size,
+                alignment,
+            } => write!(
+                f,
+                "symbol size {} is not a non-zero multiple of the alignment {}",
+                symbol_size, alignment
+            ),
+            ParameterError::TooManySourceSymbols(symbols) => write!(
+                f,
+                "{} source symbols per block required, but at most {} are allowed",
+                symbols, MAX_SOURCE_SYMBOLS_PER_BLOCK
+            ),
+            ParameterError::SymbolSizeTooLarge(symbol_size) => write!(
+                f,
+                "symbol size of {} bytes required, but at most 65535 are allowed",
+                symbol_size
+            ),
+            ParameterError::ZeroLimit => {
+                write!(f, "the object can't span zero packets or source blocks")
+            }
+            ParameterError::ZeroSymbolSize => write!(f, "the symbol size must not be zero"),
+        }
+    }
+}
+
+impl Error for ParameterError {}
+
+/// Maximum transfer length of an object, in bytes: K'_max source symbols in each of 255 source
+/// blocks, of 65535 bytes each. See section 4.1, as corrected by
+/// [errata 5548](https://www.rfc-editor.org/errata/eid5548).
+pub const MAX_TRANSFER_LENGTH: u64 = 942574504275;
+
+/// Size of the 24-bit ESI space: every ESI is less than this. See section 3.2.
+pub const MAX_ESI: u32 = 16777216;
+
+/// Maximum number of source blocks, Z, which the 8-bit source block number can address.
+/// See section 3.2.
+pub const MAX_SOURCE_BLOCKS: u8 = 255;
 
 // As defined in section 3.2
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
@@ -17,7 +145,7 @@ pub struct PayloadId {
 impl PayloadId {
     pub fn new(source_block_number: u8, encoding_symbol_id: u32) -> PayloadId {
         // Encoding Symbol ID must be a 24-bit unsigned int
-        assert!(encoding_symbol_id < 16777216);
+        assert!(encoding_symbol_id < MAX_ESI);
         PayloadId {
             source_block_number,
             encoding_symbol_id,
@@ -48,6 +176,18 @@ impl PayloadId {
     pub fn encoding_symbol_id(&self) -> u32 {
         self.encoding_symbol_id
     }
+
+    /// The 24-bit ESI in big-endian order, as in the last 3 bytes of
+    /// [`serialize`](Self::serialize).
+    pub fn esi_bytes(&self) -> [u8; 3] {
+        let data = self.serialize();
+        [data[1], data[2], data[3]]
+    }
+
+    /// Inverse of [`esi_bytes`](Self::esi_bytes).
+    pub fn from_esi_bytes(source_block_number: u8, esi: [u8; 3]) -> PayloadId {
+        PayloadId::deserialize(&[source_block_number, esi[0], esi[1], esi[2]])
+    }
 }
 
 /// Contains encoding symbols generated from a source block.
@@ -73,6 +213,18 @@ impl EncodingPacket {
         }
     }
 
+    /// Like [`deserialize`](Self::deserialize), but returns an error, rather than panicking, if
+    /// the packet is too short to hold a payload ID.
+    pub fn try_deserialize(data: &[u8]) -> Result<EncodingPacket, ParseError> {
+        if data.len() < 4 {
+            return Err(ParseError::InvalidLength {
+                expected: 4,
+                actual: data.len(),
+            });
+        }
+        Ok(EncodingPacket::deserialize(data))
+    }
+
     pub fn serialize(&self) -> Vec<u8> {
         let mut serialized = Vec::with_capacity(4 + self.data.len());
         serialized.extend_from_slice(&self.payload_id.serialize());
@@ -86,6 +238,9 @@ impl EncodingPacket {
     }
 
     /// Retrieves packet payload.
+    ///
+    /// This is a slice, rather than the underlying `Vec`, so that callers do not depend on how the
+    /// payload is stored.
     pub fn data(&self) -> &[u8] {
         &self.data
     }
@@ -94,6 +249,132 @@ impl EncodingPacket {
     pub fn split(self) -> (PayloadId, Vec<u8>) {
         (self.payload_id, self.data)
     }
+
+    /// Borrows the packet's ID and payload together, so that dispatch code can destructure a
+    /// packet without cloning either. See [`split`](Self::split) for the owning equivalent.
+    pub fn as_parts(&self) -> (&PayloadId, &[u8]) {
+        (&self.payload_id, &self.data)
+    }
+
+    /// Creates a packet from an ID and a borrowed payload, which is copied. Inverse of
+    /// [`as_parts`](Self::as_parts).
+    pub fn from_parts(payload_id: PayloadId, data: &[u8]) -> EncodingPacket {
+        EncodingPacket::new(payload_id, data.to_vec())
+    }
+}
+
+/// Concatenates several packets into a single datagram, to amortize the per-datagram overhead
+/// when symbols are small.
+///
+/// Each packet is [`serialize`](EncodingPacket::serialize)d and prefixed with its length, as a
+/// 2-byte big-endian integer. Packets of different sizes may be mixed. Panics if a serialized
+/// packet exceeds 65535 bytes.
+pub fn pack_packets(packets: &[EncodingPacket]) -> Vec<u8> {
+    let length: usize = packets.iter().map(|packet| 6 + packet.data.len()).sum();
+    let mut packed = Vec::with_capacity(length);
+    for packet in packets {
+        let serialized = packet.serialize();
+        assert!(serialized.len() <= u16::MAX as usize);
+        packed.extend_from_slice(&(serialized.len() as u16).to_be_bytes());
+        packed.extend_from_slice(&serialized);
+    }
+    packed
+}
+
+/// Inverse of [`pack_packets`]. Returns an error if the datagram is truncated, or contains a
+/// frame too short to hold a [`PayloadId`].
+pub fn unpack_packets(data: &[u8]) -> Result<Vec<EncodingPacket>, ParseError> {
+    let mut packets = vec![];
+    let mut remaining = data;
+    while !remaining.is_empty() {
+        if remaining.len() < 2 {
+            return Err(ParseError::InvalidLength {
+                expected: 2,
+                actual: remaining.len(),
+            });
+        }
+        let length = ((remaining[0] as usize) << 8) + remaining[1] as usize;
+        remaining = &remaining[2..];
+        if remaining.len() < length {
+            return Err(ParseError::InvalidLength {
+                expected: length,
+                actual: remaining.len(),
+            });
+        }
+        if length < 4 {
+            return Err(ParseError::InvalidLength {
+                expected: 4,
+                actual: length,
+            });
+        }
+        packets.push(EncodingPacket::deserialize(&remaining[..length]));
+        remaining = &remaining[length..];
+    }
+    Ok(packets)
+}
+
+/// The size and position of one sub-block's sub-symbols, as returned by
+/// [`ObjectTransmissionInformation::sub_block_layout`].
+///
+/// Every source block is divided into the same sub-blocks, and each encoding symbol is the
+/// concatenation of one sub-symbol from each sub-block, in order. A sub-block therefore holds K
+/// sub-symbols, where K is the number of source symbols in its source block.
+/// See the second to last paragraph in section 4.4.1.2.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct SubBlockInfo {
+    sub_symbol_size: u16,
+    symbol_offset: u16,
+}
+
+impl SubBlockInfo {
+    /// Size of each sub-symbol in this sub-block, in bytes. This is TL * Al for the first NL
+    /// sub-blocks and TS * Al for the rest.
+    pub fn sub_symbol_size(&self) -> u16 {
+        self.sub_symbol_size
+    }
+
+    /// Offset of this sub-block's sub-symbol within each encoding symbol, in bytes.
+    pub fn symbol_offset(&self) -> u16 {
+        self.symbol_offset
+    }
+}
+
+// See ObjectTransmissionInformation::sub_block_layout()
+pub(crate) fn sub_block_layout(
+    symbol_size: u16,
+    sub_blocks: u16,
+    alignment: u8,
+) -> Vec<SubBlockInfo> {
+    let alignment = alignment as u32;
+    let (tl, ts, nl, ns) = partition(symbol_size as u32 / alignment, sub_blocks);
+    let mut layout = Vec::with_capacity((nl + ns) as usize);
+    let mut symbol_offset = 0;
+    for sub_block in 0..(nl + ns) {
+        let sub_symbol_size = if sub_block < nl {
+            tl * alignment
+        } else {
+            ts * alignment
+        } as u16;
+        layout.push(SubBlockInfo {
+            sub_symbol_size,
+            symbol_offset,
+        });
+        symbol_offset += sub_symbol_size;
+    }
+    layout
+}
+
+/// A field of the [`ObjectTransmissionInformation`], as returned by
+/// [`ObjectTransmissionInformation::diff`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub enum OtiField {
+    TransferLength,
+    SymbolSize,
+    SourceBlocks,
+    SubBlocks,
+    SymbolAlignment,
 }
 
 // As defined in section 3.3.2 and 3.3.3
@@ -116,7 +397,7 @@ impl ObjectTransmissionInformation {
         alignment: u8,
     ) -> ObjectTransmissionInformation {
         // See errata (https://www.rfc-editor.org/errata/eid5548)
-        assert!(transfer_length <= 942574504275);
+        assert!(transfer_length <= MAX_TRANSFER_LENGTH);
         assert_eq!(symbol_size % alignment as u16, 0);
         // See section 4.4.1.2. "These parameters MUST be set so that ceil(ceil(F/T)/Z) <= K'_max."
         let symbols_required =
@@ -145,6 +426,43 @@ impl ObjectTransmissionInformation {
         }
     }
 
+    /// Like [`deserialize`](Self::deserialize), but validates the input, so that malformed OTI
+    /// received from the network is rejected here, rather than causing a panic later.
+    pub fn try_deserialize(data: &[u8]) -> Result<ObjectTransmissionInformation, ParseError> {
+        if data.len() != 12 {
+            return Err(ParseError::InvalidLength {
+                expected: 12,
+                actual: data.len(),
+            });
+        }
+        if data[5] != 0 {
+            return Err(ParseError::NonZeroReserved(data[5]));
+        }
+        let mut bytes = [0; 12];
+        bytes.copy_from_slice(data);
+        let config = ObjectTransmissionInformation::deserialize(&bytes);
+        if config.symbol_alignment == 0 {
+            return Err(ParseError::ZeroAlignment);
+        }
+        if config.num_sub_blocks == 0 {
+            return Err(ParseError::ZeroSubBlocks);
+        }
+        if config.num_source_blocks == 0 {
+            return Err(ParseError::ZeroSourceBlocks);
+        }
+        if config.symbol_size == 0 || config.symbol_size % config.symbol_alignment as u16 != 0 {
+            return Err(ParseError::InvalidSymbolSize(config.symbol_size));
+        }
+        if let Err(ParameterError::TooManySourceSymbols(symbols)) = validate_esi_space(
+            config.transfer_length,
+            config.symbol_size,
+            config.num_source_blocks,
+        ) {
+            return Err(ParseError::TooManySourceSymbols(symbols));
+        }
+        Ok(config)
+    }
+
     pub fn serialize(&self) -> [u8; 12] {
         [
             ((self.transfer_length >> 32) & 0xFF) as u8,
@@ -162,6 +480,36 @@ impl ObjectTransmissionInformation {
         ]
     }
 
+    /// Common FEC OTI: the transfer length and symbol size, as defined in
+    /// [section 3.3.2](https://tools.ietf.org/html/rfc6330#section-3.3.2). This is the first 8
+    /// bytes of [`serialize`](Self::serialize).
+    pub fn common_oti(&self) -> [u8; 8] {
+        let data = self.serialize();
+        [
+            data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7],
+        ]
+    }
+
+    /// Scheme-Specific FEC OTI: the number of source blocks, sub-blocks and the symbol alignment,
+    /// as defined in [section 3.3.3](https://tools.ietf.org/html/rfc6330#section-3.3.3). This is
+    /// the last 4 bytes of [`serialize`](Self::serialize).
+    pub fn scheme_specific_oti(&self) -> [u8; 4] {
+        let data = self.serialize();
+        [data[8], data[9], data[10], data[11]]
+    }
+
+    /// Reassembles an OTI from its Common and Scheme-Specific parts, for protocols which carry
+    /// them separately.
+    pub fn from_parts(
+        common: &[u8; 8],
+        scheme_specific: &[u8; 4],
+    ) -> ObjectTransmissionInformation {
+        let mut data = [0; 12];
+        data[..8].copy_from_slice(common);
+        data[8..].copy_from_slice(scheme_specific);
+        ObjectTransmissionInformation::deserialize(&data)
+    }
+
     pub fn transfer_length(&self) -> u64 {
         self.transfer_length
     }
@@ -182,18 +530,274 @@ impl ObjectTransmissionInformation {
         self.symbol_alignment
     }
 
+    /// Returns true if a receiver using `other` can decode the packets of a sender using this
+    /// OTI. Every field determines how the object is partitioned into symbols, so they must all
+    /// match. Use [`diff`](Self::diff) to find out which don't.
+    pub fn is_compatible_with(&self, other: &ObjectTransmissionInformation) -> bool {
+        self.diff(other).is_empty()
+    }
+
+    /// Returns the fields which differ between this OTI and `other`, in the order in which they
+    /// are serialized, for diagnosing a failed negotiation between a sender and receiver.
+    pub fn diff(&self, other: &ObjectTransmissionInformation) -> Vec<OtiField> {
+        let mut fields = vec![];
+        if self.transfer_length != other.transfer_length {
+            fields.push(OtiField::TransferLength);
+        }
+        if self.symbol_size != other.symbol_size {
+            fields.push(OtiField::SymbolSize);
+        }
+        if self.num_source_blocks != other.num_source_blocks {
+            fields.push(OtiField::SourceBlocks);
+        }
+        if self.num_sub_blocks != other.num_sub_blocks {
+            fields.push(OtiField::SubBlocks);
+        }
+        if self.symbol_alignment != other.symbol_alignment {
+            fields.push(OtiField::SymbolAlignment);
+        }
+        fields
+    }
+
+    /// Describes the N sub-blocks that each source block is divided into, in order.
+    ///
+    /// The sizes are derived from (TL, TS, NL, NS) = Partition[T/Al, N], as described in
+    /// [section 4.4.1.2](https://tools.ietf.org/html/rfc6330#section-4.4.1.2). Without
+    /// sub-blocking this is a single sub-block spanning the whole symbol.
+    pub fn sub_block_layout(&self) -> Vec<SubBlockInfo> {
+        sub_block_layout(self.symbol_size, self.num_sub_blocks, self.symbol_alignment)
+    }
+
+    /// Iterates over the source block numbers (SBNs) of the object, in order.
+    ///
+    /// ```
+    /// use raptorq::ObjectTransmissionInformation;
+    ///
+    /// let config = ObjectTransmissionInformation::new(10_000, 64, 4, 1, 8);
+    /// let symbols: u32 = config
+    ///     .source_block_numbers()
+    ///     .map(|sbn| config.symbols_in_block(sbn))
+    ///     .sum();
+    /// assert_eq!(symbols, 157);
+    /// ```
+    pub fn source_block_numbers(&self) -> impl Iterator<Item = u8> {
+        0..self.num_source_blocks
+    }
+
+    /// Number of source symbols, Kt, that the object is divided into before it is partitioned into
+    /// source blocks: ceil(F / T). See section 4.4.1.2.
+    pub fn kt(&self) -> u64 {
+        let symbol_size = self.symbol_size as u64;
+        (self.transfer_length + symbol_size - 1) / symbol_size
+    }
+
+    /// Number of source symbols, K, in the given source block.
+    ///
+    /// The Kt source symbols of the object are split across the source blocks using
+    /// [`partition`], so the first blocks may contain one more symbol than the rest.
+    /// See section 4.4.1.2.
+    pub fn symbols_in_block(&self, source_block_number: u8) -> u32 {
+        assert!(source_block_number < self.num_source_blocks);
+        let (kl, ks, zl, _) = partition(self.kt() as u32, self.num_source_blocks);
+        if (source_block_number as u32) < zl {
+            kl
+        } else {
+            ks
+        }
+    }
+
+    /// Total number of source symbols over all the source blocks, which is the same as
+    /// [`kt`](Self::kt).
+    #[deprecated(
+        since = "1.7.0",
+        note = "Use kt() instead, which returns the same value"
+    )]
+    pub fn total_source_symbols(&self) -> u64 {
+        self.kt()
+    }
+
+    /// Payload IDs of every source symbol of the object, in order of source block number, then
+    /// ESI. Each block contributes its own K symbols, as given by
+    /// [`symbols_in_block`](Self::symbols_in_block).
+    pub fn source_symbol_ids(&self) -> impl Iterator<Item = PayloadId> + '_ {
+        self.source_block_numbers().flat_map(move |sbn| {
+            (0..self.symbols_in_block(sbn)).map(move |esi| PayloadId::new(sbn, esi))
+        })
+    }
+
+    /// Total number of bytes in the serialized packets of a transfer with `repair_per_block` repair
+    /// packets in each source block: every source and repair symbol, each preceded by its 4 byte
+    /// [`PayloadId`]. This is the size of the output of
+    /// [`Encoder::get_encoded_packets`](crate::Encoder::get_encoded_packets), when each packet is
+    /// [`serialize`](EncodingPacket::serialize)d, assuming the final source symbol keeps its zero
+    /// padding (the default).
+    pub fn serialized_transfer_size(&self, repair_per_block: u32) -> u64 {
+        let packets = self.kt() + self.num_source_blocks as u64 * repair_per_block as u64;
+        packets * (4 + self.symbol_size as u64)
+    }
+
+    /// Total number of intermediate symbols, L, over all the source blocks. Multiplied by the
+    /// symbol size, this is the working set of a decoder which holds every block until it is
+    /// decoded, so receivers can check that they can afford to decode the transfer.
+    /// See section 5.3.3.3.
+    pub fn total_intermediate_symbols(&self) -> u64 {
+        self.source_block_numbers()
+            .map(|sbn| num_intermediate_symbols(self.symbols_in_block(sbn)) as u64)
+            .sum()
+    }
+
+    /// Location of the given source symbol's bytes in the original object.
+    ///
+    /// Returns `None` for ESIs which do not identify a source symbol (padding and repair symbols),
+    /// and when sub-blocking is in use, since each symbol is then a concatenation of several
+    /// non-contiguous ranges. The range of the final source symbol excludes the
+    /// [`padding_bytes()`](Self::padding_bytes).
+    pub fn symbol_byte_range(&self, source_block_number: u8, esi: u32) -> Option<Range<u64>> {
+        if source_block_number >= self.num_source_blocks
+            || esi >= self.symbols_in_block(source_block_number)
+            || self.num_sub_blocks > 1
+        {
+            return None;
+        }
+        let symbol_size = self.symbol_size as u64;
+        let (kl, ks, zl, _) = partition(self.kt() as u32, self.num_source_blocks);
+        let source_block_number = source_block_number as u64;
+        let block_start_symbol = if source_block_number < zl as u64 {
+            source_block_number * kl as u64
+        } else {
+            zl as u64 * kl as u64 + (source_block_number - zl as u64) * ks as u64
+        };
+        let start = (block_start_symbol + esi as u64) * symbol_size;
+        Some(start..min(start + symbol_size, self.transfer_length))
+    }
+
+    /// The source symbol which contains the given byte of the original object, for example to
+    /// find out which symbol to request again when that byte is known to be corrupt. Inverse of
+    /// [`symbol_byte_range`](Self::symbol_byte_range).
+    ///
+    /// Unlike `symbol_byte_range()`, this also supports sub-blocking, since each byte still belongs
+    /// to exactly one source symbol (section 4.4.1.2). Returns `None` if `byte_offset` is not less
+    /// than the transfer length.
+    pub fn symbol_at_offset(&self, byte_offset: u64) -> Option<PayloadId> {
+        if byte_offset >= self.transfer_length {
+            return None;
+        }
+        let symbol_size = self.symbol_size as u64;
+        let (kl, ks, zl, _) = partition(self.kt() as u32, self.num_source_blocks);
+        let large_blocks_bytes = zl as u64 * kl as u64 * symbol_size;
+        let (sbn, symbols, block_offset) = if byte_offset < large_blocks_bytes {
+            let block_bytes = kl as u64 * symbol_size;
+            (
+                byte_offset / block_bytes,
+                kl as u64,
+                byte_offset % block_bytes,
+            )
+        } else {
+            let block_bytes = ks as u64 * symbol_size;
+            let offset = byte_offset - large_blocks_bytes;
+            (
+                zl as u64 + offset / block_bytes,
+                ks as u64,
+                offset % block_bytes,
+            )
+        };
+
+        // Each sub-block holds the sub-symbols of every symbol of the block, one after another
+        let mut sub_block_start = 0;
+        for info in self.sub_block_layout() {
+            let sub_symbol_size = info.sub_symbol_size() as u64;
+            let sub_block_bytes = symbols * sub_symbol_size;
+            if block_offset < sub_block_start + sub_block_bytes {
+                let esi = (block_offset - sub_block_start) / sub_symbol_size;
+                return Some(PayloadId::new(sbn as u8, esi as u32));
+            }
+            sub_block_start += sub_block_bytes;
+        }
+        unreachable!();
+    }
+
+    /// Number of zero bytes appended to the final source symbol, so that the object fills a whole
+    /// number of symbols. See the third to last paragraph in section 4.4.1.2.
+    ///
+    /// The decoder always truncates the reconstructed object to `transfer_length()`, so these bytes
+    /// are stripped regardless of whether the encoder transmitted them
+    /// (see [`EncoderBuilder::set_pad_last_symbol`](crate::EncoderBuilder::set_pad_last_symbol)).
+    pub fn padding_bytes(&self) -> u64 {
+        self.kt() * self.symbol_size as u64 - self.transfer_length
+    }
+
+    /// Maps a packet's (SBN, ESI) to a single sequence number that is unique across the object.
+    ///
+    /// Each source block is assigned the full 24-bit ESI space, so sequence numbers increase
+    /// monotonically with the SBN and then the ESI, and every valid ESI (including repair symbols)
+    /// has a sequence number.
+    pub fn global_sequence(&self, payload_id: &PayloadId) -> u64 {
+        assert!(payload_id.source_block_number() < self.num_source_blocks);
+        ((payload_id.source_block_number() as u64) << 24) + payload_id.encoding_symbol_id() as u64
+    }
+
+    /// Inverse of [`global_sequence`](Self::global_sequence).
+    pub fn payload_id_from_global(&self, seq: u64) -> PayloadId {
+        let source_block_number = seq >> 24;
+        assert!(source_block_number < self.num_source_blocks as u64);
+        PayloadId::new(source_block_number as u8, (seq & 0xFF_FFFF) as u32)
+    }
+
     pub(crate) fn generate_encoding_parameters(
         transfer_length: u64,
         max_packet_size: u16,
         decoder_memory_requirement: u64,
     ) -> ObjectTransmissionInformation {
+        match ObjectTransmissionInformation::try_generate_encoding_parameters(
+            transfer_length,
+            max_packet_size,
+            decoder_memory_requirement,
+        ) {
+            Ok(config) => config,
+            Err(error) => panic!("Invalid encoding parameters: {}", error),
+        }
+    }
+
+    pub(crate) fn try_generate_encoding_parameters(
+        transfer_length: u64,
+        max_packet_size: u16,
+        decoder_memory_requirement: u64,
+    ) -> Result<ObjectTransmissionInformation, ParameterError> {
         let alignment = 8;
-        assert!(max_packet_size >= alignment);
+        // Rounding down to the alignment would leave no room for a symbol
+        if max_packet_size < alignment {
+            return Err(ParameterError::ZeroSymbolSize);
+        }
         let symbol_size = max_packet_size - (max_packet_size % alignment);
+        ObjectTransmissionInformation::try_generate_blocks(
+            transfer_length,
+            symbol_size,
+            alignment as u8,
+            decoder_memory_requirement,
+        )
+    }
+
+    // Derives the number of source blocks and sub-blocks for the given symbol size.
+    // See section 4.3
+    fn try_generate_blocks(
+        transfer_length: u64,
+        symbol_size: u16,
+        alignment: u8,
+        decoder_memory_requirement: u64,
+    ) -> Result<ObjectTransmissionInformation, ParameterError> {
+        if alignment == 0 || symbol_size == 0 || symbol_size % alignment as u16 != 0 {
+            return Err(ParameterError::InvalidAlignment {
+                symbol_size,
+                alignment,
+            });
+        }
+        let alignment = alignment as u16;
         let sub_symbol_size = 8;
 
         let kt = (transfer_length as f64 / symbol_size as f64).ceil();
-        let n_max = (symbol_size as f64 / (sub_symbol_size * alignment) as f64).floor() as u32;
+        // Symbols smaller than a sub-symbol can't be split, so allow at least one sub-block
+        let n_max =
+            ((symbol_size as f64 / (sub_symbol_size * alignment) as f64).floor() as u32).max(1);
 
         let kl = |n: u32| -> u32 {
             for &(kprime, _, _, _, _) in SYSTEMATIC_INDICES_AND_PARAMETERS.iter().rev() {
@@ -205,7 +809,10 @@ impl ObjectTransmissionInformation {
             unreachable!();
         };
 
-        let num_source_blocks = (kt / kl(n_max) as f64).ceil() as u32;
+        let num_source_blocks = (kt / kl(n_max) as f64).ceil() as u64;
+        if num_source_blocks > MAX_SOURCE_BLOCKS as u64 {
+            return Err(ParameterError::TooManySourceBlocks(num_source_blocks));
+        }
 
         let mut n = 1;
         for i in 1..=n_max {
@@ -215,13 +822,94 @@ impl ObjectTransmissionInformation {
             }
         }
 
-        ObjectTransmissionInformation {
+        Ok(ObjectTransmissionInformation {
             transfer_length,
             symbol_size,
             num_source_blocks: num_source_blocks as u8,
             num_sub_blocks: n as u16,
             symbol_alignment: alignment as u8,
+        })
+    }
+
+    /// Like [`try_with_defaults`](Self::try_with_defaults), but uses exactly the given
+    /// `symbol_size`, rather than deriving it from a maximum packet size. The number of source
+    /// blocks and sub-blocks is derived for the same 10MB decoder memory budget.
+    ///
+    /// Returns an error if `symbol_size` is not a non-zero multiple of `alignment`, or if the
+    /// object needs more than 255 source blocks to respect the limit on source symbols per block.
+    pub fn with_symbol_size(
+        transfer_length: u64,
+        symbol_size: u16,
+        alignment: u8,
+    ) -> Result<ObjectTransmissionInformation, ParameterError> {
+        ObjectTransmissionInformation::try_generate_blocks(
+            transfer_length,
+            symbol_size,
+            alignment,
+            10 * 1024 * 1024,
+        )
+    }
+
+    /// Derives the symbol size so that the object spans approximately `target_packets` source
+    /// symbols, for distribution schemes which send a fixed number of packets per object. The
+    /// number of source blocks and sub-blocks is then derived as in
+    /// [`with_symbol_size`](Self::with_symbol_size).
+    ///
+    /// The symbol size is rounded up to a multiple of `alignment`, so the object may span fewer
+    /// than `target_packets` symbols, but never more. Returns an error if `target_packets` is zero,
+    /// if that symbol size exceeds the 16-bit symbol size field, or under the same conditions as
+    /// [`with_symbol_size`](Self::with_symbol_size).
+    pub fn for_packet_count(
+        transfer_length: u64,
+        target_packets: u32,
+        alignment: u8,
+    ) -> Result<ObjectTransmissionInformation, ParameterError> {
+        if target_packets == 0 {
+            return Err(ParameterError::ZeroLimit);
         }
+        if alignment == 0 {
+            return Err(ParameterError::InvalidAlignment {
+                symbol_size: 0,
+                alignment,
+            });
+        }
+        let alignment_bytes = alignment as u64;
+        let target_packets = target_packets as u64;
+        let symbol_size = (transfer_length + target_packets - 1) / target_packets;
+        let symbol_size = ((symbol_size + alignment_bytes - 1) / alignment_bytes * alignment_bytes)
+            .max(alignment_bytes);
+        if symbol_size > u16::MAX as u64 {
+            return Err(ParameterError::SymbolSizeTooLarge(symbol_size));
+        }
+        let config = ObjectTransmissionInformation::with_symbol_size(
+            transfer_length,
+            symbol_size as u16,
+            alignment,
+        )?;
+        validate_esi_space(
+            transfer_length,
+            config.symbol_size,
+            config.num_source_blocks,
+        )?;
+        Ok(config)
+    }
+
+    /// Derives the number of source blocks and sub-blocks that the same object would need with
+    /// `new_symbol_size` byte symbols, keeping the transfer length and symbol alignment, as in
+    /// [`with_symbol_size`](Self::with_symbol_size). Useful for comparing configurations.
+    ///
+    /// The decoder memory requirement used to derive this OTI isn't recorded in it, so the blocks
+    /// are derived for the default 10MB. Returns an error if `new_symbol_size` is not a non-zero
+    /// multiple of the alignment, or if the object would need more than 255 source blocks.
+    pub fn repartition(
+        &self,
+        new_symbol_size: u16,
+    ) -> Result<ObjectTransmissionInformation, ParameterError> {
+        ObjectTransmissionInformation::with_symbol_size(
+            self.transfer_length,
+            new_symbol_size,
+            self.symbol_alignment,
+        )
     }
 
     pub fn with_defaults(
@@ -234,6 +922,121 @@ impl ObjectTransmissionInformation {
             10 * 1024 * 1024,
         )
     }
+
+    /// Fallible version of [`with_defaults`](Self::with_defaults), which returns an error instead
+    /// of panicking when the object cannot be split into at most 255 source blocks, or when
+    /// `max_packet_size` is smaller than the 8 byte symbol alignment.
+    pub fn try_with_defaults(
+        transfer_length: u64,
+        max_packet_size: u16,
+    ) -> Result<ObjectTransmissionInformation, ParameterError> {
+        ObjectTransmissionInformation::try_generate_encoding_parameters(
+            transfer_length,
+            max_packet_size,
+            10 * 1024 * 1024,
+        )
+    }
+
+    /// Like [`try_with_defaults`](Self::try_with_defaults), but splits the object into at most
+    /// `max_source_blocks` source blocks, for deployments which bound the source block number,
+    /// or route each block separately.
+    ///
+    /// If the parameters derived from `max_packet_size` need more blocks than that, the symbol
+    /// size is increased, to the smallest multiple of the alignment which needs few enough blocks.
+    /// Each block then holds more data, so the decoder needs more memory per block, and symbols
+    /// no longer fit in `max_packet_size` byte packets. Returns an error if `max_source_blocks` is
+    /// zero, if no symbol size which fits in the 16-bit symbol size field needs few enough blocks,
+    /// or if the result leaves too little of the ESI space for repair symbols (see
+    /// [`validate_esi_space`]).
+    pub fn with_max_source_blocks(
+        transfer_length: u64,
+        max_packet_size: u16,
+        max_source_blocks: u8,
+    ) -> Result<ObjectTransmissionInformation, ParameterError> {
+        if max_source_blocks == 0 {
+            return Err(ParameterError::ZeroLimit);
+        }
+        let fits = |result: &Result<ObjectTransmissionInformation, ParameterError>| match result {
+            Ok(config) => config.num_source_blocks <= max_source_blocks,
+            Err(_) => false,
+        };
+        let mut config =
+            ObjectTransmissionInformation::try_with_defaults(transfer_length, max_packet_size);
+        if let Err(ParameterError::ZeroSymbolSize) = config {
+            return config;
+        }
+        if !fits(&config) {
+            let alignment = 8;
+            let generate = |symbol_size: u16| {
+                ObjectTransmissionInformation::try_generate_blocks(
+                    transfer_length,
+                    symbol_size,
+                    alignment as u8,
+                    10 * 1024 * 1024,
+                )
+            };
+            // Larger symbols need fewer blocks, so search for the smallest symbol size which fits
+            let mut low = (max_packet_size - (max_packet_size % alignment)) / alignment;
+            let mut high = u16::MAX / alignment;
+            config = generate(high * alignment);
+            if !fits(&config) {
+                return match config {
+                    Ok(config) => Err(ParameterError::TooManySourceBlocks(
+                        config.num_source_blocks as u64,
+                    )),
+                    Err(error) => Err(error),
+                };
+            }
+            while high - low > 1 {
+                let middle = low + (high - low) / 2;
+                let candidate = generate(middle * alignment);
+                if fits(&candidate) {
+                    high = middle;
+                    config = candidate;
+                } else {
+                    low = middle;
+                }
+            }
+        }
+        let config = config?;
+        validate_esi_space(
+            transfer_length,
+            config.symbol_size,
+            config.num_source_blocks,
+        )?;
+        Ok(config)
+    }
+}
+
+/// Checks that an object of `transfer_length` bytes, split into `num_blocks` source blocks of
+/// `symbol_size` byte symbols, leaves room in the 24-bit ESI space of each block for its source
+/// symbols and a reasonable number of repair symbols.
+///
+/// A block's repair symbols use the ESIs from K' upwards, so a tiny symbol size, which makes K
+/// very large, exhausts the ESI space. Blocks are limited to K'_max = 56403 source symbols (see
+/// [section 5.1.2](https://tools.ietf.org/html/rfc6330#section-5.1.2)), which bounds K' and leaves
+/// more than 16 million ESIs for repair symbols. This is checked up front, rather than failing
+/// once the encoder is constructed or high ESI repair symbols are generated. A zero symbol size or
+/// number of blocks is also an error.
+pub fn validate_esi_space(
+    transfer_length: u64,
+    symbol_size: u16,
+    num_blocks: u8,
+) -> Result<(), ParameterError> {
+    if symbol_size == 0 {
+        return Err(ParameterError::ZeroSymbolSize);
+    }
+    if num_blocks == 0 {
+        return Err(ParameterError::ZeroLimit);
+    }
+    let symbol_size = symbol_size as u64;
+    let kt = (transfer_length + symbol_size - 1) / symbol_size;
+    // The first blocks of the partition are the largest
+    let largest_block = (kt + num_blocks as u64 - 1) / num_blocks as u64;
+    if largest_block > MAX_SOURCE_SYMBOLS_PER_BLOCK as u64 {
+        return Err(ParameterError::TooManySourceSymbols(largest_block));
+    }
+    Ok(())
 }
 
 // Partition[I, J] function, as defined in section 4.4.1.2
@@ -307,12 +1110,248 @@ pub fn intermediate_tuple(
 
 #[cfg(test)]
 mod tests {
-    use crate::{EncodingPacket, ObjectTransmissionInformation, PayloadId};
+    use crate::base::{ParameterError, ParseError};
+    use crate::systematic_constants::MAX_SOURCE_SYMBOLS_PER_BLOCK;
+    use crate::{pack_packets, unpack_packets, validate_esi_space};
+    use crate::{partition, EncodingPacket, ObjectTransmissionInformation, PayloadId};
+    use crate::{Encoder, OtiField, MAX_SOURCE_BLOCKS, MAX_TRANSFER_LENGTH};
     use rand::Rng;
 
+    #[test]
+    fn sub_block_layout() {
+        // T/Al = 256, so (TL, TS, NL, NS) = Partition[256, 3] = (86, 85, 1, 2)
+        let oti = ObjectTransmissionInformation::new(100_000, 1024, 1, 3, 4);
+        let layout = oti.sub_block_layout();
+        let sizes: Vec<(u16, u16)> = layout
+            .iter()
+            .map(|info| (info.sub_symbol_size(), info.symbol_offset()))
+            .collect();
+        assert_eq!(sizes, vec![(344, 0), (340, 344), (340, 684)]);
+
+        let single = ObjectTransmissionInformation::new(100_000, 1024, 1, 1, 4);
+        let layout = single.sub_block_layout();
+        assert_eq!(layout.len(), 1);
+        assert_eq!(layout[0].sub_symbol_size(), 1024);
+        assert_eq!(layout[0].symbol_offset(), 0);
+
+        // T/Al = 8 doesn't divide evenly into 5 sub-blocks: Partition[8, 5] = (2, 1, 3, 2)
+        let uneven = ObjectTransmissionInformation::new(10_000, 64, 1, 5, 8);
+        let sizes: Vec<u16> = uneven
+            .sub_block_layout()
+            .iter()
+            .map(|info| info.sub_symbol_size())
+            .collect();
+        assert_eq!(sizes, vec![16, 16, 16, 8, 8]);
+    }
+
+    #[test]
+    fn symbol_byte_ranges() {
+        // Kt = 10, so block 0 has 4 symbols, and blocks 1 and 2 have 3
+        let oti = ObjectTransmissionInformation::new(9 * 64 + 10, 64, 3, 1, 8);
+        assert_eq!(oti.symbol_byte_range(0, 0), Some(0..64));
+        assert_eq!(oti.symbol_byte_range(0, 3), Some(192..256));
+        assert_eq!(oti.symbol_byte_range(0, 4), None);
+        assert_eq!(oti.symbol_byte_range(1, 0), Some(256..320));
+        assert_eq!(oti.symbol_byte_range(1, 3), None);
+        assert_eq!(oti.symbol_byte_range(2, 0), Some(448..512));
+        // Final partial symbol
+        assert_eq!(oti.symbol_byte_range(2, 2), Some(576..586));
+        assert_eq!(oti.symbol_byte_range(2, 3), None);
+        assert_eq!(oti.symbol_byte_range(3, 0), None);
+
+        let sub_blocked = ObjectTransmissionInformation::new(9 * 64 + 10, 64, 3, 2, 8);
+        assert_eq!(sub_blocked.symbol_byte_range(0, 0), None);
+    }
+
+    #[test]
+    fn symbol_at_offset() {
+        // Kt = 10, so block 0 has 4 symbols, and blocks 1 and 2 have 3
+        let oti = ObjectTransmissionInformation::new(9 * 64 + 10, 64, 3, 1, 8);
+        assert_eq!(oti.symbol_at_offset(0), Some(PayloadId::new(0, 0)));
+        assert_eq!(oti.symbol_at_offset(63), Some(PayloadId::new(0, 0)));
+        assert_eq!(oti.symbol_at_offset(64), Some(PayloadId::new(0, 1)));
+        assert_eq!(oti.symbol_at_offset(255), Some(PayloadId::new(0, 3)));
+        assert_eq!(oti.symbol_at_offset(256), Some(PayloadId::new(1, 0)));
+        assert_eq!(oti.symbol_at_offset(447), Some(PayloadId::new(1, 2)));
+        assert_eq!(oti.symbol_at_offset(448), Some(PayloadId::new(2, 0)));
+        // Final partial symbol
+        assert_eq!(oti.symbol_at_offset(576), Some(PayloadId::new(2, 2)));
+        assert_eq!(oti.symbol_at_offset(585), Some(PayloadId::new(2, 2)));
+        assert_eq!(oti.symbol_at_offset(586), None);
+        for offset in 0..586 {
+            let payload_id = oti.symbol_at_offset(offset).unwrap();
+            let range = oti
+                .symbol_byte_range(
+                    payload_id.source_block_number(),
+                    payload_id.encoding_symbol_id(),
+                )
+                .unwrap();
+            assert!(range.contains(&offset));
+        }
+
+        // Two sub-blocks of 32 byte sub-symbols: block 0 holds the first halves of its 4 symbols,
+        // then the second halves
+        let sub_blocked = ObjectTransmissionInformation::new(9 * 64 + 10, 64, 3, 2, 8);
+        assert_eq!(sub_blocked.symbol_at_offset(32), Some(PayloadId::new(0, 1)));
+        assert_eq!(
+            sub_blocked.symbol_at_offset(127),
+            Some(PayloadId::new(0, 3))
+        );
+        assert_eq!(
+            sub_blocked.symbol_at_offset(128),
+            Some(PayloadId::new(0, 0))
+        );
+        assert_eq!(
+            sub_blocked.symbol_at_offset(255),
+            Some(PayloadId::new(0, 3))
+        );
+        assert_eq!(
+            sub_blocked.symbol_at_offset(256),
+            Some(PayloadId::new(1, 0))
+        );
+    }
+
+    #[test]
+    fn oti_try_deserialize() {
+        let oti = ObjectTransmissionInformation::new(1_000_000, 1024, 4, 2, 8);
+        let data = oti.serialize();
+        assert_eq!(
+            ObjectTransmissionInformation::try_deserialize(&data),
+            Ok(oti)
+        );
+        assert_eq!(
+            ObjectTransmissionInformation::try_deserialize(&data[..11]),
+            Err(ParseError::InvalidLength {
+                expected: 12,
+                actual: 11
+            })
+        );
+
+        let mut reserved = data;
+        reserved[5] = 1;
+        assert_eq!(
+            ObjectTransmissionInformation::try_deserialize(&reserved),
+            Err(ParseError::NonZeroReserved(1))
+        );
+
+        let mut zero_alignment = data;
+        zero_alignment[11] = 0;
+        assert_eq!(
+            ObjectTransmissionInformation::try_deserialize(&zero_alignment),
+            Err(ParseError::ZeroAlignment)
+        );
+
+        let mut zero_sub_blocks = data;
+        zero_sub_blocks[9] = 0;
+        zero_sub_blocks[10] = 0;
+        assert_eq!(
+            ObjectTransmissionInformation::try_deserialize(&zero_sub_blocks),
+            Err(ParseError::ZeroSubBlocks)
+        );
+
+        let mut zero_source_blocks = data;
+        zero_source_blocks[8] = 0;
+        assert_eq!(
+            ObjectTransmissionInformation::try_deserialize(&zero_source_blocks),
+            Err(ParseError::ZeroSourceBlocks)
+        );
+
+        let mut misaligned = data;
+        misaligned[7] = 1;
+        assert_eq!(
+            ObjectTransmissionInformation::try_deserialize(&misaligned),
+            Err(ParseError::InvalidSymbolSize(1025))
+        );
+
+        // 1,000,000 bytes in 8 byte symbols is 31,250 symbols per block with 4 blocks, but
+        // 125,000 with 1
+        let small_symbols = ObjectTransmissionInformation::new(1_000_000, 8, 4, 1, 8);
+        let mut too_many_symbols = small_symbols.serialize();
+        assert_eq!(
+            ObjectTransmissionInformation::try_deserialize(&too_many_symbols),
+            Ok(small_symbols)
+        );
+        too_many_symbols[8] = 1;
+        assert_eq!(
+            ObjectTransmissionInformation::try_deserialize(&too_many_symbols),
+            Err(ParseError::TooManySourceSymbols(125_000))
+        );
+    }
+
+    #[test]
+    fn with_symbol_size() {
+        let oti = ObjectTransmissionInformation::with_symbol_size(1_000_000, 512, 8).unwrap();
+        assert_eq!(oti.symbol_size(), 512);
+        assert_eq!(oti.symbol_alignment(), 8);
+        assert_eq!(
+            oti,
+            ObjectTransmissionInformation::with_defaults(1_000_000, 512)
+        );
+
+        let small = ObjectTransmissionInformation::with_symbol_size(1_000, 4, 4).unwrap();
+        assert_eq!(small.symbol_size(), 4);
+        assert_eq!(small.sub_blocks(), 1);
+
+        for &(symbol_size, alignment) in [(512, 0), (0, 8), (500, 8)].iter() {
+            assert_eq!(
+                ObjectTransmissionInformation::with_symbol_size(1_000_000, symbol_size, alignment),
+                Err(ParameterError::InvalidAlignment {
+                    symbol_size,
+                    alignment
+                })
+            );
+        }
+        assert_eq!(
+            ObjectTransmissionInformation::with_symbol_size(942574504275, 1024, 8),
+            Err(ParameterError::TooManySourceBlocks(16320))
+        );
+    }
+
+    #[test]
+    fn oti_parts() {
+        let oti = ObjectTransmissionInformation::new(1_000_000, 1024, 4, 2, 8);
+        let common = oti.common_oti();
+        let scheme_specific = oti.scheme_specific_oti();
+        assert_eq!(common[..], oti.serialize()[..8]);
+        assert_eq!(scheme_specific[..], oti.serialize()[8..]);
+        assert_eq!(
+            ObjectTransmissionInformation::from_parts(&common, &scheme_specific),
+            oti
+        );
+    }
+
+    #[test]
+    fn partition_vectors() {
+        // (i, j, (il, is, jl, js))
+        let vectors = [
+            (0u32, 1u32, (0, 0, 0, 1)),
+            (1, 1, (1, 1, 0, 1)),
+            (10, 3, (4, 3, 1, 2)),
+            (10, 10, (1, 1, 0, 10)),
+        ];
+        for &(i, j, expected) in vectors.iter() {
+            let (il, is, jl, js) = partition(i, j);
+            assert_eq!((il, is, jl, js), expected, "partition({}, {})", i, j);
+            assert_eq!(jl * il + js * is, i);
+            assert_eq!(jl + js, j);
+        }
+    }
+
     #[test]
     fn max_transfer_size() {
         ObjectTransmissionInformation::new(942574504275, 65535, 255, 1, 1);
+        assert_eq!(
+            MAX_TRANSFER_LENGTH,
+            MAX_SOURCE_SYMBOLS_PER_BLOCK as u64 * MAX_SOURCE_BLOCKS as u64 * 65535
+        );
+    }
+
+    #[test]
+    fn payload_id_esi_bytes() {
+        let payload_id = PayloadId::new(7, 0x0A0B0C);
+        assert_eq!(payload_id.esi_bytes(), [0x0A, 0x0B, 0x0C]);
+        assert_eq!(PayloadId::from_esi_bytes(7, [0x0A, 0x0B, 0x0C]), payload_id);
+        assert_eq!(PayloadId::new(0, 16777215).esi_bytes(), [0xFF, 0xFF, 0xFF]);
     }
 
     #[test]
@@ -337,11 +1376,387 @@ mod tests {
     }
 
     #[test]
-    fn oti_serialization() {
-        let oti = ObjectTransmissionInformation::with_defaults(
-            rand::thread_rng().gen_range(0..(256 * 256 * 256 * 256 * 256)),
-            rand::thread_rng().gen(),
+    fn encoding_packet_parts() {
+        let packet = EncodingPacket::new(PayloadId::new(3, 1000), vec![1, 2, 3]);
+        let (payload_id, data) = packet.as_parts();
+        assert_eq!(payload_id, &PayloadId::new(3, 1000));
+        assert_eq!(data, &[1, 2, 3]);
+        assert_eq!(EncodingPacket::from_parts(payload_id.clone(), data), packet);
+    }
+
+    #[test]
+    fn packed_packets() {
+        let packets: Vec<EncodingPacket> = [0usize, 1, 10, 64, 3]
+            .iter()
+            .enumerate()
+            .map(|(i, &length)| {
+                let mut data = vec![0; length];
+                for element in &mut data {
+                    *element = rand::thread_rng().gen();
+                }
+                EncodingPacket::new(PayloadId::new(i as u8, 1000 * i as u32), data)
+            })
+            .collect();
+        let packed = pack_packets(&packets);
+        assert_eq!(packed.len(), 6 * packets.len() + 78);
+        assert_eq!(packed[..2], [0, 4]);
+        assert_eq!(unpack_packets(&packed), Ok(packets));
+        assert_eq!(unpack_packets(&[]), Ok(vec![]));
+
+        assert_eq!(
+            unpack_packets(&packed[..packed.len() - 1]),
+            Err(ParseError::InvalidLength {
+                expected: 7,
+                actual: 6
+            })
+        );
+        assert_eq!(
+            unpack_packets(&[0]),
+            Err(ParseError::InvalidLength {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(
+            unpack_packets(&[0, 3, 1, 2, 3]),
+            Err(ParseError::InvalidLength {
+                expected: 4,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn symbols_per_block() {
+        // Kt = 100, which splits into one block of 34 and two of 33
+        let oti = ObjectTransmissionInformation::new(1000, 10, 3, 1, 1);
+        assert_eq!(oti.symbols_in_block(0), 34);
+        assert_eq!(oti.symbols_in_block(1), 33);
+        assert_eq!(oti.symbols_in_block(2), 33);
+
+        // Kt = 101, with a partial final symbol
+        let oti = ObjectTransmissionInformation::new(1001, 10, 3, 1, 1);
+        assert_eq!(oti.symbols_in_block(0), 34);
+        assert_eq!(oti.symbols_in_block(1), 34);
+        assert_eq!(oti.symbols_in_block(2), 33);
+
+        let oti = ObjectTransmissionInformation::new(1000, 10, 1, 1, 1);
+        assert_eq!(oti.symbols_in_block(0), 100);
+    }
+
+    #[test]
+    fn esi_space() {
+        assert_eq!(validate_esi_space(1_000_000, 1024, 1), Ok(()));
+        assert_eq!(validate_esi_space(0, 1, 1), Ok(()));
+        assert_eq!(validate_esi_space(56403, 1, 1), Ok(()));
+        assert_eq!(
+            validate_esi_space(56404, 1, 1),
+            Err(ParameterError::TooManySourceSymbols(56404))
+        );
+        // One byte symbols for a 100MB object would need over 6 times the 2^24 ESI space
+        assert_eq!(
+            validate_esi_space(100 * 1024 * 1024, 1, 1),
+            Err(ParameterError::TooManySourceSymbols(104857600))
+        );
+        assert_eq!(
+            validate_esi_space(100 * 1024 * 1024, 1, 255),
+            Err(ParameterError::TooManySourceSymbols(411207))
+        );
+        assert_eq!(validate_esi_space(100 * 1024 * 1024, 8, 255), Ok(()));
+        assert_eq!(
+            validate_esi_space(1000, 0, 1),
+            Err(ParameterError::ZeroSymbolSize)
+        );
+        assert_eq!(
+            validate_esi_space(1000, 64, 0),
+            Err(ParameterError::ZeroLimit)
+        );
+    }
+
+    #[test]
+    fn kt() {
+        assert_eq!(
+            ObjectTransmissionInformation::new(1000, 100, 1, 1, 4).kt(),
+            10
+        );
+        assert_eq!(
+            ObjectTransmissionInformation::new(1001, 100, 1, 1, 4).kt(),
+            11
+        );
+        assert_eq!(
+            ObjectTransmissionInformation::new(999, 100, 1, 1, 4).kt(),
+            10
+        );
+        assert_eq!(ObjectTransmissionInformation::new(1, 100, 1, 1, 4).kt(), 1);
+        assert_eq!(ObjectTransmissionInformation::new(0, 100, 1, 1, 4).kt(), 0);
+    }
+
+    #[test]
+    fn source_symbol_ids() {
+        // K = 7 and 6
+        let config = ObjectTransmissionInformation::new(1300, 100, 2, 1, 4);
+        let ids: Vec<PayloadId> = config.source_symbol_ids().collect();
+        assert_eq!(ids.len() as u64, config.kt());
+        assert_eq!(ids.len(), 13);
+        assert_eq!(ids[0], PayloadId::new(0, 0));
+        assert_eq!(ids[6], PayloadId::new(0, 6));
+        assert_eq!(ids[7], PayloadId::new(1, 0));
+        assert_eq!(ids[12], PayloadId::new(1, 5));
+
+        let config = ObjectTransmissionInformation::new(1_000_000, 1000, 3, 1, 8);
+        assert_eq!(config.kt(), 1000);
+        assert_eq!(config.source_symbol_ids().count(), 1000);
+    }
+
+    #[test]
+    fn serialized_transfer_size() {
+        // K = 7 and 6, plus 3 repair symbols each
+        let config = ObjectTransmissionInformation::new(1300, 100, 2, 1, 4);
+        assert_eq!(config.serialized_transfer_size(3), (10 + 9) * 104);
+        assert_eq!(config.serialized_transfer_size(0), 13 * 104);
+
+        for &(transfer_length, symbol_size, blocks) in
+            [(1, 8, 1), (10_000, 64, 3), (6400, 64, 1)].iter()
+        {
+            let config =
+                ObjectTransmissionInformation::new(transfer_length, symbol_size, blocks, 1, 8);
+            let data = vec![0; transfer_length as usize];
+            let serialized: usize = Encoder::new(&data, config)
+                .get_encoded_packets(5)
+                .iter()
+                .map(|packet| packet.serialize().len())
+                .sum();
+            assert_eq!(config.serialized_transfer_size(5), serialized as u64);
+        }
+    }
+
+    #[test]
+    fn total_intermediate_symbols() {
+        // K = 13, so K' = 18, S = 11 and H = 10
+        let single = ObjectTransmissionInformation::new(1300, 100, 1, 1, 4);
+        assert_eq!(single.total_intermediate_symbols(), 18 + 11 + 10);
+
+        // K = 7 and 6, both of which extend to K' = 10, with S = 7 and H = 10
+        let split = ObjectTransmissionInformation::new(1300, 100, 2, 1, 4);
+        assert_eq!(split.total_intermediate_symbols(), 2 * (10 + 7 + 10));
+    }
+
+    #[test]
+    fn with_max_source_blocks() {
+        let transfer_length = 1024 * 1024 * 1024;
+        let defaults =
+            ObjectTransmissionInformation::try_with_defaults(transfer_length, 1024).unwrap();
+        assert_eq!(defaults.source_blocks(), 19);
+        assert_eq!(
+            ObjectTransmissionInformation::with_max_source_blocks(transfer_length, 1024, 20),
+            Ok(defaults)
+        );
+
+        let capped =
+            ObjectTransmissionInformation::with_max_source_blocks(transfer_length, 1024, 4)
+                .unwrap();
+        assert!(capped.source_blocks() <= 4);
+        assert!(capped.symbol_size() > 1024);
+        assert_eq!(capped.symbol_size() % 8, 0);
+        // The symbol size is the smallest which needs few enough blocks
+        let smaller = ObjectTransmissionInformation::with_symbol_size(
+            transfer_length,
+            capped.symbol_size() - 8,
+            8,
+        )
+        .unwrap();
+        assert!(smaller.source_blocks() > 4);
+
+        // Even 65528 byte symbols need 3 blocks for 10GB
+        assert_eq!(
+            ObjectTransmissionInformation::with_max_source_blocks(10 * transfer_length, 1024, 1),
+            Err(ParameterError::TooManySourceBlocks(3))
+        );
+        assert_eq!(
+            ObjectTransmissionInformation::with_max_source_blocks(transfer_length, 1024, 0),
+            Err(ParameterError::ZeroLimit)
+        );
+    }
+
+    #[test]
+    fn for_packet_count() {
+        let exact = ObjectTransmissionInformation::for_packet_count(1_000_000, 1000, 8).unwrap();
+        assert_eq!(exact.symbol_size(), 1000);
+        assert_eq!(exact.source_blocks(), 1);
+
+        // 1001 bytes per packet are rounded up to 1008, so 993 symbols are needed
+        let rounded = ObjectTransmissionInformation::for_packet_count(1_000_001, 1000, 8).unwrap();
+        assert_eq!(rounded.symbol_size(), 1008);
+        let symbols = (rounded.transfer_length() + 1007) / 1008;
+        assert_eq!(symbols, 993);
+
+        let tiny = ObjectTransmissionInformation::for_packet_count(10, 100, 4).unwrap();
+        assert_eq!(tiny.symbol_size(), 4);
+
+        assert_eq!(
+            ObjectTransmissionInformation::for_packet_count(100 * 1024 * 1024, 10, 8),
+            Err(ParameterError::SymbolSizeTooLarge(10485760))
+        );
+        assert_eq!(
+            ObjectTransmissionInformation::for_packet_count(1000, 10, 0),
+            Err(ParameterError::InvalidAlignment {
+                symbol_size: 0,
+                alignment: 0
+            })
+        );
+        assert_eq!(
+            ObjectTransmissionInformation::for_packet_count(1000, 0, 8),
+            Err(ParameterError::ZeroLimit)
+        );
+    }
+
+    #[test]
+    fn oti_diff() {
+        let config = ObjectTransmissionInformation::new(1_000_000, 1024, 4, 2, 8);
+        assert!(config.is_compatible_with(&config));
+        assert!(config.diff(&config).is_empty());
+
+        let differing = [
+            (
+                ObjectTransmissionInformation::new(1_000_001, 1024, 4, 2, 8),
+                OtiField::TransferLength,
+            ),
+            (
+                ObjectTransmissionInformation::new(1_000_000, 1280, 4, 2, 8),
+                OtiField::SymbolSize,
+            ),
+            (
+                ObjectTransmissionInformation::new(1_000_000, 1024, 5, 2, 8),
+                OtiField::SourceBlocks,
+            ),
+            (
+                ObjectTransmissionInformation::new(1_000_000, 1024, 4, 1, 8),
+                OtiField::SubBlocks,
+            ),
+            (
+                ObjectTransmissionInformation::new(1_000_000, 1024, 4, 2, 4),
+                OtiField::SymbolAlignment,
+            ),
+        ];
+        for &(other, field) in differing.iter() {
+            assert!(!config.is_compatible_with(&other));
+            assert!(!other.is_compatible_with(&config));
+            assert_eq!(config.diff(&other), vec![field]);
+        }
+
+        let other = ObjectTransmissionInformation::new(2_000_000, 1024, 4, 1, 8);
+        assert_eq!(
+            config.diff(&other),
+            vec![OtiField::TransferLength, OtiField::SubBlocks]
+        );
+    }
+
+    #[test]
+    fn repartition() {
+        let config = ObjectTransmissionInformation::with_defaults(100 * 1024 * 1024, 1280);
+        let smaller = config.repartition(640).unwrap();
+        assert_eq!(
+            smaller,
+            ObjectTransmissionInformation::with_symbol_size(100 * 1024 * 1024, 640, 8).unwrap()
+        );
+        assert_eq!(smaller.transfer_length(), config.transfer_length());
+        assert_eq!(smaller.symbol_alignment(), 8);
+        assert!(smaller.source_blocks() > config.source_blocks());
+        assert_eq!(config.repartition(1280), Ok(config));
+
+        assert_eq!(
+            config.repartition(100),
+            Err(ParameterError::InvalidAlignment {
+                symbol_size: 100,
+                alignment: 8
+            })
+        );
+        // 8 byte symbols allow at most 56403 * 8 bytes per block
+        let large = ObjectTransmissionInformation::with_defaults(1024 * 1024 * 1024, 1280);
+        assert_eq!(
+            large.repartition(8),
+            Err(ParameterError::TooManySourceBlocks(2380))
+        );
+    }
+
+    #[test]
+    fn too_many_source_blocks() {
+        // 64 byte symbols allow at most 56403 symbols per block, so this needs 257 blocks
+        let transfer_length = 64 * 56403 * 256 + 1;
+        assert_eq!(
+            ObjectTransmissionInformation::try_with_defaults(transfer_length, 64),
+            Err(ParameterError::TooManySourceBlocks(257))
         );
+        // A small decoder memory requirement forces small blocks
+        assert!(matches!(
+            ObjectTransmissionInformation::try_generate_encoding_parameters(
+                1024 * 1024 * 1024,
+                1024,
+                16 * 1024
+            ),
+            Err(ParameterError::TooManySourceBlocks(_))
+        ));
+
+        let config = ObjectTransmissionInformation::try_with_defaults(64 * 56403 * 255, 64);
+        assert_eq!(config.unwrap().source_blocks(), 255);
+    }
+
+    #[test]
+    fn packet_size_below_alignment() {
+        for max_packet_size in 0..8 {
+            assert_eq!(
+                ObjectTransmissionInformation::try_with_defaults(1_000, max_packet_size),
+                Err(ParameterError::ZeroSymbolSize)
+            );
+            assert_eq!(
+                ObjectTransmissionInformation::with_max_source_blocks(1_000, max_packet_size, 1),
+                Err(ParameterError::ZeroSymbolSize)
+            );
+        }
+        let config = ObjectTransmissionInformation::try_with_defaults(1_000, 8).unwrap();
+        assert_eq!(config.symbol_size(), 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn with_defaults_too_many_source_blocks() {
+        ObjectTransmissionInformation::with_defaults(64 * 56403 * 256 + 1, 64);
+    }
+
+    #[test]
+    fn global_sequence_round_trip() {
+        let oti = ObjectTransmissionInformation::new(1_000_000, 1000, 3, 1, 8);
+        let mut previous = None;
+        for source_block_number in 0..3 {
+            for &esi in [0, 1, 332, 333, 334, 16777215].iter() {
+                let payload_id = PayloadId::new(source_block_number, esi);
+                let seq = oti.global_sequence(&payload_id);
+                assert_eq!(oti.payload_id_from_global(seq), payload_id);
+                if let Some(previous) = previous {
+                    assert!(seq > previous);
+                }
+                previous = Some(seq);
+            }
+        }
+        assert_eq!(
+            oti.global_sequence(&PayloadId::new(1, 0)),
+            oti.global_sequence(&PayloadId::new(0, 16777215)) + 1
+        );
+    }
+
+    #[test]
+    fn oti_serialization() {
+        // Large objects with small packets can require too many source blocks, so retry until the
+        // random parameters are valid
+        let oti = loop {
+            let result = ObjectTransmissionInformation::try_with_defaults(
+                rand::thread_rng().gen_range(0..(256 * 256 * 256 * 256 * 256)),
+                rand::thread_rng().gen_range(64..=u16::MAX),
+            );
+            if let Ok(oti) = result {
+                break oti;
+            }
+        };
         let deserialized = ObjectTransmissionInformation::deserialize(&oti.serialize());
         assert_eq!(deserialized, oti);
     }
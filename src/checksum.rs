@@ -0,0 +1,102 @@
+/// Incrementally computes a digest over a byte stream, such as an object as it is reconstructed.
+///
+/// Implement this to plug in other hash functions (e.g. SHA-256).
+pub trait ObjectHasher {
+    type Output;
+
+    /// Feeds the next bytes of the stream into the hasher.
+    fn update(&mut self, data: &[u8]);
+
+    /// Consumes the hasher, returning the digest of all bytes fed to it.
+    fn finish(self) -> Self::Output;
+}
+
+/// An [`ObjectHasher`] whose digest can be embedded in a
+/// [`TransferManifest`](crate::TransferManifest), so that receivers can verify the reconstructed
+/// object.
+pub trait TransferHasher: ObjectHasher {
+    /// Encodes the digest as bytes. At most 255 bytes are supported.
+    fn digest_bytes(digest: Self::Output) -> Vec<u8>;
+}
+
+// Reversed form of the IEEE 802.3 polynomial
+const CRC32_POLYNOMIAL: u32 = 0xEDB8_8320;
+
+static CRC32_TABLE: [u32; 256] = calculate_crc32_table();
+
+const fn calculate_crc32_table() -> [u32; 256] {
+    let mut result = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            if crc & 1 == 1 {
+                crc = (crc >> 1) ^ CRC32_POLYNOMIAL;
+            } else {
+                crc >>= 1;
+            }
+            bit += 1;
+        }
+        result[i] = crc;
+        i += 1;
+    }
+    return result;
+}
+
+/// CRC-32 (IEEE 802.3), as used by zlib, gzip & PNG.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Crc32 {
+    state: u32,
+}
+
+impl Crc32 {
+    pub fn new() -> Crc32 {
+        Crc32 { state: 0xFFFF_FFFF }
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Crc32 {
+        Crc32::new()
+    }
+}
+
+impl ObjectHasher for Crc32 {
+    type Output = u32;
+
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.state =
+                CRC32_TABLE[((self.state ^ byte as u32) & 0xFF) as usize] ^ (self.state >> 8);
+        }
+    }
+
+    fn finish(self) -> u32 {
+        !self.state
+    }
+}
+
+impl TransferHasher for Crc32 {
+    fn digest_bytes(digest: u32) -> Vec<u8> {
+        digest.to_be_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checksum::{Crc32, ObjectHasher, TransferHasher};
+
+    #[test]
+    fn crc32_check_value() {
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+        assert_eq!(Crc32::new().finish(), 0);
+        assert_eq!(
+            Crc32::digest_bytes(0xCBF4_3926),
+            vec![0xCB, 0xF4, 0x39, 0x26]
+        );
+    }
+}
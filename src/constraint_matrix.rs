@@ -99,7 +99,7 @@ fn generate_hdpc_rows(Kprime: usize, S: usize, H: usize) -> DenseOctetMatrix {
 
     // I_H
     for i in 0..H {
-        matrix.set(i, i + (Kprime + S) as usize, Octet::one());
+        matrix.set(i, i + (Kprime + S), Octet::one());
     }
 
     matrix
@@ -141,7 +141,7 @@ pub fn generate_constraint_matrix<T: BinaryMatrix>(
 
     // I_S
     for i in 0..S {
-        matrix.set(i as usize, i + B as usize, Octet::one());
+        matrix.set(i, i + B, Octet::one());
     }
 
     // G_LDPC,2
@@ -161,7 +161,7 @@ pub fn generate_constraint_matrix<T: BinaryMatrix>(
         let tuple = intermediate_tuple(i, lt_symbols, sys_index, p1);
 
         for j in enc_indices(tuple, lt_symbols, pi_symbols, p1) {
-            matrix.set(row as usize + S + H, j, Octet::one());
+            matrix.set(row + S + H, j, Octet::one());
         }
     }
 
@@ -243,7 +243,7 @@ mod tests {
 
         // I_H
         for i in 0..H {
-            matrix.set(i, i + (Kprime + S) as usize, Octet::one());
+            matrix.set(i, i + (Kprime + S), Octet::one());
         }
 
         matrix
@@ -0,0 +1,280 @@
+//! Diagnostic tools for analyzing the code, enabled by the `debug-tools` feature.
+
+use crate::base::deg;
+use crate::base::intermediate_tuple;
+use crate::base::EncodingPacket;
+use crate::decoder::Decoder;
+use crate::encoder::Encoder;
+use crate::systematic_constants::{
+    calculate_p1, extended_source_block_symbols, num_lt_symbols, systematic_index,
+};
+use std::time::Instant;
+
+/// Computes a histogram of the LT degrees, d, of the first `num_symbols` encoding symbols (ESIs
+/// 0, 1, ...) of a source block with `source_block_symbols` source symbols. The padding ESIs
+/// between K and K' are skipped, since they are never sent. `result[d]` is the number of symbols
+/// of degree d, which ranges from 1 to 30.
+///
+/// The degrees should follow the distribution of the table in
+/// [section 5.3.5.2](https://tools.ietf.org/html/rfc6330#section-5.3.5.2), truncated to W - 2.
+pub fn degree_histogram(source_block_symbols: u32, num_symbols: u32) -> Vec<u32> {
+    let padding = extended_source_block_symbols(source_block_symbols) - source_block_symbols;
+    let lt_symbols = num_lt_symbols(source_block_symbols);
+    let sys_index = systematic_index(source_block_symbols);
+    let p1 = calculate_p1(source_block_symbols);
+    let mut histogram = vec![0; 31];
+    for esi in 0..num_symbols {
+        // Convert the ESI to an ISI, as described in section 5.3.1
+        let internal_symbol_id = if esi < source_block_symbols {
+            esi
+        } else {
+            esi + padding
+        };
+        let (d, _, _, _, _, _) = intermediate_tuple(internal_symbol_id, lt_symbols, sys_index, p1);
+        histogram[d as usize] += 1;
+    }
+    histogram
+}
+
+/// Computes the mean LT degree, d, of the encoding symbols of a source block with
+/// `source_block_symbols` source symbols, over the whole ESI space.
+///
+/// The degree is determined by the 20-bit value v of the tuple generator
+/// ([section 5.3.5.4](https://tools.ietf.org/html/rfc6330#section-5.3.5.4)), which is uniformly
+/// distributed over the ESI space, so this averages the degree of every possible v, rather than
+/// sampling ESIs as [`degree_histogram`] does. A higher mean degree makes the constraint matrix
+/// denser, and so both encoding and decoding slower. For large blocks it is about 4.8; smaller
+/// blocks have a lower mean, since the degree is truncated to W - 2.
+pub fn expected_degree(source_block_symbols: u32) -> f64 {
+    let lt_symbols = num_lt_symbols(source_block_symbols);
+    let total: u64 = (0..1048576).map(|v| deg(v, lt_symbols) as u64).sum();
+    total as f64 / 1048576.0
+}
+
+/// An encoding packet, with the time at which it was generated, for measuring the latency
+/// between encoding a packet and sending it. The timestamp is not part of the wire format, so
+/// send [`packet`](Self::packet) as usual.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimedPacket {
+    packet: EncodingPacket,
+    generated_at: u64,
+}
+
+impl TimedPacket {
+    pub fn packet(&self) -> &EncodingPacket {
+        &self.packet
+    }
+
+    /// Nanoseconds between the creation of the [`PacketTimer`] which stamped this packet, and the
+    /// generation of the packet
+    pub fn generated_at(&self) -> u64 {
+        self.generated_at
+    }
+
+    pub fn into_packet(self) -> EncodingPacket {
+        self.packet
+    }
+}
+
+/// Stamps packets with a monotonic timestamp, in nanoseconds since the timer was created.
+///
+/// To time each packet individually, stamp the packets as they are generated lazily, e.g. by
+/// [`SourceBlockEncoder::symbols_in_range`](crate::SourceBlockEncoder::symbols_in_range), rather
+/// than after generating a whole batch:
+///
+/// ```ignore
+/// let timer = PacketTimer::new();
+/// for packet in encoder.symbols_in_range(0..100).map(|packet| timer.stamp(packet)) {
+///     send(packet.packet());
+///     record_latency(timer.now() - packet.generated_at());
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PacketTimer {
+    epoch: Instant,
+}
+
+impl PacketTimer {
+    pub fn new() -> PacketTimer {
+        PacketTimer {
+            epoch: Instant::now(),
+        }
+    }
+
+    /// Nanoseconds elapsed since the timer was created
+    pub fn now(&self) -> u64 {
+        let elapsed = self.epoch.elapsed();
+        elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64
+    }
+
+    pub fn stamp(&self, packet: EncodingPacket) -> TimedPacket {
+        TimedPacket {
+            packet,
+            generated_at: self.now(),
+        }
+    }
+}
+
+impl Default for PacketTimer {
+    fn default() -> PacketTimer {
+        PacketTimer::new()
+    }
+}
+
+/// Whether the object of `encoder` decodes in each of `trials` simulated transfers, with
+/// `repair_packets` repair packets sent for every source block. See [`simulate_min_overhead`].
+pub fn decodes_with<F>(encoder: &Encoder, trials: u32, repair_packets: u32, channel: &mut F) -> bool
+where
+    F: FnMut(u32, u8, Vec<EncodingPacket>) -> Vec<EncodingPacket>,
+{
+    (0..trials).all(|trial| {
+        let mut decoder = Decoder::new(encoder.get_config());
+        let mut received = vec![];
+        for (block, block_encoder) in encoder.get_block_encoders().iter().enumerate() {
+            let mut packets = block_encoder.source_packets();
+            packets.extend(block_encoder.repair_packets(0, repair_packets));
+            received.extend(channel(trial, block as u8, packets));
+        }
+        decoder.add_packets(received).is_some()
+    })
+}
+
+/// Binary searches for the minimum number of repair packets per source block with which the
+/// object of `encoder` decoded in all of `trials` simulated transfers over a lossy channel, to
+/// calibrate the overhead to send for a network empirically.
+///
+/// `channel(trial, sbn, packets)` is given the packets sent for one source block, source symbols
+/// first and then repair symbols in ESI order, and returns those which arrive. The search assumes
+/// that sending more repair packets never makes a trial fail, which holds if the channel loses
+/// the same packets from the start of the list however long it is, for example by seeding a
+/// random number generator from the trial and SBN, and deciding the fate of each packet in turn.
+///
+/// The result is statistical: it is only the minimum for these particular trials, and more trials
+/// tend to find a higher value, which the object is more likely to decode with. Use it to
+/// calibrate the overhead, with some margin, not as a guarantee.
+///
+/// Panics if the object doesn't decode even with as many repair packets as the ESI space allows.
+pub fn simulate_min_overhead<F>(encoder: &Encoder, trials: u32, mut channel: F) -> u32
+where
+    F: FnMut(u32, u8, Vec<EncodingPacket>) -> Vec<EncodingPacket>,
+{
+    let max_repair_packets = encoder
+        .get_block_encoders()
+        .iter()
+        .map(|block_encoder| block_encoder.remaining_esi_space())
+        .min()
+        .unwrap();
+    let mut high = 1.min(max_repair_packets);
+    while !decodes_with(encoder, trials, high, &mut channel) {
+        assert!(
+            high < max_repair_packets,
+            "the object did not decode with any number of repair packets"
+        );
+        high = (high * 2).min(max_repair_packets);
+    }
+    let mut low = 0;
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if decodes_with(encoder, trials, mid, &mut channel) {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    high
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::debug::{
+        decodes_with, degree_histogram, expected_degree, simulate_min_overhead, PacketTimer,
+    };
+    use crate::{Encoder, EncodingPacket, ObjectTransmissionInformation, SourceBlockEncoder};
+
+    #[test]
+    fn degree_distribution() {
+        let histogram = degree_histogram(1000, 100_000);
+        assert_eq!(histogram.len(), 31);
+        assert_eq!(histogram.iter().sum::<u32>(), 100_000);
+        assert_eq!(histogram[0], 0);
+        // Expected fractions from the degree table in section 5.3.5.2
+        let expected = [(1, 0.005), (2, 0.500), (3, 0.167), (4, 0.083), (30, 0.030)];
+        for &(degree, fraction) in expected.iter() {
+            let observed = histogram[degree] as f64 / 100_000.0;
+            assert!(
+                (observed - fraction).abs() < 0.01,
+                "degree {}: {} != {}",
+                degree,
+                observed,
+                fraction
+            );
+        }
+    }
+
+    #[test]
+    fn mean_degree() {
+        let histogram = degree_histogram(1000, 100_000);
+        let sampled = histogram
+            .iter()
+            .enumerate()
+            .map(|(degree, &count)| (degree as u32 * count) as f64)
+            .sum::<f64>()
+            / 100_000.0;
+        let expected = expected_degree(1000);
+        assert!(
+            (sampled - expected).abs() < 0.05,
+            "{} != {}",
+            sampled,
+            expected
+        );
+
+        // W = 17 for K' = 10, which truncates the degree to 15
+        assert!(expected_degree(10) < expected);
+    }
+
+    #[test]
+    fn timed_packets() {
+        let data = vec![1; 64 * 10];
+        let config = ObjectTransmissionInformation::new(0, 64, 0, 1, 1);
+        let encoder = SourceBlockEncoder::new2(0, &config, &data);
+        let timer = PacketTimer::new();
+        let timed: Vec<_> = encoder
+            .symbols_in_range(0..20)
+            .map(|packet| timer.stamp(packet))
+            .collect();
+        for pair in timed.windows(2) {
+            assert!(pair[0].generated_at() <= pair[1].generated_at());
+        }
+        assert!(timed[19].generated_at() <= timer.now());
+        let packets: Vec<_> = timed
+            .into_iter()
+            .map(|packet| packet.into_packet())
+            .collect();
+        assert_eq!(packets, encoder.symbols_in_range(0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn min_overhead() {
+        let data = vec![7; 5000];
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let lossless = |_, _, packets| packets;
+        assert_eq!(simulate_min_overhead(&encoder, 3, lossless), 0);
+
+        // Loses the first 10 packets of each block in trial 0, and 11 in trial 1, along with every
+        // 5th one, regardless of how many are sent. K = 40 for block 0, so at least 20 repair
+        // packets are needed for 40 of its packets to arrive in trial 1
+        let mut channel = |trial: u32, _, packets: Vec<EncodingPacket>| {
+            packets
+                .into_iter()
+                .enumerate()
+                .filter(|&(i, _)| i >= 10 + trial as usize && i % 5 != 0)
+                .map(|(_, packet)| packet)
+                .collect()
+        };
+        let overhead = simulate_min_overhead(&encoder, 2, &mut channel);
+        assert!(overhead >= 20, "{}", overhead);
+        assert!(decodes_with(&encoder, 2, overhead, &mut channel));
+        assert!(!decodes_with(&encoder, 2, overhead - 1, &mut channel));
+    }
+}
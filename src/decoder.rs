@@ -1,51 +1,501 @@
 use crate::base::intermediate_tuple;
 use crate::base::partition;
+use crate::base::sub_block_layout;
 use crate::base::EncodingPacket;
 use crate::base::ObjectTransmissionInformation;
+use crate::base::ParseError;
+use crate::base::PayloadId;
+use crate::base::SubBlockInfo;
+use crate::base::MAX_ESI;
+use crate::checksum::{ObjectHasher, TransferHasher};
 use crate::constraint_matrix::enc_indices;
 use crate::constraint_matrix::generate_constraint_matrix;
 use crate::encoder::SPARSE_MATRIX_THRESHOLD;
+use crate::manifest::TransferManifest;
 use crate::matrix::{BinaryMatrix, DenseBinaryMatrix};
+use crate::octet::Octet;
 use crate::octet_matrix::DenseOctetMatrix;
-use crate::pi_solver::fused_inverse_mul_symbols;
+use crate::octets::{add_assign, fused_addassign_mul_scalar, mulassign_scalar};
+use crate::pi_solver::IntermediateSymbolDecoder;
 use crate::sparse_matrix::SparseBinaryMatrix;
 use crate::symbol::Symbol;
+use crate::symbol_store::{MemorySymbolStore, SymbolStore};
 use crate::systematic_constants::num_hdpc_symbols;
+use crate::systematic_constants::num_intermediate_symbols;
 use crate::systematic_constants::num_ldpc_symbols;
 use crate::systematic_constants::{
     calculate_p1, extended_source_block_symbols, num_lt_symbols, num_pi_symbols, systematic_index,
 };
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, Read};
+use std::ops::Range;
+use std::path::Path;
 use std::{collections::HashSet, iter};
 
+/// Error returned when the object cannot be decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Decoding a source block required more row operations than the limit allowed
+    BudgetExceeded,
+    /// Not enough packets have been received to decode every source block
+    InsufficientSymbols,
+    /// The transfer manifest describes a different object transmission information than the
+    /// decoder was created with
+    ManifestMismatch,
+    /// The reconstructed object does not match the hash in the transfer manifest
+    HashMismatch,
+    /// A source block has received at least as many symbols as it has source symbols (K), but
+    /// only `rank` of the `received` symbols are linearly independent, so it cannot be decoded
+    /// until K - `rank` more independent symbols arrive
+    RankDeficient { received: u32, rank: u32 },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::BudgetExceeded => write!(f, "decoding exceeded the operation limit"),
+            DecodeError::InsufficientSymbols => write!(f, "insufficient symbols to decode"),
+            DecodeError::ManifestMismatch => {
+                write!(
+                    f,
+                    "transfer manifest does not match the decoder configuration"
+                )
+            }
+            DecodeError::HashMismatch => {
+                write!(f, "decoded object does not match the transfer hash")
+            }
+            DecodeError::RankDeficient { received, rank } => write!(
+                f,
+                "received {} symbols, but only {} are linearly independent",
+                received, rank
+            ),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// Progress of a single source block, as returned by [`Decoder::block_state`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockState {
+    /// The block has been reconstructed
+    Decoded,
+    /// At least this many more linearly independent symbols are required to decode the block.
+    /// Once K' or more symbols have been received, this is exactly the rank deficiency of the
+    /// constraint matrix. It is zero if the symbols suffice, but the last attempt was aborted by
+    /// the limit set with [`Decoder::decode_with_limit`].
+    NeedMore(u32),
+    /// More symbols are required, but the transmission has been marked complete with
+    /// [`Decoder::set_transmission_complete`], so the block can never be decoded
+    Undecodable,
+}
+
+/// When a [`Decoder`] attempts to decode a source block as packets are received, set with
+/// [`Decoder::set_decode_strategy`].
+///
+/// A block is never solved before K of its symbols have been received, since fewer can't be
+/// enough. Except under `Manual`, a block is also always reconstructed as soon as every one of its
+/// source symbols has been received, since that requires no solve.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub enum DecodeStrategy {
+    /// Attempt to decode after every packet, minimizing latency
+    Eager,
+    /// Only attempt to decode once at least K plus this many symbols have been received, and
+    /// after every packet from then on. Each failed attempt costs a matrix solve, so a margin of a
+    /// few symbols avoids most of them. [RFC 6330](https://tools.ietf.org/html/rfc6330) gives the
+    /// decoding failure probability, from K received symbols plus the K' - K padding symbols, as
+    /// less than 1%, from one more symbol less than 1 in 10<sup>4</sup>, and from two more less
+    /// than 1 in 10<sup>6</sup>.
+    OnThreshold(u32),
+    /// Never attempt to decode while ingesting packets. Decoding is only attempted by
+    /// [`Decoder::try_decode`] and the other methods which explicitly decode.
+    Manual,
+}
+
+impl Default for DecodeStrategy {
+    fn default() -> DecodeStrategy {
+        DecodeStrategy::OnThreshold(0)
+    }
+}
+
+/// Describes the first malformed packet found by [`Decoder::validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The source block number is not less than the number of source blocks
+    InvalidSourceBlock(PayloadId),
+    /// The ESI identifies one of the padding symbols between K and K', which are never sent
+    PaddingSymbol(PayloadId),
+    /// The payload length does not match the symbol size
+    InvalidLength {
+        payload_id: PayloadId,
+        length: usize,
+    },
+    /// Two packets with the same payload ID carry different data
+    ConflictingPayload(PayloadId),
+    /// The byte offset is not the start of a source symbol of the object
+    InvalidOffset(u64),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::InvalidSourceBlock(payload_id) => write!(
+                f,
+                "source block {} does not exist",
+                payload_id.source_block_number()
+            ),
+            ValidationError::PaddingSymbol(payload_id) => write!(
+                f,
+                "ESI {} of source block {} is a padding symbol",
+                payload_id.encoding_symbol_id(),
+                payload_id.source_block_number()
+            ),
+            ValidationError::InvalidLength { payload_id, length } => write!(
+                f,
+                "payload of ESI {} in source block {} has invalid length {}",
+                payload_id.encoding_symbol_id(),
+                payload_id.source_block_number(),
+                length
+            ),
+            ValidationError::ConflictingPayload(payload_id) => write!(
+                f,
+                "conflicting payloads for ESI {} of source block {}",
+                payload_id.encoding_symbol_id(),
+                payload_id.source_block_number()
+            ),
+            ValidationError::InvalidOffset(offset) => {
+                write!(
+                    f,
+                    "byte offset {} is not the start of a source symbol",
+                    offset
+                )
+            }
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Receives the source symbols of each source block as it is decoded, so that callers can stream
+/// the object to a file or socket without the decoder building the whole object in memory.
+/// See [`Decoder::decode_into`] and [`SourceBlockDecoder::decode_into`].
+pub trait DecodeSink {
+    /// Called with each of the source symbols of source block `sbn`, in increasing ESI order,
+    /// once the block has been decoded.
+    ///
+    /// `data` is the symbol exactly as it was encoded, which is `symbol_size` bytes: the final
+    /// source symbol of the object includes the
+    /// [`padding_bytes()`](ObjectTransmissionInformation::padding_bytes), and with sub-blocking
+    /// each symbol interleaves sub-symbols from each sub-block (section 4.4.1.2), so that the
+    /// symbol's bytes are not contiguous in the object.
+    fn write_symbol(&mut self, sbn: u8, esi: u32, data: &[u8]);
+}
+
+/// A [`DecodeSink`] which reassembles the object in memory, as [`Decoder::decode`] does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VecSink {
+    config: ObjectTransmissionInformation,
+    layout: Vec<SubBlockInfo>,
+    // Offset of each source block in data
+    block_offsets: Vec<usize>,
+    data: Vec<u8>,
+}
+
+impl VecSink {
+    pub fn new(config: ObjectTransmissionInformation) -> VecSink {
+        let symbol_size = config.symbol_size() as usize;
+        let mut block_offsets = vec![];
+        let mut length = 0;
+        for sbn in config.source_block_numbers() {
+            block_offsets.push(length);
+            length += config.symbols_in_block(sbn) as usize * symbol_size;
+        }
+        VecSink {
+            config,
+            layout: config.sub_block_layout(),
+            block_offsets,
+            data: vec![0; length],
+        }
+    }
+
+    /// The reassembled object, truncated to the transfer length. Bytes of source symbols which
+    /// were not written are zero.
+    pub fn into_vec(mut self) -> Vec<u8> {
+        self.data.truncate(self.config.transfer_length() as usize);
+        self.data
+    }
+}
+
+impl DecodeSink for VecSink {
+    fn write_symbol(&mut self, sbn: u8, esi: u32, data: &[u8]) {
+        let block_symbols = self.config.symbols_in_block(sbn) as usize;
+        let start = self.block_offsets[sbn as usize];
+        let end = start + block_symbols * self.config.symbol_size() as usize;
+        unpack_sub_blocks(
+            &mut self.data[start..end],
+            data,
+            esi as usize,
+            block_symbols,
+            &self.layout,
+        );
+    }
+}
+
+// Reassembles a single source block, for SourceBlockDecoder::decode()
+struct BlockSink {
+    layout: Vec<SubBlockInfo>,
+    block_symbols: usize,
+    block: Vec<u8>,
+}
+
+impl DecodeSink for BlockSink {
+    fn write_symbol(&mut self, _sbn: u8, esi: u32, data: &[u8]) {
+        unpack_sub_blocks(
+            &mut self.block,
+            data,
+            esi as usize,
+            self.block_symbols,
+            &self.layout,
+        );
+    }
+}
+
+// Copies each sub-symbol of a source symbol to its place in its sub-block. Each sub-block holds
+// the sub-symbols of every symbol of the source block in turn. See section 4.4.1.2
+fn unpack_sub_blocks(
+    block: &mut [u8],
+    symbol: &[u8],
+    symbol_index: usize,
+    block_symbols: usize,
+    layout: &[SubBlockInfo],
+) {
+    let mut sub_block_offset = 0;
+    for info in layout {
+        let bytes = info.sub_symbol_size() as usize;
+        let symbol_offset = info.symbol_offset() as usize;
+        let start = sub_block_offset + bytes * symbol_index;
+        block[start..start + bytes].copy_from_slice(&symbol[symbol_offset..symbol_offset + bytes]);
+        sub_block_offset += bytes * block_symbols;
+    }
+}
+
+// Allowance for the solver's working copies of (parts of) the constraint matrix, on top of the
+// matrix itself. See Decoder::estimated_peak_memory()
+const DENSE_SUBMATRIX_OVERHEAD_FACTOR: u64 = 2;
+
+/// Estimates the relative CPU cost of decoding a source block with the given number of symbols.
+///
+/// The cost is modeled as L^2, where L is the number of intermediate symbols
+/// ([section 5.3.3.3](https://tools.ietf.org/html/rfc6330#section-5.3.3.3)), since decoding
+/// eliminates an L column constraint matrix whose rows each touch O(L) entries. The result is in
+/// arbitrary units, and is only meaningful for ordering decodes relative to one another.
+pub fn estimate_decode_cost(source_block_symbols: u32) -> u64 {
+    let intermediate_symbols = num_intermediate_symbols(source_block_symbols) as u64;
+    intermediate_symbols * intermediate_symbols
+}
+
+/// Summary of a completed decode, as returned by [`Decoder::decode_result`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct DecodeResult {
+    source_symbols: u64,
+    received_symbols: u64,
+    redundant_esis: Vec<PayloadId>,
+}
+
+impl DecodeResult {
+    /// Total number of source symbols, K, across all source blocks.
+    pub fn source_symbols(&self) -> u64 {
+        self.source_symbols
+    }
+
+    /// Total number of distinct symbols that had been received by the time each source block was
+    /// decoded.
+    pub fn received_symbols(&self) -> u64 {
+        self.received_symbols
+    }
+
+    /// Number of symbols received beyond the source symbols, which is the reception overhead.
+    pub fn overhead_symbols(&self) -> u64 {
+        self.received_symbols - self.source_symbols
+    }
+
+    /// Reception overhead as a fraction of the source symbols.
+    pub fn overhead(&self) -> f64 {
+        self.overhead_symbols() as f64 / self.source_symbols as f64
+    }
+
+    /// Received symbols which weren't needed to decode their source block, because they didn't
+    /// increase the rank of its constraint matrix, ordered by SBN. Operators can use this to tune
+    /// the number of repair symbols sent downward.
+    ///
+    /// These are the rows which the solver left out of its pivots when each block was decoded, so
+    /// no extra solve is needed to find them. Which symbols are redundant depends on the order in
+    /// which the pivots were picked, so this is one set of symbols that could have been lost,
+    /// rather than the only one. It is also a lower bound: the solver picks the LDPC, HDPC and
+    /// padding rows ahead of the received symbols where it can, but it may leave one of them out
+    /// instead, in which case fewer symbols are listed than were received beyond K. When every
+    /// source symbol of a block was received, all of its repair symbols are listed.
+    pub fn redundant_esis(&self) -> &[PayloadId] {
+        &self.redundant_esis
+    }
+}
+
+/// Accumulates the reception overhead of many decodes, so that senders can tune the number of
+/// repair symbols that they send from the overhead actually observed by receivers.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct OverheadStats {
+    decodes: u64,
+    total_overhead: f64,
+    min_overhead: f64,
+    max_overhead: f64,
+}
+
+impl OverheadStats {
+    pub fn new() -> OverheadStats {
+        OverheadStats::default()
+    }
+
+    pub fn record(&mut self, result: &DecodeResult) {
+        let overhead = result.overhead();
+        if self.decodes == 0 {
+            self.min_overhead = overhead;
+            self.max_overhead = overhead;
+        } else {
+            self.min_overhead = self.min_overhead.min(overhead);
+            self.max_overhead = self.max_overhead.max(overhead);
+        }
+        self.decodes += 1;
+        self.total_overhead += overhead;
+    }
+
+    /// Number of decodes recorded.
+    pub fn decodes(&self) -> u64 {
+        self.decodes
+    }
+
+    pub fn min_overhead(&self) -> Option<f64> {
+        self.recorded(self.min_overhead)
+    }
+
+    pub fn mean_overhead(&self) -> Option<f64> {
+        self.recorded(self.total_overhead / self.decodes as f64)
+    }
+
+    pub fn max_overhead(&self) -> Option<f64> {
+        self.recorded(self.max_overhead)
+    }
+
+    /// Overhead, as a fraction of the source symbols, to provision for in addition to the
+    /// expected loss: the largest overhead recorded, so that every recorded decode would have
+    /// succeeded. Returns `None` if nothing has been recorded.
+    ///
+    /// RaptorQ decodes from K symbols with high probability, so this is usually a small fraction,
+    /// but a rare unlucky decode keeps it raised for as long as these statistics are retained.
+    pub fn recommended_overhead(&self) -> Option<f64> {
+        self.max_overhead()
+    }
+
+    fn recorded(&self, value: f64) -> Option<f64> {
+        if self.decodes == 0 {
+            None
+        } else {
+            Some(value)
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
-pub struct Decoder {
+pub struct Decoder<S: SymbolStore = MemorySymbolStore> {
     config: ObjectTransmissionInformation,
-    block_decoders: Vec<SourceBlockDecoder>,
+    block_decoders: Vec<SourceBlockDecoder<S>>,
     blocks: Vec<Option<Vec<u8>>>,
+    transmission_complete: bool,
+    rejected_packets: u64,
+    strategy: DecodeStrategy,
 }
 
 impl Decoder {
     pub fn new(config: ObjectTransmissionInformation) -> Decoder {
+        Decoder::with_symbol_stores(config, |_| MemorySymbolStore::new())
+    }
+
+    /// Creates a decoder from a header packet, as produced by
+    /// [`Encoder::header_packet`](crate::Encoder::header_packet), for senders which transmit the
+    /// OTI in a dedicated first packet rather than out of band.
+    ///
+    /// The OTI is read from the first 12 bytes of the packet and validated with
+    /// [`ObjectTransmissionInformation::try_deserialize`]. Any further bytes are ignored, so that
+    /// applications can append their own metadata.
+    pub fn from_header_packet(packet: &[u8]) -> Result<Decoder, ParseError> {
+        if packet.len() < 12 {
+            return Err(ParseError::InvalidLength {
+                expected: 12,
+                actual: packet.len(),
+            });
+        }
+        let config = ObjectTransmissionInformation::try_deserialize(&packet[..12])?;
+        Ok(Decoder::new(config))
+    }
+
+    /// Creates a decoder with storage preallocated for `expected_symbols_per_block` received
+    /// symbols (source and repair) in each source block.
+    ///
+    /// This is only a hint: receiving more symbols than expected just triggers normal growth.
+    pub fn with_capacity(
+        config: ObjectTransmissionInformation,
+        expected_symbols_per_block: u32,
+    ) -> Decoder {
+        let mut decoder = Decoder::new(config);
+        for block_decoder in decoder.block_decoders.iter_mut() {
+            block_decoder.reserve(expected_symbols_per_block);
+        }
+        decoder
+    }
+}
+
+impl<S: SymbolStore> Decoder<S> {
+    /// Creates a decoder which buffers the symbols received for each source block in the store
+    /// returned by `create_store` for that source block number.
+    pub fn with_symbol_stores<F: FnMut(u8) -> S>(
+        config: ObjectTransmissionInformation,
+        mut create_store: F,
+    ) -> Decoder<S> {
         let kt = (config.transfer_length() as f64 / config.symbol_size() as f64).ceil() as u32;
         let (kl, ks, zl, zs) = partition(kt, config.source_blocks());
 
         let mut decoders = vec![];
         for i in 0..zl {
-            decoders.push(SourceBlockDecoder::new2(
+            decoders.push(SourceBlockDecoder::with_symbol_store(
                 i as u8,
                 &config,
                 u64::from(kl) * u64::from(config.symbol_size()),
+                create_store(i as u8),
             ));
         }
 
         for i in zl..(zl + zs) {
-            decoders.push(SourceBlockDecoder::new2(
+            let mut block_length = u64::from(ks) * u64::from(config.symbol_size());
+            // The final block is not padded to a whole number of symbols, so that its final
+            // source symbol is accepted without the padding
+            if i + 1 == zl + zs && config.sub_blocks() == 1 {
+                block_length -= config.padding_bytes();
+            }
+            decoders.push(SourceBlockDecoder::with_symbol_store(
                 i as u8,
                 &config,
-                u64::from(ks) * u64::from(config.symbol_size()),
+                block_length,
+                create_store(i as u8),
             ));
         }
 
@@ -53,6 +503,9 @@ impl Decoder {
             config,
             block_decoders: decoders,
             blocks: vec![None; (zl + zs) as usize],
+            transmission_complete: false,
+            rejected_packets: 0,
+            strategy: DecodeStrategy::default(),
         }
     }
 
@@ -63,69 +516,610 @@ impl Decoder {
         }
     }
 
+    /// See [`SourceBlockDecoder::set_truncate_oversized_packets`]
+    pub fn set_truncate_oversized_packets(&mut self, truncate: bool) {
+        for block_decoder in self.block_decoders.iter_mut() {
+            block_decoder.set_truncate_oversized_packets(truncate);
+        }
+    }
+
+    /// See [`SourceBlockDecoder::set_rank_tracking`]
+    pub fn set_rank_tracking(&mut self, enabled: bool) {
+        for block_decoder in self.block_decoders.iter_mut() {
+            block_decoder.set_rank_tracking(enabled);
+        }
+    }
+
+    /// Sets when source blocks are decoded as packets are ingested by [`decode`](Self::decode),
+    /// [`add_new_packet`](Self::add_new_packet) and [`add_packets`](Self::add_packets).
+    /// Defaults to [`DecodeStrategy::OnThreshold(0)`](DecodeStrategy::OnThreshold), which attempts
+    /// to decode after every packet once K symbols have been received.
+    pub fn set_decode_strategy(&mut self, strategy: DecodeStrategy) {
+        self.strategy = strategy;
+    }
+
     pub fn decode(&mut self, packet: EncodingPacket) -> Option<Vec<u8>> {
         let block_number = packet.payload_id.source_block_number() as usize;
-        if self.blocks[block_number].is_none() {
-            self.blocks[block_number] =
-                self.block_decoders[block_number].decode(iter::once(packet));
+        self.ingest_block_packets(block_number, iter::once(packet));
+        self.assemble(|_| {})
+    }
+
+    /// Like [`decode`](Self::decode), but writes the source symbols of each source block to `sink`
+    /// as soon as the block has been decoded, rather than buffering the whole object. Returns true
+    /// once every source block has been written. Use [`VecSink`] to build the object in memory.
+    ///
+    /// Blocks written to the sink are not retained by the decoder, so this shouldn't be mixed with
+    /// [`decode`](Self::decode) or the other functions which return the object:
+    /// [`get_result`](Self::get_result) returns `None` once any block has been written to a sink.
+    pub fn decode_into<D: DecodeSink + ?Sized>(
+        &mut self,
+        packet: EncodingPacket,
+        sink: &mut D,
+    ) -> bool {
+        let block_number = packet.payload_id.source_block_number() as usize;
+        let decoder = &mut self.block_decoders[block_number];
+        if !decoder.decoded {
+            decoder.ingest_packet(packet);
+            if decoder.should_attempt_decode(self.strategy) {
+                decoder.decode_into(iter::empty(), sink);
+            }
         }
-        for block in self.blocks.iter() {
-            if block.is_none() {
+        self.block_decoders.iter().all(|decoder| decoder.decoded)
+    }
+
+    /// Attempts to decode every source block which hasn't been decoded yet, regardless of the
+    /// [`DecodeStrategy`], and returns the object if it can be reconstructed.
+    pub fn try_decode(&mut self) -> Option<Vec<u8>> {
+        for (block, decoder) in self.block_decoders.iter_mut().enumerate() {
+            if self.blocks[block].is_none() {
+                self.blocks[block] = decoder.decode(iter::empty());
+            }
+        }
+        self.assemble(|_| {})
+    }
+
+    // Ingests packets of a single source block, then attempts to decode it if the strategy allows
+    fn ingest_block_packets<T: IntoIterator<Item = EncodingPacket>>(
+        &mut self,
+        block_number: usize,
+        packets: T,
+    ) {
+        if self.blocks[block_number].is_some() {
+            return;
+        }
+        let decoder = &mut self.block_decoders[block_number];
+        for packet in packets {
+            decoder.ingest_packet(packet);
+        }
+        if decoder.should_attempt_decode(self.strategy) {
+            self.blocks[block_number] = decoder.decode(iter::empty());
+        }
+    }
+
+    /// Reconstructs only the bytes `[start, end)` of the object, for receivers which need part of
+    /// it, returning `None` if the source blocks which cover that range can't be decoded yet.
+    ///
+    /// Only the covering blocks are solved, so this returns as soon as enough symbols have been
+    /// received for them, regardless of the state of the other blocks. Note that
+    /// [`decode`](Self::decode) and [`add_new_packet`](Self::add_new_packet) still attempt to
+    /// decode the block of every packet passed to them, so packets for blocks outside the range
+    /// are best not passed to the decoder at all.
+    pub fn decode_range(&mut self, start: u64, end: u64) -> Option<Vec<u8>> {
+        assert!(start <= end);
+        assert!(end <= self.config.transfer_length());
+        let symbol_size = self.config.symbol_size() as u64;
+        let mut result = Vec::with_capacity((end - start) as usize);
+        let mut block_start = 0;
+        for sbn in self.config.source_block_numbers() {
+            let block_end = block_start + self.config.symbols_in_block(sbn) as u64 * symbol_size;
+            if block_start < end && start < block_end {
+                let block = sbn as usize;
+                if self.blocks[block].is_none() {
+                    self.blocks[block] = self.block_decoders[block].decode(iter::empty());
+                }
+                let data = self.blocks[block].as_ref()?;
+                let from = (start.max(block_start) - block_start) as usize;
+                let to = (end.min(block_end) - block_start) as usize;
+                result.extend_from_slice(&data[from..to]);
+            }
+            block_start = block_end;
+        }
+        Some(result)
+    }
+
+    /// Like [`decode`](Self::decode), but first passes the packet to `verifier`, and discards it
+    /// if that returns false. This allows corrupted symbols to be detected as they arrive, for
+    /// example by comparing them against per-symbol checksums sent alongside the object, rather
+    /// than only once the reconstructed object fails to verify.
+    ///
+    /// For source symbols, the verifier is also given the range of the object which the symbol
+    /// holds (see [`ObjectTransmissionInformation::symbol_byte_range`]). This is `None` for repair
+    /// symbols, whose contents can't be predicted from the object structure, and when sub-blocking
+    /// is in use. Packets for source blocks which have already been decoded are discarded without
+    /// being verified.
+    pub fn decode_checked<F>(&mut self, packet: EncodingPacket, verifier: F) -> Option<Vec<u8>>
+    where
+        F: FnOnce(&EncodingPacket, Option<Range<u64>>) -> bool,
+    {
+        let block_number = packet.payload_id.source_block_number();
+        if self.blocks[block_number as usize].is_none() {
+            let range = self
+                .config
+                .symbol_byte_range(block_number, packet.payload_id.encoding_symbol_id());
+            if !verifier(&packet, range) {
+                self.rejected_packets += 1;
                 return None;
             }
         }
+        self.decode(packet)
+    }
 
-        let mut result = vec![];
-        for block in self.blocks.iter() {
-            if let Some(block) = block {
-                result.extend(block);
+    /// Number of packets discarded because the verifier passed to
+    /// [`decode_checked`](Self::decode_checked) rejected them.
+    pub fn rejected_packets(&self) -> u64 {
+        self.rejected_packets
+    }
+
+    /// Decodes the packets in a file written by
+    /// [`Encoder::encode_to_file`](crate::Encoder::encode_to_file), reading only until the object
+    /// has been reconstructed. Returns `None` if the file does not contain enough packets. The
+    /// file is read sequentially through a buffer, rather than memory-mapped, since std has no
+    /// safe way to map a file; only one packet is held in memory at a time.
+    ///
+    /// Never panics, whatever the contents of the file. Returns an error of kind
+    /// [`InvalidData`](io::ErrorKind::InvalidData) if a length prefix is too short to hold a
+    /// [`PayloadId`] or longer than a serialized packet of this decoder's symbol size, or if a
+    /// packet fails [`EncodingPacket::try_deserialize`] or [`validate`](Self::validate), and
+    /// [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) if the file ends part way through a
+    /// packet.
+    pub fn decode_from_file(&mut self, path: &Path) -> io::Result<Option<Vec<u8>>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut length = [0; 4];
+        loop {
+            // Distinguish the end of the file from a truncated length prefix
+            if reader.read(&mut length[..1])? == 0 {
+                return Ok(None);
+            }
+            reader.read_exact(&mut length[1..])?;
+            let length = u32::from_be_bytes(length) as usize;
+            // A payload ID, followed by at most one symbol
+            let max_length = 4 + self.config.symbol_size() as usize;
+            if length < 4 || length > max_length {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "packet length {} is not between 4 and {}",
+                        length, max_length
+                    ),
+                ));
+            }
+            let mut serialized = vec![0; length];
+            reader.read_exact(&mut serialized)?;
+            let packet = EncodingPacket::try_deserialize(&serialized)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            let result = self
+                .try_decode_packet(packet)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            if result.is_some() {
+                return Ok(result);
             }
         }
+    }
 
-        result.truncate(self.config.transfer_length() as usize);
-        Some(result)
+    /// Attempts to reconstruct the object from the packets received so far, aborting the solve of
+    /// any source block which requires more than `max_ops` row operations. This bounds the CPU
+    /// time that a pathological or adversarial set of packets can consume. The limit is checked
+    /// as each row is eliminated, so a solve may overshoot it by the operations of one row, at
+    /// most about L (the number of intermediate symbols).
+    ///
+    /// The limit only applies to this call: later calls to [`decode`](Self::decode) and
+    /// [`add_new_packet`](Self::add_new_packet) are unlimited again.
+    ///
+    /// When measured, successful solves needed between about 40 and 75 row operations per
+    /// extended source symbol (K', see [section 5.6](https://tools.ietf.org/html/rfc6330#section-5.6)),
+    /// the most for the smallest and the largest blocks, so a limit of `100 * K'` for the
+    /// largest source block leaves a margin.
+    ///
+    /// Returns [`DecodeError::RankDeficient`] if a source block has received enough symbols by
+    /// count, but they are linearly dependent, in which case more symbols must be requested.
+    pub fn decode_with_limit(&mut self, max_ops: u64) -> Result<Vec<u8>, DecodeError> {
+        for (block, decoder) in self.block_decoders.iter_mut().enumerate() {
+            if self.blocks[block].is_none() {
+                let previous_limit = decoder.operation_limit.replace(max_ops);
+                self.blocks[block] = decoder.decode(iter::empty());
+                decoder.operation_limit = previous_limit;
+                if decoder.operation_limit_exceeded {
+                    return Err(DecodeError::BudgetExceeded);
+                }
+                if self.blocks[block].is_none() {
+                    if let Some(rank) = decoder.received_rank() {
+                        return Err(DecodeError::RankDeficient {
+                            received: decoder.received_symbol_ids().len() as u32,
+                            rank,
+                        });
+                    }
+                }
+            }
+        }
+        self.assemble(|_| {})
+            .ok_or(DecodeError::InsufficientSymbols)
+    }
+
+    /// Number of received packets which were discarded because a packet with the same
+    /// (SBN, ESI) had already been received. Packets for source blocks which have already been
+    /// decoded are discarded without being counted.
+    pub fn duplicates_ignored(&self) -> u64 {
+        self.block_decoders
+            .iter()
+            .map(|decoder| decoder.duplicates_ignored())
+            .sum()
+    }
+
+    /// See [`SourceBlockDecoder::invalid_length_ignored`]
+    pub fn invalid_length_ignored(&self) -> u64 {
+        self.block_decoders
+            .iter()
+            .map(|decoder| decoder.invalid_length_ignored())
+            .sum()
+    }
+
+    /// ESIs of the source symbols of block `sbn` which have not been received, in increasing
+    /// order. Empty once the block has been decoded.
+    ///
+    /// On a link with little loss, resending exactly these source symbols is cheaper for the
+    /// sender than generating repair symbols. Only source symbols are covered: this does not
+    /// account for the repair symbols already received, so after a resend the block may decode
+    /// before all of them arrive.
+    pub fn missing_source_esis(&self, sbn: u8) -> Vec<u32> {
+        if self.blocks[sbn as usize].is_some() {
+            return vec![];
+        }
+        self.block_decoders[sbn as usize].missing_source_esis()
+    }
+
+    /// See [`SourceBlockDecoder::verify_block`]. Returns false if block `sbn` has not been decoded.
+    #[cfg(feature = "verify")]
+    pub fn verify_block(&self, sbn: u8) -> bool {
+        self.blocks[sbn as usize].is_some() && self.block_decoders[sbn as usize].verify_block()
+    }
+
+    /// Indicates that the sender will not send any more symbols, for example because an explicit
+    /// end of transmission was received, so that [`block_state`](Self::block_state) can report
+    /// blocks which can no longer be decoded. Defaults to false.
+    pub fn set_transmission_complete(&mut self, complete: bool) {
+        self.transmission_complete = complete;
+    }
+
+    /// Estimates, in bytes, the peak memory needed to decode the whole object, so that a receiver
+    /// with limited memory can reject a transfer before accepting any packets for it.
+    ///
+    /// This counts L symbols for every source block (see
+    /// [`total_intermediate_symbols`](ObjectTransmissionInformation::total_intermediate_symbols)),
+    /// the reassembled object, and, for the largest block, the solver's copy of its L symbols and
+    /// its constraint matrix, which is conservatively assumed to be stored as a dense L x L binary
+    /// matrix with a constant factor for the solver's dense submatrices. Blocks are solved one at
+    /// a time, so only one block's solver state is counted. It ignores allocator overhead and the small
+    /// per-packet bookkeeping, so it should be treated as accurate to within a small multiple
+    /// rather than as an exact bound.
+    pub fn estimated_peak_memory(&self) -> u64 {
+        let symbols = self.config.total_intermediate_symbols() * self.config.symbol_size() as u64;
+        let largest_block = self
+            .config
+            .source_block_numbers()
+            .map(|sbn| num_intermediate_symbols(self.config.symbols_in_block(sbn)) as u64)
+            .max()
+            .unwrap_or(0);
+        // The solver works on a copy of the received symbols, alongside the stored ones
+        let solver_symbols = largest_block * self.config.symbol_size() as u64;
+        let constraint_matrix = DENSE_SUBMATRIX_OVERHEAD_FACTOR * largest_block * largest_block / 8;
+        symbols + self.config.transfer_length() + solver_symbols + constraint_matrix
+    }
+
+    /// Returns the leading bytes of source block `sbn` which are available without decoding it,
+    /// so that e.g. media can be played out before the whole block has arrived. Once the block
+    /// has been decoded, the whole block is returned.
+    ///
+    /// This only covers the gap-free prefix of the source (systematic) symbols: the source
+    /// symbols received from ESI 0 up to the first missing one. Source symbols after a gap, and
+    /// repair symbols, don't contribute until the block is decoded. With more than one sub-block,
+    /// each source symbol holds bytes from every sub-block, so only the part of the prefix which
+    /// lies in the first sub-block is returned.
+    ///
+    /// Borrows the decoded block once it's available. Before then, the prefix is copied out of the
+    /// received symbols, since they are not stored contiguously.
+    pub fn available_prefix(&self, sbn: u8) -> Cow<'_, [u8]> {
+        // The final symbol of the object is padded
+        let symbol_size = self.config.symbol_size() as usize;
+        let mut block_length = self.config.symbols_in_block(sbn) as usize * symbol_size;
+        if sbn == self.config.source_blocks() - 1 {
+            block_length -= self.config.padding_bytes() as usize;
+        }
+        match self.blocks[sbn as usize] {
+            Some(ref block) => Cow::Borrowed(&block[..block_length.min(block.len())]),
+            None => {
+                let mut prefix = self.block_decoders[sbn as usize].received_prefix();
+                prefix.truncate(block_length);
+                Cow::Owned(prefix)
+            }
+        }
+    }
+
+    /// Reports whether source block `sbn` has been decoded, and if not, whether it may still be.
+    ///
+    /// Before K' symbols of the block have been received, this assumes that every received
+    /// symbol was linearly independent, which is true unless an unlucky set of repair symbols
+    /// was received. After that, the rank of the received symbols is computed by a dense
+    /// Gaussian elimination, as in [`would_increase_rank`](Self::would_increase_rank), which is
+    /// expensive for large blocks.
+    ///
+    /// A block is only reported [`Undecodable`](BlockState::Undecodable) once the transmission
+    /// has been marked complete, since any missing symbols might otherwise still arrive.
+    pub fn block_state(&self, sbn: u8) -> BlockState {
+        if self.blocks[sbn as usize].is_some() || self.block_decoders[sbn as usize].decoded {
+            return BlockState::Decoded;
+        }
+        let needed = self.block_decoders[sbn as usize].symbols_needed();
+        if needed > 0 && self.transmission_complete {
+            BlockState::Undecodable
+        } else {
+            BlockState::NeedMore(needed)
+        }
+    }
+
+    /// See [`SourceBlockDecoder::would_increase_rank`]. Always false for source blocks which have
+    /// already been decoded.
+    pub fn would_increase_rank(&self, packet: &EncodingPacket) -> bool {
+        let block_number = packet.payload_id.source_block_number() as usize;
+        self.blocks[block_number].is_none()
+            && self.block_decoders[block_number].would_increase_rank(packet)
+    }
+
+    /// See [`SourceBlockDecoder::can_decode`]. Always true for source blocks which have already
+    /// been decoded.
+    pub fn can_decode(&self, sbn: u8) -> bool {
+        self.blocks[sbn as usize].is_some() || self.block_decoders[sbn as usize].can_decode()
     }
 
     pub fn add_new_packet(&mut self, packet: EncodingPacket) {
         let block_number = packet.payload_id.source_block_number() as usize;
-        if self.blocks[block_number].is_none() {
-            self.blocks[block_number] =
-                self.block_decoders[block_number].decode(iter::once(packet));
+        self.ingest_block_packets(block_number, iter::once(packet));
+    }
+
+    /// Ingests a batch of packets, moving their data into the decoder, and returns the object if
+    /// it can now be reconstructed. Unlike calling [`decode`](Self::decode) for each packet, the
+    /// packets of each source block are all ingested before a single decoding attempt is made for
+    /// that block, if the [`DecodeStrategy`] allows one.
+    pub fn add_packets(&mut self, packets: Vec<EncodingPacket>) -> Option<Vec<u8>> {
+        let mut block_packets: Vec<Vec<EncodingPacket>> = vec![vec![]; self.blocks.len()];
+        for packet in packets {
+            let block_number = packet.payload_id.source_block_number() as usize;
+            block_packets[block_number].push(packet);
+        }
+        for (block_number, packets) in block_packets.drain(..).enumerate() {
+            if !packets.is_empty() {
+                self.ingest_block_packets(block_number, packets);
+            }
+        }
+        self.assemble(|_| {})
+    }
+
+    /// Like [`decode`](Self::decode), but first checks the packet with
+    /// [`validate`](Self::validate), and returns an error, rather than panicking, if it is
+    /// malformed.
+    ///
+    /// Together with [`ObjectTransmissionInformation::try_deserialize`] (or
+    /// [`from_header_packet`](Decoder::from_header_packet)) and
+    /// [`EncodingPacket::try_deserialize`], this gives a receive path which never panics,
+    /// whatever bytes arrive from the network.
+    pub fn try_decode_packet(
+        &mut self,
+        packet: EncodingPacket,
+    ) -> Result<Option<Vec<u8>>, ValidationError> {
+        self.validate(std::slice::from_ref(&packet))?;
+        Ok(self.decode(packet))
+    }
+
+    /// Like [`try_decode_packet`](Self::try_decode_packet), but for a source symbol identified by
+    /// the offset of its first byte in the object, rather than by its payload ID, for storage
+    /// systems which address data by offset. The final source symbol may omit its padding.
+    ///
+    /// This only works for systematic symbols: repair symbols have no place in the object, and
+    /// must be passed to [`decode`](Self::decode) with their payload ID. It is also not
+    /// supported with sub-blocking, where a symbol's bytes are not contiguous in the object (see
+    /// [`ObjectTransmissionInformation::symbol_byte_range`]). Offsets which do not start a
+    /// source symbol return [`ValidationError::InvalidOffset`].
+    pub fn add_symbol_at_offset(
+        &mut self,
+        byte_offset: u64,
+        data: &[u8],
+    ) -> Result<Option<Vec<u8>>, ValidationError> {
+        let payload_id = self
+            .config
+            .symbol_at_offset(byte_offset)
+            .filter(|payload_id| {
+                self.config
+                    .symbol_byte_range(
+                        payload_id.source_block_number(),
+                        payload_id.encoding_symbol_id(),
+                    )
+                    .map(|range| range.start)
+                    == Some(byte_offset)
+            })
+            .ok_or(ValidationError::InvalidOffset(byte_offset))?;
+        self.try_decode_packet(EncodingPacket::new(payload_id, data.to_vec()))
+    }
+
+    /// Checks that `packets` are consistent with the OTI of this decoder, before passing them to
+    /// [`decode`](Self::decode) or [`add_new_packet`](Self::add_new_packet), which panic on
+    /// some malformed input, and may otherwise waste an expensive solve on it.
+    ///
+    /// Each packet must belong to an existing source block, must not use one of the never
+    /// transmitted padding ESIs, and its payload must be a full symbol (the final source symbol of
+    /// the object may omit exactly its
+    /// [`padding_bytes()`](ObjectTransmissionInformation::padding_bytes), and longer payloads are
+    /// accepted if [`set_truncate_oversized_packets`](Self::set_truncate_oversized_packets) is
+    /// enabled).
+    /// Duplicate packets are allowed, as long as they carry the same data.
+    pub fn validate(&self, packets: &[EncodingPacket]) -> Result<(), ValidationError> {
+        let mut payloads = HashMap::new();
+        for packet in packets {
+            let payload_id = packet.payload_id().clone();
+            let sbn = payload_id.source_block_number() as usize;
+            if sbn >= self.block_decoders.len() {
+                return Err(ValidationError::InvalidSourceBlock(payload_id));
+            }
+            let block_decoder = &self.block_decoders[sbn];
+            let esi = payload_id.encoding_symbol_id();
+            let length = packet.data().len();
+            if esi >= block_decoder.source_block_symbols
+                && !block_decoder.repair_esi_range().contains(&esi)
+            {
+                return Err(ValidationError::PaddingSymbol(payload_id));
+            }
+            if !block_decoder.valid_payload_length(esi, length) {
+                return Err(ValidationError::InvalidLength { payload_id, length });
+            }
+            if let Some(&previous) = payloads.get(&payload_id) {
+                if previous != packet.data() {
+                    return Err(ValidationError::ConflictingPayload(payload_id));
+                }
+            } else {
+                payloads.insert(payload_id, packet.data());
+            }
         }
+        Ok(())
     }
 
     pub fn get_result(&self) -> Option<Vec<u8>> {
+        self.assemble(|_| {})
+    }
+
+    /// Like [`get_result`](Self::get_result), but also feeds the reconstructed object through
+    /// `hasher` as it is assembled, avoiding a second pass over the data.
+    pub fn get_result_with_hasher<H: ObjectHasher>(
+        &self,
+        mut hasher: H,
+    ) -> Option<(Vec<u8>, H::Output)> {
+        let result = self.assemble(|data| hasher.update(data))?;
+        Some((result, hasher.finish()))
+    }
+
+    /// Like [`get_result`](Self::get_result), but checks the decoder against `manifest`, and the
+    /// reconstructed object against its transfer hash, if it has one. `hasher` must be of the
+    /// same kind that the sender passed to
+    /// [`TransferManifest::set_transfer_hash`](crate::TransferManifest::set_transfer_hash).
+    pub fn get_verified_result<H: TransferHasher>(
+        &self,
+        manifest: &TransferManifest,
+        hasher: H,
+    ) -> Result<Vec<u8>, DecodeError> {
+        if manifest.config() != self.config {
+            return Err(DecodeError::ManifestMismatch);
+        }
+        let (result, digest) = self
+            .get_result_with_hasher(hasher)
+            .ok_or(DecodeError::InsufficientSymbols)?;
+        if let Some(expected) = manifest.transfer_hash() {
+            if H::digest_bytes(digest) != expected {
+                return Err(DecodeError::HashMismatch);
+            }
+        }
+        Ok(result)
+    }
+
+    // Concatenates the decoded blocks, excluding the padding beyond the transfer length
+    fn assemble<F: FnMut(&[u8])>(&self, mut visit: F) -> Option<Vec<u8>> {
         for block in self.blocks.iter() {
             if block.is_none() {
                 return None;
             }
         }
 
-        let mut result = vec![];
-        for block in self.blocks.iter() {
-            if let Some(block) = block {
-                result.extend(block);
-            }
+        let transfer_length = self.config.transfer_length() as usize;
+        let mut result = Vec::with_capacity(transfer_length);
+        for block in self.blocks.iter().flatten() {
+            let remaining = transfer_length - result.len();
+            let block = &block[..block.len().min(remaining)];
+            visit(block);
+            result.extend_from_slice(block);
+        }
+        Some(result)
+    }
+
+    /// Summarizes the symbols used to decode the object, or returns `None` if it has not been
+    /// decoded yet. Packets passed to [`decode`](Self::decode) or
+    /// [`add_new_packet`](Self::add_new_packet) after a source block was decoded are discarded,
+    /// so they aren't counted.
+    pub fn decode_result(&self) -> Option<DecodeResult> {
+        if self.blocks.iter().any(|block| block.is_none()) {
+            return None;
+        }
+        let mut result = DecodeResult {
+            source_symbols: 0,
+            received_symbols: 0,
+            redundant_esis: vec![],
+        };
+        for block_decoder in self.block_decoders.iter() {
+            let source_symbols = block_decoder.source_block_symbols;
+            let padding = extended_source_block_symbols(source_symbols) - source_symbols;
+            result.source_symbols += source_symbols as u64;
+            result.received_symbols += block_decoder.received_esi.len() as u64 - padding as u64;
+            result.redundant_esis.extend(
+                block_decoder
+                    .redundant_esis
+                    .iter()
+                    .map(|&esi| PayloadId::new(block_decoder.source_block_id, esi)),
+            );
         }
-        result.truncate(self.config.transfer_length() as usize);
         Some(result)
     }
+
+    /// Returns true if both decoders have received the same set of (SBN, ESI) pairs, regardless
+    /// of the order in which they arrived.
+    pub fn received_set_eq<T: SymbolStore>(&self, other: &Decoder<T>) -> bool {
+        self.block_decoders.len() == other.block_decoders.len()
+            && self
+                .block_decoders
+                .iter()
+                .zip(other.block_decoders.iter())
+                .all(|(x, y)| x.received_symbol_ids() == y.received_symbol_ids())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
-pub struct SourceBlockDecoder {
+pub struct SourceBlockDecoder<S: SymbolStore = MemorySymbolStore> {
     source_block_id: u8,
     symbol_size: u16,
     num_sub_blocks: u16,
     symbol_alignment: u8,
     source_block_symbols: u32,
-    source_symbols: Vec<Option<Symbol>>,
-    repair_packets: Vec<EncodingPacket>,
+    // Source symbols are stored at the index of their ESI, followed by repair symbols
+    symbols: S,
+    repair_esis: Vec<u32>,
     received_source_symbols: u32,
+    // Length of the final source symbol without its padding, which it may be sent without
+    final_symbol_length: usize,
     received_esi: HashSet<u32>,
     decoded: bool,
     sparse_threshold: u32,
+    truncate_oversized_packets: bool,
+    operation_limit: Option<u64>,
+    operation_limit_exceeded: bool,
+    // Rank of the constraint matrix reported by the solver, if the last attempt to solve it failed
+    // because it is singular
+    constraint_rank: Option<u32>,
+    // The received symbols which the solver left out of the rows it used to decode the block,
+    // sorted. See DecodeResult::redundant_esis()
+    redundant_esis: Vec<u32>,
+    duplicates_ignored: u64,
+    invalid_length_ignored: u64,
+    // Reduced basis of the constraint rows of the received symbols, kept up to date by
+    // ingest_packet() if rank tracking is enabled. See set_rank_tracking()
+    rank_basis: Option<Vec<(usize, Vec<u8>)>>,
+    // Kept after a successful solve, so that the block can be verified
+    #[cfg(feature = "verify")]
+    intermediate_symbols: Option<Vec<Symbol>>,
 }
 
 impl SourceBlockDecoder {
@@ -144,6 +1138,23 @@ impl SourceBlockDecoder {
         config: &ObjectTransmissionInformation,
         block_length: u64,
     ) -> SourceBlockDecoder {
+        SourceBlockDecoder::with_symbol_store(
+            source_block_id,
+            config,
+            block_length,
+            MemorySymbolStore::new(),
+        )
+    }
+}
+
+impl<S: SymbolStore> SourceBlockDecoder<S> {
+    /// Like [`new2`](SourceBlockDecoder::new2), but buffers received symbols in `store`.
+    pub fn with_symbol_store(
+        source_block_id: u8,
+        config: &ObjectTransmissionInformation,
+        block_length: u64,
+        store: S,
+    ) -> SourceBlockDecoder<S> {
         let source_symbols = (block_length as f64 / config.symbol_size() as f64).ceil() as u32;
         let mut received_esi = HashSet::new();
         for i in source_symbols..extended_source_block_symbols(source_symbols) {
@@ -155,12 +1166,25 @@ impl SourceBlockDecoder {
             num_sub_blocks: config.sub_blocks(),
             symbol_alignment: config.symbol_alignment(),
             source_block_symbols: source_symbols,
-            source_symbols: vec![None; source_symbols as usize],
-            repair_packets: vec![],
+            symbols: store,
+            repair_esis: vec![],
             received_source_symbols: 0,
+            final_symbol_length: (block_length
+                - u64::from(source_symbols.saturating_sub(1)) * u64::from(config.symbol_size()))
+                as usize,
             received_esi,
             decoded: false,
             sparse_threshold: SPARSE_MATRIX_THRESHOLD,
+            truncate_oversized_packets: false,
+            operation_limit: None,
+            operation_limit_exceeded: false,
+            constraint_rank: None,
+            redundant_esis: vec![],
+            duplicates_ignored: 0,
+            invalid_length_ignored: 0,
+            rank_basis: None,
+            #[cfg(feature = "verify")]
+            intermediate_symbols: None,
         }
     }
 
@@ -169,54 +1193,386 @@ impl SourceBlockDecoder {
         self.sparse_threshold = value;
     }
 
-    fn unpack_sub_blocks(&self, result: &mut Vec<u8>, symbol: &Symbol, symbol_index: usize) {
-        let (tl, ts, nl, ns) = partition(
-            (self.symbol_size / self.symbol_alignment as u16) as u32,
-            self.num_sub_blocks,
-        );
+    /// Discard any bytes beyond the symbol size at the end of received packets, rather than
+    /// treating them as symbol data. This allows decoding packets received from transports
+    /// which pad every frame to a fixed size. Packets no longer than the symbol size are
+    /// unaffected. Defaults to false.
+    pub fn set_truncate_oversized_packets(&mut self, truncate: bool) {
+        self.truncate_oversized_packets = truncate;
+    }
 
-        let mut symbol_offset = 0;
-        let mut sub_block_offset = 0;
-        for sub_block in 0..(nl + ns) as u32 {
-            let bytes = if sub_block < nl {
-                tl as usize * self.symbol_alignment as usize
-            } else {
-                ts as usize * self.symbol_alignment as usize
-            };
-            let start = sub_block_offset + bytes * symbol_index;
-            result[start..start + bytes]
-                .copy_from_slice(&symbol.as_bytes()[symbol_offset..symbol_offset + bytes]);
-            symbol_offset += bytes;
-            sub_block_offset += bytes * self.source_block_symbols as usize;
+    /// Keep a reduced basis of the received symbols up to date as packets are ingested, so that
+    /// [`would_increase_rank`](Self::would_increase_rank), and the number of symbols still needed
+    /// reported by [`Decoder::block_state`], take O(L^2) operations rather than eliminating every
+    /// received symbol again in O(L^3). Each ingested packet then costs O(L^2) operations, and
+    /// the basis takes L^2 bytes, so this suits receivers which poll for every packet. Defaults to
+    /// false.
+    pub fn set_rank_tracking(&mut self, enabled: bool) {
+        if !enabled {
+            self.rank_basis = None;
+        } else if self.rank_basis.is_none() {
+            self.rank_basis = Some(self.received_basis());
         }
     }
 
-    fn try_pi_decode(
-        &mut self,
-        constraint_matrix: impl BinaryMatrix,
-        hdpc_rows: DenseOctetMatrix,
-        symbols: Vec<Symbol>,
-    ) -> Option<Vec<u8>> {
-        let intermediate_symbols = match fused_inverse_mul_symbols(
-            constraint_matrix,
-            hdpc_rows,
-            symbols,
-            self.source_block_symbols,
-        ) {
-            (None, _) => return None,
-            (Some(s), _) => s,
-        };
+    /// Returns the range of ESIs which identify repair symbols of this block: from K' up to the
+    /// limit of the 24-bit ESI field.
+    pub fn repair_esi_range(&self) -> Range<u32> {
+        extended_source_block_symbols(self.source_block_symbols)..MAX_ESI
+    }
 
-        let mut result = vec![0; self.symbol_size as usize * self.source_block_symbols as usize];
-        let lt_symbols = num_lt_symbols(self.source_block_symbols);
-        let pi_symbols = num_pi_symbols(self.source_block_symbols);
-        let sys_index = systematic_index(self.source_block_symbols);
-        let p1 = calculate_p1(self.source_block_symbols);
-        for i in 0..self.source_block_symbols as usize {
-            if let Some(ref symbol) = self.source_symbols[i] {
-                self.unpack_sub_blocks(&mut result, symbol, i);
-            } else {
-                let rebuilt = self.rebuild_source_symbol(
+    /// Number of received packets which were discarded because a packet with the same ESI had
+    /// already been received.
+    pub fn duplicates_ignored(&self) -> u64 {
+        self.duplicates_ignored
+    }
+
+    /// Number of received packets which were discarded because their payload was not a whole
+    /// symbol. Only the final source symbol of the object may be sent without its padding, and
+    /// longer payloads are accepted only if
+    /// [`set_truncate_oversized_packets`](Self::set_truncate_oversized_packets) is enabled.
+    pub fn invalid_length_ignored(&self) -> u64 {
+        self.invalid_length_ignored
+    }
+
+    // Whether a payload of `length` bytes is acceptable for the symbol with the given ESI
+    fn valid_payload_length(&self, esi: u32, length: usize) -> bool {
+        let symbol_size = self.symbol_size as usize;
+        if length > symbol_size {
+            return self.truncate_oversized_packets;
+        }
+        length == symbol_size
+            || (esi + 1 == self.source_block_symbols && length == self.final_symbol_length)
+    }
+
+    /// ESIs of the source symbols which have not been received, in increasing order.
+    /// See [`Decoder::missing_source_esis`].
+    pub fn missing_source_esis(&self) -> Vec<u32> {
+        if self.decoded {
+            return vec![];
+        }
+        (0..self.source_block_symbols)
+            .filter(|esi| !self.received_esi.contains(esi))
+            .collect()
+    }
+
+    /// Returns true if the symbol in `packet` is linearly independent of the symbols received so
+    /// far, so that adding it would increase the rank of the constraint matrix
+    /// ([section 5.3.3.4.2](https://tools.ietf.org/html/rfc6330#section-5.3.3.4.2)). The block
+    /// can be decoded once that rank reaches L, the number of intermediate symbols, so a receiver
+    /// can use this to avoid storing symbols which will not help. The packet is not ingested.
+    ///
+    /// This performs a dense Gaussian elimination over the received symbols, taking O(L^3)
+    /// operations, so it is only cheap for small source blocks, unless
+    /// [`set_rank_tracking`](Self::set_rank_tracking) is enabled, which reduces it to O(L^2).
+    pub fn would_increase_rank(&self, packet: &EncodingPacket) -> bool {
+        assert_eq!(
+            self.source_block_id,
+            packet.payload_id.source_block_number()
+        );
+        let esi = packet.payload_id.encoding_symbol_id();
+        // Padding symbols are implicitly received from the start
+        if self.decoded || self.received_esi.contains(&esi) {
+            return false;
+        }
+
+        if let Some(ref basis) = self.rank_basis {
+            if basis.len() == num_intermediate_symbols(self.source_block_symbols) as usize {
+                return false;
+            }
+            let mut row = self.encoding_row(esi);
+            reduce_by_basis(basis, &mut row);
+            return row.iter().any(|&x| x != 0);
+        }
+        let mut basis = self.received_basis();
+        if basis.len() == num_intermediate_symbols(self.source_block_symbols) as usize {
+            return false;
+        }
+        extend_basis(&mut basis, self.encoding_row(esi))
+    }
+
+    /// Returns true if the block can be decoded from the symbols received so far, so that a
+    /// receiver knows exactly when to stop collecting symbols, rather than relying on the count
+    /// of received symbols.
+    ///
+    /// This runs the same solver as [`decode`](Self::decode) over the constraint matrix of the
+    /// received symbols, but with empty symbols, so only the matrix is eliminated: it is much
+    /// cheaper than decoding, and than [`would_increase_rank`](Self::would_increase_rank), but
+    /// does no work which a later call to `decode()` can reuse.
+    pub fn can_decode(&self) -> bool {
+        if self.decoded || self.received_source_symbols == self.source_block_symbols {
+            return true;
+        }
+        let num_extended_symbols = extended_source_block_symbols(self.source_block_symbols);
+        if (self.received_esi.len() as u32) < num_extended_symbols {
+            return false;
+        }
+
+        // The same rows, in the same order, as pi_decode()
+        let mut encoded_indices: Vec<u32> = (0..self.source_block_symbols)
+            .filter(|esi| self.received_esi.contains(esi))
+            .collect();
+        encoded_indices.extend(self.source_block_symbols..num_extended_symbols);
+        encoded_indices.extend_from_slice(&self.repair_esis);
+        self.is_solvable(&encoded_indices)
+    }
+
+    // Runs the solver over the constraint matrix of the given symbols, with empty symbols, to
+    // find out whether the matrix has full rank. See can_decode()
+    fn is_solvable(&self, encoded_indices: &[u32]) -> bool {
+        let s = num_ldpc_symbols(self.source_block_symbols) as usize;
+        let h = num_hdpc_symbols(self.source_block_symbols) as usize;
+        let symbols = vec![Symbol::zero(0usize); s + h + encoded_indices.len()];
+
+        if extended_source_block_symbols(self.source_block_symbols) >= self.sparse_threshold {
+            let (constraint_matrix, hdpc) = generate_constraint_matrix::<SparseBinaryMatrix>(
+                self.source_block_symbols,
+                encoded_indices,
+            );
+            IntermediateSymbolDecoder::new(
+                constraint_matrix,
+                hdpc,
+                symbols,
+                self.source_block_symbols,
+            )
+            .execute()
+            .0
+            .is_some()
+        } else {
+            let (constraint_matrix, hdpc) = generate_constraint_matrix::<DenseBinaryMatrix>(
+                self.source_block_symbols,
+                encoded_indices,
+            );
+            IntermediateSymbolDecoder::new(
+                constraint_matrix,
+                hdpc,
+                symbols,
+                self.source_block_symbols,
+            )
+            .execute()
+            .0
+            .is_some()
+        }
+    }
+
+    // Lower bound on the number of additional symbols required to decode the block.
+    // See Decoder::block_state()
+    fn symbols_needed(&self) -> u32 {
+        if self.decoded {
+            return 0;
+        }
+        let num_extended_symbols = extended_source_block_symbols(self.source_block_symbols);
+        let received = self.received_esi.len() as u32;
+        if received < num_extended_symbols {
+            return num_extended_symbols - received;
+        }
+        num_intermediate_symbols(self.source_block_symbols) - self.received_basis_rank()
+    }
+
+    // The number of linearly independent symbols among those received, if the block could not be
+    // decoded even though at least K symbols have been received
+    fn received_rank(&self) -> Option<u32> {
+        let num_extended_symbols = extended_source_block_symbols(self.source_block_symbols);
+        if self.decoded || (self.received_esi.len() as u32) < num_extended_symbols {
+            return None;
+        }
+        // The solver only reports the rank when it fails in the second phase
+        let rank = match self.constraint_rank {
+            Some(rank) => rank,
+            None => self.received_basis_rank(),
+        };
+        // The LDPC, HDPC and padding rows are always linearly independent, so the L - K rows
+        // which don't correspond to received symbols contribute exactly that much to the rank
+        let fixed_rows =
+            num_intermediate_symbols(self.source_block_symbols) - self.source_block_symbols;
+        Some(rank - fixed_rows)
+    }
+
+    // Rank of the constraint rows of the received symbols, including the L - K fixed rows
+    fn received_basis_rank(&self) -> u32 {
+        match self.rank_basis {
+            Some(ref basis) => basis.len() as u32,
+            None => self.received_basis().len() as u32,
+        }
+    }
+
+    // A basis for the rows of the constraint matrix of the received symbols, stopping early if
+    // it reaches full rank
+    fn received_basis(&self) -> Vec<(usize, Vec<u8>)> {
+        let intermediate_symbols = num_intermediate_symbols(self.source_block_symbols) as usize;
+        let mut basis = Vec::with_capacity(intermediate_symbols);
+        let received_rows = self.received_esi.iter().map(|&esi| self.encoding_row(esi));
+        for row in self
+            .fixed_constraint_rows()
+            .into_iter()
+            .chain(received_rows)
+        {
+            extend_basis(&mut basis, row);
+            if basis.len() == intermediate_symbols {
+                break;
+            }
+        }
+        basis
+    }
+
+    // The LDPC and HDPC rows of the constraint matrix, which don't depend on the received symbols,
+    // as dense GF(256) vectors
+    fn fixed_constraint_rows(&self) -> Vec<Vec<u8>> {
+        let s = num_ldpc_symbols(self.source_block_symbols) as usize;
+        let h = num_hdpc_symbols(self.source_block_symbols) as usize;
+        let l = num_intermediate_symbols(self.source_block_symbols) as usize;
+        // Any set of ESIs large enough to fill the matrix will do, since only the first S + H rows
+        // are used
+        let indices: Vec<u32> =
+            (0..extended_source_block_symbols(self.source_block_symbols)).collect();
+        let (matrix, hdpc) =
+            generate_constraint_matrix::<DenseBinaryMatrix>(self.source_block_symbols, &indices);
+        let mut rows = Vec::with_capacity(s + h);
+        for i in 0..s {
+            rows.push((0..l).map(|j| matrix.get(i, j).byte()).collect());
+        }
+        for i in 0..h {
+            rows.push((0..l).map(|j| hdpc.get(i, j).byte()).collect());
+        }
+        rows
+    }
+
+    // The row of the constraint matrix for the symbol with the given ESI, as a dense GF(256) vector
+    fn encoding_row(&self, esi: u32) -> Vec<u8> {
+        let lt_symbols = num_lt_symbols(self.source_block_symbols);
+        let pi_symbols = num_pi_symbols(self.source_block_symbols);
+        let sys_index = systematic_index(self.source_block_symbols);
+        let p1 = calculate_p1(self.source_block_symbols);
+        let mut row = vec![0; num_intermediate_symbols(self.source_block_symbols) as usize];
+        let tuple = intermediate_tuple(esi, lt_symbols, sys_index, p1);
+        for i in enc_indices(tuple, lt_symbols, pi_symbols, p1) {
+            row[i] = 1;
+        }
+        row
+    }
+
+    // ESIs of the symbols received so far, excluding the implicit padding symbols
+    fn received_symbol_ids(&self) -> HashSet<u32> {
+        let padding =
+            self.source_block_symbols..extended_source_block_symbols(self.source_block_symbols);
+        self.received_esi
+            .iter()
+            .filter(|esi| !padding.contains(esi))
+            .cloned()
+            .collect()
+    }
+
+    // The source symbol with the given ESI, if it has been received
+    fn source_symbol(&self, esi: usize) -> Option<Cow<'_, [u8]>> {
+        if self.received_esi.contains(&(esi as u32)) {
+            self.symbols.get(esi)
+        } else {
+            None
+        }
+    }
+
+    // Preallocates storage for the given number of received symbols
+    fn reserve(&mut self, symbols: u32) {
+        let repair_symbols = symbols.saturating_sub(self.source_block_symbols);
+        self.repair_esis.reserve(repair_symbols as usize);
+        self.symbols.reserve(symbols as usize);
+        self.received_esi
+            .reserve(symbols.saturating_sub(self.received_esi.len() as u32) as usize);
+    }
+
+    // The bytes at the start of the block covered by the source symbols received from ESI 0 up to
+    // the first missing one. See Decoder::available_prefix()
+    fn received_prefix(&self) -> Vec<u8> {
+        let received = (0..self.source_block_symbols)
+            .take_while(|&esi| self.received_esi.contains(&esi))
+            .count();
+        if received == self.source_block_symbols as usize {
+            let mut sink = self.block_sink();
+            for i in 0..received {
+                sink.write_symbol(
+                    self.source_block_id,
+                    i as u32,
+                    &self.source_symbol(i).unwrap(),
+                );
+            }
+            return sink.block;
+        }
+        // The first sub-block's sub-symbol is at the start of each symbol
+        let (tl, ts, nl, _) = partition(
+            (self.symbol_size / self.symbol_alignment as u16) as u32,
+            self.num_sub_blocks,
+        );
+        let sub_symbol_bytes =
+            if nl > 0 { tl } else { ts } as usize * self.symbol_alignment as usize;
+        let mut prefix = Vec::with_capacity(received * sub_symbol_bytes);
+        for i in 0..received {
+            prefix.extend_from_slice(&self.source_symbol(i).unwrap()[..sub_symbol_bytes]);
+        }
+        prefix
+    }
+
+    fn block_sink(&self) -> BlockSink {
+        BlockSink {
+            layout: sub_block_layout(self.symbol_size, self.num_sub_blocks, self.symbol_alignment),
+            block_symbols: self.source_block_symbols as usize,
+            block: vec![0; self.symbol_size as usize * self.source_block_symbols as usize],
+        }
+    }
+
+    fn try_pi_decode<D: DecodeSink + ?Sized>(
+        &mut self,
+        constraint_matrix: impl BinaryMatrix,
+        hdpc_rows: DenseOctetMatrix,
+        symbols: Vec<Symbol>,
+        encoded_indices: &[u32],
+        sink: &mut D,
+    ) -> bool {
+        let mut solver = IntermediateSymbolDecoder::new(
+            constraint_matrix,
+            hdpc_rows,
+            symbols,
+            self.source_block_symbols,
+        );
+        if let Some(limit) = self.operation_limit {
+            solver.set_operation_limit(limit);
+        }
+        // The first S + H rows are the LDPC and HDPC constraints, followed by a row for each of
+        // encoded_indices. The padding rows are always independent, like the constraint rows, so
+        // prefer them as pivots over the received symbols
+        let constraint_rows = (num_ldpc_symbols(self.source_block_symbols)
+            + num_hdpc_symbols(self.source_block_symbols)) as usize;
+        let num_extended_symbols = extended_source_block_symbols(self.source_block_symbols);
+        for (i, &esi) in encoded_indices.iter().enumerate() {
+            if esi >= self.source_block_symbols && esi < num_extended_symbols {
+                solver.prefer_row(constraint_rows + i);
+            }
+        }
+        let intermediate_symbols = solver.execute().0;
+        self.operation_limit_exceeded = solver.operation_limit_exceeded();
+        self.constraint_rank = solver.rank().map(|rank| rank as u32);
+        let intermediate_symbols = match intermediate_symbols {
+            Some(symbols) => symbols,
+            None => return false,
+        };
+        // Padding symbols weren't received, so they aren't reported
+        self.redundant_esis = solver
+            .redundant_rows()
+            .iter()
+            .filter(|&&row| row >= constraint_rows)
+            .map(|&row| encoded_indices[row - constraint_rows])
+            .filter(|&esi| esi < self.source_block_symbols || esi >= num_extended_symbols)
+            .collect();
+        self.redundant_esis.sort_unstable();
+
+        let lt_symbols = num_lt_symbols(self.source_block_symbols);
+        let pi_symbols = num_pi_symbols(self.source_block_symbols);
+        let sys_index = systematic_index(self.source_block_symbols);
+        let p1 = calculate_p1(self.source_block_symbols);
+        for i in 0..self.source_block_symbols as usize {
+            if let Some(symbol) = self.source_symbol(i) {
+                sink.write_symbol(self.source_block_id, i as u32, &symbol);
+            } else {
+                let rebuilt = self.rebuild_source_symbol(
                     &intermediate_symbols,
                     i as u32,
                     lt_symbols,
@@ -224,212 +1580,1585 @@ impl SourceBlockDecoder {
                     sys_index,
                     p1,
                 );
-                self.unpack_sub_blocks(&mut result, &rebuilt, i);
+                sink.write_symbol(self.source_block_id, i as u32, rebuilt.as_bytes());
+            }
+        }
+
+        #[cfg(feature = "verify")]
+        {
+            self.intermediate_symbols = Some(intermediate_symbols);
+        }
+        self.decoded = true;
+        return true;
+    }
+
+    /// Regenerates every received symbol, source and repair, from the intermediate symbols
+    /// recovered when the block was decoded, and checks that they match what was received. A
+    /// mismatch means that a received symbol was corrupted, or that the decoder is faulty.
+    ///
+    /// Returns false if the block has not been decoded. Blocks which were decoded without solving
+    /// for the intermediate symbols, because every source symbol was received, are reassembled
+    /// directly from the received symbols, so there is nothing to check and true is returned.
+    ///
+    /// Requires the `verify` feature, which keeps the L intermediate symbols of each block in
+    /// memory once it has been decoded.
+    #[cfg(feature = "verify")]
+    pub fn verify_block(&self) -> bool {
+        if !self.decoded {
+            return false;
+        }
+        let intermediate_symbols = match self.intermediate_symbols {
+            Some(ref symbols) => symbols,
+            None => return true,
+        };
+        let lt_symbols = num_lt_symbols(self.source_block_symbols);
+        let pi_symbols = num_pi_symbols(self.source_block_symbols);
+        let sys_index = systematic_index(self.source_block_symbols);
+        let p1 = calculate_p1(self.source_block_symbols);
+        let regenerate = |id: u32| {
+            self.rebuild_source_symbol(
+                intermediate_symbols,
+                id,
+                lt_symbols,
+                pi_symbols,
+                sys_index,
+                p1,
+            )
+        };
+        for i in 0..self.source_block_symbols {
+            if let Some(symbol) = self.source_symbol(i as usize) {
+                if regenerate(i).as_bytes() != &symbol[..] {
+                    return false;
+                }
+            }
+        }
+        for (i, &esi) in self.repair_esis.iter().enumerate() {
+            let symbol = self
+                .symbols
+                .get(self.source_block_symbols as usize + i)
+                .unwrap();
+            if regenerate(esi).as_bytes() != &symbol[..] {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Whether Decoder should attempt to decode this block, after ingesting a packet
+    fn should_attempt_decode(&self, strategy: DecodeStrategy) -> bool {
+        match strategy {
+            DecodeStrategy::Eager => true,
+            DecodeStrategy::OnThreshold(margin) => {
+                // received_esi also holds the padding ESIs, so this is K plus the margin
+                let threshold =
+                    extended_source_block_symbols(self.source_block_symbols) as u64 + margin as u64;
+                self.received_source_symbols == self.source_block_symbols
+                    || self.received_esi.len() as u64 >= threshold
+            }
+            DecodeStrategy::Manual => false,
+        }
+    }
+
+    fn ingest_packet(&mut self, packet: EncodingPacket) {
+        assert_eq!(
+            self.source_block_id,
+            packet.payload_id.source_block_number()
+        );
+
+        let (payload_id, mut payload) = packet.split();
+        if !self.valid_payload_length(payload_id.encoding_symbol_id(), payload.len()) {
+            self.invalid_length_ignored += 1;
+            return;
+        }
+        payload.truncate(self.symbol_size as usize);
+        let num_extended_symbols = extended_source_block_symbols(self.source_block_symbols);
+        if self.received_esi.insert(payload_id.encoding_symbol_id()) {
+            if let Some(mut basis) = self.rank_basis.take() {
+                if basis.len() < num_intermediate_symbols(self.source_block_symbols) as usize {
+                    extend_basis(
+                        &mut basis,
+                        self.encoding_row(payload_id.encoding_symbol_id()),
+                    );
+                }
+                self.rank_basis = Some(basis);
+            }
+            if payload_id.encoding_symbol_id() >= num_extended_symbols {
+                // Repair symbol
+                let index = self.source_block_symbols as usize + self.repair_esis.len();
+                self.symbols.put(index, payload);
+                self.repair_esis.push(payload_id.encoding_symbol_id());
+            } else {
+                // Check that this is not an extended symbol (which aren't explicitly sent)
+                assert!(payload_id.encoding_symbol_id() < self.source_block_symbols);
+                // Source symbol. The final one may have been sent without its zero padding
+                payload.resize(self.symbol_size as usize, 0);
+                self.symbols
+                    .put(payload_id.encoding_symbol_id() as usize, payload);
+                self.received_source_symbols += 1;
+            }
+        } else {
+            self.duplicates_ignored += 1;
+        }
+    }
+
+    // Writes out the received source symbols. Only valid once every source symbol has been
+    // received, in which case no matrix solve is required.
+    fn systematic_decode<D: DecodeSink + ?Sized>(&mut self, sink: &mut D) {
+        debug_assert_eq!(self.received_source_symbols, self.source_block_symbols);
+        for i in 0..self.source_block_symbols as usize {
+            let symbol = self.source_symbol(i).unwrap();
+            sink.write_symbol(self.source_block_id, i as u32, &symbol);
+        }
+
+        self.redundant_esis = self.repair_esis.clone();
+        self.redundant_esis.sort_unstable();
+        self.decoded = true;
+    }
+
+    pub fn decode<T: IntoIterator<Item = EncodingPacket>>(
+        &mut self,
+        packets: T,
+    ) -> Option<Vec<u8>> {
+        let mut sink = self.block_sink();
+        if self.decode_into(packets, &mut sink) {
+            Some(sink.block)
+        } else {
+            None
+        }
+    }
+
+    /// Like [`decode`](Self::decode), but writes the source symbols of the block to `sink`, rather
+    /// than returning the reassembled block. Returns true if the block was decoded, in which case
+    /// every source symbol has been written.
+    pub fn decode_into<T: IntoIterator<Item = EncodingPacket>, D: DecodeSink + ?Sized>(
+        &mut self,
+        packets: T,
+        sink: &mut D,
+    ) -> bool {
+        for packet in packets {
+            self.ingest_packet(packet);
+        }
+
+        // Fast path: with no loss of source symbols the block is a plain concatenation
+        if self.received_source_symbols == self.source_block_symbols {
+            self.systematic_decode(sink);
+            return true;
+        }
+
+        self.pi_decode(sink)
+    }
+
+    // Solves for the intermediate symbols and rebuilds any missing source symbols from them.
+    // See section 5.4.2
+    fn pi_decode<D: DecodeSink + ?Sized>(&mut self, sink: &mut D) -> bool {
+        let num_extended_symbols = extended_source_block_symbols(self.source_block_symbols);
+        if self.received_esi.len() as u32 >= num_extended_symbols {
+            let s = num_ldpc_symbols(self.source_block_symbols) as usize;
+            let h = num_hdpc_symbols(self.source_block_symbols) as usize;
+
+            let mut encoded_indices = vec![];
+            // See section 5.3.3.4.2. There are S + H zero symbols to start the D vector
+            let mut d = vec![Symbol::zero(self.symbol_size); s + h];
+            for i in 0..self.source_block_symbols as usize {
+                if let Some(symbol) = self.source_symbol(i) {
+                    encoded_indices.push(i as u32);
+                    d.push(Symbol::new(symbol.into_owned()));
+                }
+            }
+
+            // Append the extended padding symbols
+            for i in self.source_block_symbols..num_extended_symbols {
+                encoded_indices.push(i);
+                d.push(Symbol::zero(self.symbol_size));
+            }
+
+            for (i, &esi) in self.repair_esis.iter().enumerate() {
+                encoded_indices.push(esi);
+                let index = self.source_block_symbols as usize + i;
+                d.push(Symbol::new(self.symbols.get(index).unwrap().into_owned()));
+            }
+
+            if extended_source_block_symbols(self.source_block_symbols) >= self.sparse_threshold {
+                let (constraint_matrix, hdpc) = generate_constraint_matrix::<SparseBinaryMatrix>(
+                    self.source_block_symbols,
+                    &encoded_indices,
+                );
+                return self.try_pi_decode(constraint_matrix, hdpc, d, &encoded_indices, sink);
+            } else {
+                let (constraint_matrix, hdpc) = generate_constraint_matrix::<DenseBinaryMatrix>(
+                    self.source_block_symbols,
+                    &encoded_indices,
+                );
+                return self.try_pi_decode(constraint_matrix, hdpc, d, &encoded_indices, sink);
+            }
+        }
+        false
+    }
+
+    fn rebuild_source_symbol(
+        &self,
+        intermediate_symbols: &[Symbol],
+        source_symbol_id: u32,
+        lt_symbols: u32,
+        pi_symbols: u32,
+        sys_index: u32,
+        p1: u32,
+    ) -> Symbol {
+        let mut rebuilt = Symbol::zero(self.symbol_size);
+        let tuple = intermediate_tuple(source_symbol_id, lt_symbols, sys_index, p1);
+
+        for i in enc_indices(tuple, lt_symbols, pi_symbols, p1) {
+            rebuilt += &intermediate_symbols[i];
+        }
+        rebuilt
+    }
+}
+
+// Reduces `row` by the rows of `basis`, each of which is zero in the pivot columns of the rows
+// before it, and has a one in its own pivot column. The remainder is zero exactly if `row` is
+// linearly dependent on the basis.
+fn reduce_by_basis(basis: &[(usize, Vec<u8>)], row: &mut [u8]) {
+    for (pivot, basis_row) in basis.iter() {
+        let scalar = Octet::new(row[*pivot]);
+        if scalar == Octet::one() {
+            add_assign(row, basis_row);
+        } else if scalar != Octet::zero() {
+            fused_addassign_mul_scalar(row, basis_row, &scalar);
+        }
+    }
+}
+
+// Adds `row` to `basis` if it is linearly independent of it, returning true in that case
+fn extend_basis(basis: &mut Vec<(usize, Vec<u8>)>, mut row: Vec<u8>) -> bool {
+    reduce_by_basis(basis, &mut row);
+    match row.iter().position(|&x| x != 0) {
+        Some(pivot) => {
+            let scalar = Octet::one() / Octet::new(row[pivot]);
+            mulassign_scalar(&mut row, &scalar);
+            basis.push((pivot, row));
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod codec_tests {
+    use crate::estimate_decode_cost;
+    use crate::systematic_constants::{extended_source_block_symbols, num_intermediate_symbols};
+    use crate::SourceBlockEncoder;
+    use crate::{BlockState, DecodeError, DecodeStrategy, Decoder};
+    use crate::{Crc32, ObjectHasher, ParseError, TransferManifest};
+    use crate::{DecodeResult, DecodeSink, OverheadStats, VecSink};
+    use crate::{Encoder, EncoderBuilder, EncodingPacket};
+    use crate::{ObjectTransmissionInformation, SourceBlockDecoder};
+    use crate::{PayloadId, ValidationError};
+    use crate::{SourceBlockEncodingPlan, SymbolStore};
+    use rand::seq::SliceRandom;
+    use rand::Rng;
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::{fs, io, process};
+    use std::{
+        iter,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    fn gen_test_data(size: usize) -> Vec<u8> {
+        let mut data: Vec<u8> = vec![0; size];
+        for element in &mut data {
+            *element = rand::thread_rng().gen();
+        }
+        data
+    }
+    #[test]
+    fn random_erasure_dense() {
+        random_erasure(99_999);
+    }
+
+    #[test]
+    fn random_erasure_sparse() {
+        random_erasure(0);
+    }
+
+    fn random_erasure(sparse_threshold: u32) {
+        let elements: usize = rand::thread_rng().gen_range(1..1_000_000);
+        let data = gen_test_data(elements);
+
+        // MTU is set to not be too small, otherwise this test may take a very long time
+        let mtu = rand::thread_rng().gen_range(((elements / 100) as u16)..10_000);
+
+        let encoder = Encoder::with_defaults(&data, mtu);
+
+        let mut packets = encoder.get_encoded_packets(15);
+        packets.shuffle(&mut rand::thread_rng());
+        // Erase 10 packets at random
+        let length = packets.len();
+        packets.truncate(length - 10);
+
+        let mut decoder = Decoder::new(encoder.get_config());
+        decoder.set_sparse_threshold(sparse_threshold);
+
+        let mut result = None;
+        while !packets.is_empty() {
+            result = decoder.decode(packets.pop().unwrap());
+            if result.is_some() {
+                break;
+            }
+        }
+
+        assert_eq!(result.unwrap(), data);
+    }
+
+    #[test]
+    fn sub_block_erasure() {
+        let data = gen_test_data(10_000);
+
+        let mut builder = EncoderBuilder::new();
+        builder.set_decoder_memory_requirement(5000);
+        builder.set_max_packet_size(500);
+        let encoder = builder.build(&data);
+        assert!(encoder.get_config().sub_blocks() > 2);
+
+        // Test round trip
+        let mut decoder = Decoder::new(encoder.get_config());
+        let mut result = None;
+        for packet in encoder.get_encoded_packets(0) {
+            assert_eq!(result, None);
+            result = decoder.decode(packet);
+        }
+        assert_eq!(result.unwrap(), data);
+
+        // Test repair
+        let mut packets = encoder.get_encoded_packets(15);
+        packets.shuffle(&mut rand::thread_rng());
+        // Erase 10 packets at random
+        let length = packets.len();
+        packets.truncate(length - 10);
+
+        let mut decoder = Decoder::new(encoder.get_config());
+
+        let mut result = None;
+        while !packets.is_empty() {
+            result = decoder.decode(packets.pop().unwrap());
+            if result.is_some() {
+                break;
+            }
+        }
+
+        assert_eq!(result.unwrap(), data);
+    }
+
+    #[test]
+    fn systematic_fast_path() {
+        let elements: usize = 10_240;
+        let data = gen_test_data(elements);
+
+        for sub_blocks in [1, 4].iter() {
+            let config = ObjectTransmissionInformation::new(0, 64, 0, *sub_blocks, 8);
+            let encoder = SourceBlockEncoder::new2(1, &config, &data);
+
+            let mut decoder = SourceBlockDecoder::new2(1, &config, elements as u64);
+            for packet in encoder.source_packets() {
+                decoder.ingest_packet(packet);
+            }
+            let mut fast_path = decoder.block_sink();
+            decoder.systematic_decode(&mut fast_path);
+            let mut general_path = decoder.block_sink();
+            assert!(decoder.pi_decode(&mut general_path));
+
+            assert_eq!(fast_path.block, data);
+            assert_eq!(general_path.block, fast_path.block);
+        }
+    }
+
+    #[test]
+    fn decoder_with_capacity() {
+        let data = gen_test_data(10_000);
+
+        let encoder = Encoder::with_defaults(&data, 64);
+        let mut packets = encoder.get_encoded_packets(80);
+        packets.retain(|packet| packet.payload_id().encoding_symbol_id() % 4 != 0);
+
+        // Hint at fewer symbols than will arrive, to exercise growth past the hint
+        let mut decoder = Decoder::with_capacity(encoder.get_config(), 20);
+        let mut result = None;
+        for packet in packets {
+            result = decoder.decode(packet);
+            if result.is_some() {
+                break;
+            }
+        }
+
+        assert_eq!(result.unwrap(), data);
+    }
+
+    #[test]
+    fn truncate_oversized_packets() {
+        let data = gen_test_data(10_000);
+
+        let encoder = Encoder::with_defaults(&data, 64);
+        let mut decoder = Decoder::new(encoder.get_config());
+        decoder.set_truncate_oversized_packets(true);
+        let mut result = None;
+        for packet in encoder.get_encoded_packets(50) {
+            if packet.payload_id().encoding_symbol_id() % 5 == 0 {
+                continue;
+            }
+            // Pad every packet to a fixed 80 byte frame
+            let (payload_id, mut payload) = packet.split();
+            payload.resize(80, 0xFF);
+            result = decoder.decode(EncodingPacket::new(payload_id, payload));
+            if result.is_some() {
+                break;
+            }
+        }
+
+        assert_eq!(result.unwrap(), data);
+    }
+
+    #[test]
+    fn repair_esi_range() {
+        let data = vec![0; 25 * 64];
+        let config = ObjectTransmissionInformation::new(0, 64, 0, 1, 1);
+        let encoder = SourceBlockEncoder::new2(1, &config, &data);
+        let decoder = SourceBlockDecoder::new2(1, &config, data.len() as u64);
+        // K = 25, K' = 26
+        let range = decoder.repair_esi_range();
+        assert_eq!(range, encoder.repair_esi_range());
+        assert!(!range.contains(&25));
+        assert!(range.contains(&26));
+        assert!(!range.contains(&16777216));
+    }
+
+    #[test]
+    fn decode_into_sink() {
+        struct CountingSink {
+            symbols: Vec<(u8, u32)>,
+            data: VecSink,
+        }
+        impl DecodeSink for CountingSink {
+            fn write_symbol(&mut self, sbn: u8, esi: u32, data: &[u8]) {
+                assert_eq!(data.len(), 16);
+                self.symbols.push((sbn, esi));
+                self.data.write_symbol(sbn, esi, data);
+            }
+        }
+
+        let data = gen_test_data(1000);
+        for sub_blocks in [1, 2].iter() {
+            // Two blocks of 32 and 31 symbols
+            let config = ObjectTransmissionInformation::new(1000, 16, 2, *sub_blocks, 4);
+            let encoder = Encoder::new(&data, config);
+            let mut decoder = Decoder::new(config);
+            let mut sink = CountingSink {
+                symbols: vec![],
+                data: VecSink::new(config),
+            };
+            let mut packets = encoder.get_encoded_packets(20);
+            // Lose some source packets of the first block, so that it must be solved
+            packets.retain(|packet| {
+                let payload_id = packet.payload_id();
+                payload_id.source_block_number() != 0 || payload_id.encoding_symbol_id() % 4 != 1
+            });
+
+            // Packets which arrive once their block has been written are ignored
+            let mut complete = false;
+            for packet in packets {
+                complete = decoder.decode_into(packet, &mut sink);
+            }
+            assert!(complete);
+            assert_eq!(decoder.block_state(0), BlockState::Decoded);
+            assert_eq!(decoder.get_result(), None);
+
+            let expected: Vec<(u8, u32)> = (0..32)
+                .map(|esi| (0, esi))
+                .chain((0..31).map(|esi| (1, esi)))
+                .collect();
+            assert_eq!(sink.symbols, expected);
+            assert_eq!(sink.data.into_vec(), data);
+        }
+    }
+
+    #[test]
+    fn estimated_peak_memory() {
+        // K = 13, so K' = 18, S = 11 and H = 10, giving L = 39
+        let config = ObjectTransmissionInformation::new(1300, 100, 1, 1, 4);
+        let decoder = Decoder::new(config);
+        assert_eq!(
+            decoder.estimated_peak_memory(),
+            39 * 100 + 1300 + 39 * 100 + 2 * 39 * 39 / 8
+        );
+
+        let small = Decoder::new(ObjectTransmissionInformation::new(1_000, 100, 1, 1, 4));
+        let large = Decoder::new(ObjectTransmissionInformation::new(1_000_000, 100, 1, 1, 4));
+        assert!(large.estimated_peak_memory() > 1_000_000);
+        assert!(large.estimated_peak_memory() > 100 * small.estimated_peak_memory());
+    }
+
+    #[test]
+    fn available_prefix() {
+        let data = gen_test_data(1000);
+        // Two blocks of 32 and 31 symbols, with 8 bytes of padding at the end of the second
+        let config = ObjectTransmissionInformation::new(1000, 16, 2, 1, 1);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+        let packets: Vec<Vec<EncodingPacket>> = encoder
+            .get_block_encoders()
+            .iter()
+            .map(|block| block.source_packets())
+            .collect();
+
+        assert!(decoder.available_prefix(0).is_empty());
+        for i in [0, 1, 2, 3, 4, 7].iter() {
+            decoder.add_new_packet(packets[0][*i].clone());
+        }
+        assert_eq!(decoder.available_prefix(0), &data[..16 * 5]);
+        decoder.add_new_packet(packets[0][5].clone());
+        decoder.add_new_packet(packets[0][6].clone());
+        assert_eq!(decoder.available_prefix(0), &data[..16 * 8]);
+
+        for packet in packets[1].iter() {
+            decoder.add_new_packet(packet.clone());
+        }
+        assert_eq!(decoder.block_state(1), BlockState::Decoded);
+        assert_eq!(decoder.available_prefix(1), &data[16 * 32..]);
+
+        // The first of the two sub-blocks holds the first 8 bytes of each 16 byte symbol
+        let config = ObjectTransmissionInformation::new(1000, 16, 1, 2, 4);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+        let packets = encoder.get_block_encoders()[0].source_packets();
+        for packet in packets[..10].iter() {
+            decoder.add_new_packet(packet.clone());
+        }
+        assert_eq!(decoder.available_prefix(0), &data[..8 * 10]);
+        for packet in packets[10..].iter() {
+            decoder.add_new_packet(packet.clone());
+        }
+        assert_eq!(decoder.available_prefix(0), data);
+    }
+
+    #[test]
+    fn decode_rank_deficient() {
+        let data = vec![5; 640];
+        let encoder = Encoder::with_defaults(&data, 64);
+        let block_encoder = &encoder.get_block_encoders()[0];
+        // K = K' = 10. Search for a set of K repair symbols which are linearly dependent
+        let mut start = 0;
+        loop {
+            let mut decoder = Decoder::new(encoder.get_config());
+            decoder.set_decode_strategy(DecodeStrategy::Manual);
+            for packet in block_encoder.repair_packets(start, 10) {
+                decoder.add_new_packet(packet);
+            }
+            match decoder.decode_with_limit(u64::MAX) {
+                Ok(result) => assert_eq!(result, data),
+                Err(DecodeError::RankDeficient { received, rank }) => {
+                    assert_eq!(received, 10);
+                    assert!(rank < 10);
+                    // The rank reported by the solver matches a dense elimination, excluding the
+                    // L - K rows of the constraint matrix which aren't received symbols
+                    let basis = decoder.block_decoders[0].received_basis();
+                    assert_eq!(
+                        basis.len() as u32 - (num_intermediate_symbols(10) - 10),
+                        rank
+                    );
+                    for packet in block_encoder.repair_packets(start + 10, 10 - rank + 5) {
+                        decoder.add_new_packet(packet);
+                    }
+                    assert_eq!(decoder.decode_with_limit(u64::MAX), Ok(data));
+                    return;
+                }
+                Err(error) => panic!("{}", error),
+            }
+            start += 10;
+        }
+    }
+
+    #[test]
+    fn decode_operation_limit() {
+        let data = gen_test_data(10_000);
+
+        let encoder = Encoder::with_defaults(&data, 64);
+        let mut packets = encoder.get_encoded_packets(80);
+        packets.retain(|packet| packet.payload_id().encoding_symbol_id() % 4 != 0);
+
+        let mut limited = Decoder::new(encoder.get_config());
+        limited.set_decode_strategy(DecodeStrategy::Manual);
+        let mut unlimited = Decoder::new(encoder.get_config());
+        assert_eq!(
+            limited.decode_with_limit(10),
+            Err(DecodeError::InsufficientSymbols)
+        );
+        for packet in packets {
+            limited.add_new_packet(packet.clone());
+            unlimited.add_new_packet(packet);
+        }
+        assert_eq!(limited.get_result(), None);
+        assert_eq!(
+            limited.decode_with_limit(10),
+            Err(DecodeError::BudgetExceeded)
+        );
+        // The limit does not outlive the call
+        assert_eq!(limited.try_decode(), Some(data.clone()));
+        assert_eq!(unlimited.get_result(), Some(data));
+    }
+
+    #[test]
+    fn decode_operation_limit_margin() {
+        // Successful solves need fewer than 100 operations per K', as documented
+        for &symbols in [10, 100, 300].iter() {
+            let data = vec![7; symbols * 8];
+            let config = ObjectTransmissionInformation::new(data.len() as u64, 8, 1, 1, 8);
+            let encoder = Encoder::new(&data, config);
+            let mut packets = encoder.get_encoded_packets(symbols as u32 / 2 + 10);
+            packets.retain(|packet| packet.payload_id().encoding_symbol_id() % 3 != 0);
+            let mut decoder = Decoder::new(config);
+            decoder.set_decode_strategy(DecodeStrategy::Manual);
+            for packet in packets {
+                decoder.add_new_packet(packet);
+            }
+            let kprime = extended_source_block_symbols(symbols as u32) as u64;
+            assert_eq!(decoder.decode_with_limit(100 * kprime), Ok(data));
+        }
+    }
+
+    #[test]
+    fn duplicate_packets() {
+        let data = gen_test_data(10_000);
+
+        let encoder = Encoder::with_defaults(&data, 64);
+        let mut packets = encoder.get_encoded_packets(20);
+        // Drop one source symbol, so that decoding requires repair symbols
+        packets.remove(1);
+        let mut decoder = Decoder::new(encoder.get_config());
+        for packet in packets.iter() {
+            decoder.add_new_packet(packet.clone());
+            decoder.add_new_packet(packet.clone());
+            if decoder.get_result().is_some() {
+                break;
+            }
+        }
+        assert_eq!(decoder.get_result(), Some(data));
+        // The first repair symbol completes the block, so its duplicate is never ingested
+        assert_eq!(decoder.duplicates_ignored(), packets.len() as u64 - 20);
+    }
+
+    #[test]
+    fn missing_source_esis() {
+        let elements: usize = 10_000;
+        let data = gen_test_data(elements);
+
+        // K = 79 in block 0 and 78 in block 1
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let packets = encoder.get_encoded_packets(0);
+        let lost = |packet: &EncodingPacket| {
+            let payload_id = packet.payload_id();
+            payload_id.source_block_number() == 0
+                && [3, 10, 78].contains(&payload_id.encoding_symbol_id())
+        };
+        let mut decoder = Decoder::new(config);
+        for packet in packets.iter().filter(|packet| !lost(packet)) {
+            decoder.add_new_packet(packet.clone());
+        }
+        assert_eq!(decoder.missing_source_esis(0), vec![3, 10, 78]);
+        assert!(decoder.missing_source_esis(1).is_empty());
+        assert_eq!(decoder.get_result(), None);
+
+        for packet in packets.iter().filter(|packet| lost(packet)) {
+            decoder.add_new_packet(packet.clone());
+        }
+        assert!(decoder.missing_source_esis(0).is_empty());
+        assert_eq!(decoder.get_result(), Some(data));
+    }
+
+    #[test]
+    fn add_symbol_at_offset() {
+        let data = gen_test_data(1000);
+        // Two blocks of 32 and 31 symbols, with 8 bytes of padding at the end of the second
+        let config = ObjectTransmissionInformation::new(1000, 16, 2, 1, 1);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+
+        assert_eq!(
+            decoder.add_symbol_at_offset(17, &data[17..33]),
+            Err(ValidationError::InvalidOffset(17))
+        );
+        assert_eq!(
+            decoder.add_symbol_at_offset(1008, &[]),
+            Err(ValidationError::InvalidOffset(1008))
+        );
+        assert_eq!(
+            decoder.add_symbol_at_offset(16, &data[16..40]),
+            Err(ValidationError::InvalidLength {
+                payload_id: PayloadId::new(0, 1),
+                length: 24
+            })
+        );
+
+        // Symbol 40 of the object is ESI 8 of the second block. Lose it and symbol 3, and replace
+        // them with repair symbols.
+        for offset in (0..1000).step_by(16) {
+            if offset == 3 * 16 || offset == 40 * 16 {
+                continue;
+            }
+            let end = (offset + 16).min(1000);
+            assert_eq!(
+                decoder.add_symbol_at_offset(offset as u64, &data[offset..end]),
+                Ok(None)
+            );
+        }
+        assert_eq!(decoder.block_state(1), BlockState::NeedMore(1));
+        let blocks = encoder.get_block_encoders();
+        assert_eq!(
+            decoder.decode(blocks[0].repair_packets(0, 1).remove(0)),
+            None
+        );
+        assert_eq!(
+            decoder.decode(blocks[1].repair_packets(0, 1).remove(0)),
+            Some(data.clone())
+        );
+
+        // Sub-blocked symbols are not contiguous in the object
+        let config = ObjectTransmissionInformation::new(1000, 16, 1, 2, 4);
+        assert_eq!(
+            Decoder::new(config).add_symbol_at_offset(0, &data[..16]),
+            Err(ValidationError::InvalidOffset(0))
+        );
+    }
+
+    #[test]
+    fn can_decode() {
+        let elements: usize = 1_000;
+        let data = gen_test_data(elements);
+
+        // K = 16, K' = 18
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 1, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        // Drop the odd source symbols, so that decoding requires repair symbols
+        let packets: Vec<EncodingPacket> = encoder
+            .get_encoded_packets(30)
+            .into_iter()
+            .filter(|packet| {
+                let esi = packet.payload_id().encoding_symbol_id();
+                esi >= 16 || esi % 2 == 0
+            })
+            .collect();
+
+        // Never try to decode while ingesting, so that can_decode() is checked against decode()
+        let mut decoder = Decoder::new(config);
+        decoder.set_decode_strategy(DecodeStrategy::Manual);
+        for packet in packets {
+            decoder.add_new_packet(packet);
+            let mut probe = decoder.clone();
+            let can_decode = decoder.can_decode(0);
+            assert_eq!(can_decode, probe.try_decode().is_some());
+            if can_decode {
+                assert_eq!(decoder.try_decode(), Some(data));
+                assert!(decoder.can_decode(0));
+                return;
             }
         }
+        panic!("never decodable");
+    }
 
-        self.decoded = true;
-        return Some(result);
+    #[test]
+    fn would_increase_rank() {
+        let elements: usize = 1_000;
+        let data = gen_test_data(elements);
+
+        // K = 16, K' = 18
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 1, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        // Drop the odd source symbols, so that decoding requires repair symbols
+        let packets: Vec<EncodingPacket> = encoder
+            .get_encoded_packets(30)
+            .into_iter()
+            .filter(|packet| {
+                let esi = packet.payload_id().encoding_symbol_id();
+                esi >= 16 || esi % 2 == 0
+            })
+            .collect();
+
+        let mut decoder = Decoder::new(config);
+        assert!(decoder.would_increase_rank(&packets[0]));
+        decoder.add_new_packet(packets[0].clone());
+        assert!(!decoder.would_increase_rank(&packets[0]));
+
+        // Together with the S + H constraint rows and the K' - K padding symbols, K independent
+        // symbols reach full rank
+        let mut independent = 1;
+        for packet in packets[1..].iter() {
+            if decoder.would_increase_rank(packet) {
+                assert_eq!(decoder.get_result(), None);
+                decoder.add_new_packet(packet.clone());
+                independent += 1;
+            }
+            if independent == 16 {
+                break;
+            }
+        }
+        assert_eq!(independent, 16);
+        assert_eq!(decoder.get_result(), Some(data));
+        assert!(!decoder.would_increase_rank(&packets[packets.len() - 1]));
     }
 
-    pub fn decode<T: IntoIterator<Item = EncodingPacket>>(
-        &mut self,
-        packets: T,
-    ) -> Option<Vec<u8>> {
-        for packet in packets {
+    #[test]
+    fn rank_tracking() {
+        let elements: usize = 1_000;
+        let data = gen_test_data(elements);
+
+        // K = 16, K' = 18
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 1, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let packets: Vec<EncodingPacket> = encoder
+            .get_encoded_packets(30)
+            .into_iter()
+            .filter(|packet| packet.payload_id().encoding_symbol_id() % 3 != 0)
+            .collect();
+
+        // The tracked basis gives the same answers as eliminating the received symbols again
+        let mut tracked = Decoder::new(config);
+        tracked.set_decode_strategy(DecodeStrategy::Manual);
+        let mut untracked = tracked.clone();
+        tracked.add_new_packet(packets[0].clone());
+        untracked.add_new_packet(packets[0].clone());
+        tracked.set_rank_tracking(true);
+        for packet in packets[1..].iter() {
             assert_eq!(
-                self.source_block_id,
-                packet.payload_id.source_block_number()
+                tracked.would_increase_rank(packet),
+                untracked.would_increase_rank(packet)
             );
+            assert_eq!(tracked.block_state(0), untracked.block_state(0));
+            tracked.add_new_packet(packet.clone());
+            untracked.add_new_packet(packet.clone());
+        }
+        assert_eq!(tracked.block_state(0), BlockState::NeedMore(0));
+        assert_eq!(tracked.try_decode(), Some(data));
+    }
 
-            let (payload_id, payload) = packet.split();
-            let num_extended_symbols = extended_source_block_symbols(self.source_block_symbols);
-            if self.received_esi.insert(payload_id.encoding_symbol_id()) {
-                if payload_id.encoding_symbol_id() >= num_extended_symbols {
-                    // Repair symbol
-                    self.repair_packets
-                        .push(EncodingPacket::new(payload_id, payload));
-                } else {
-                    // Check that this is not an extended symbol (which aren't explicitly sent)
-                    assert!(payload_id.encoding_symbol_id() < self.source_block_symbols);
-                    // Source symbol
-                    self.source_symbols[payload_id.encoding_symbol_id() as usize] =
-                        Some(Symbol::new(payload));
-                    self.received_source_symbols += 1;
-                }
-            }
+    #[test]
+    fn block_states() {
+        let elements: usize = 1_000;
+        let data = gen_test_data(elements);
+
+        // K = 16, K' = 18
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 1, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut packets = encoder.get_encoded_packets(10);
+        packets.retain(|packet| packet.payload_id().encoding_symbol_id() % 3 != 0);
+
+        let mut decoder = Decoder::new(config);
+        assert_eq!(decoder.block_state(0), BlockState::NeedMore(16));
+        for packet in packets[..6].iter() {
+            decoder.add_new_packet(packet.clone());
         }
+        assert_eq!(decoder.block_state(0), BlockState::NeedMore(10));
+        decoder.set_transmission_complete(true);
+        assert_eq!(decoder.block_state(0), BlockState::Undecodable);
+        decoder.set_transmission_complete(false);
+
+        // With enough symbols, but the solve aborted, nothing more is needed
+        decoder.set_decode_strategy(DecodeStrategy::Manual);
+        for packet in packets[6..].iter() {
+            decoder.add_new_packet(packet.clone());
+        }
+        assert_eq!(
+            decoder.decode_with_limit(1),
+            Err(DecodeError::BudgetExceeded)
+        );
+        assert_eq!(decoder.get_result(), None);
+        assert_eq!(decoder.block_state(0), BlockState::NeedMore(0));
+        decoder.set_transmission_complete(true);
+        assert_eq!(decoder.block_state(0), BlockState::NeedMore(0));
 
-        let num_extended_symbols = extended_source_block_symbols(self.source_block_symbols);
-        if self.received_source_symbols == self.source_block_symbols {
-            let mut result =
-                vec![0; self.symbol_size as usize * self.source_block_symbols as usize];
-            for (i, symbol) in self.source_symbols.iter().enumerate() {
-                self.unpack_sub_blocks(&mut result, symbol.as_ref().unwrap(), i);
+        assert_eq!(decoder.decode_with_limit(u64::MAX), Ok(data));
+        assert_eq!(decoder.block_state(0), BlockState::Decoded);
+    }
+
+    #[cfg(feature = "allocator_api")]
+    #[test]
+    fn matrix_allocator() {
+        use crate::with_matrix_allocator;
+        use std::alloc::{AllocError, Allocator, Global, Layout};
+        use std::ptr::NonNull;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct CountingAllocator(AtomicUsize);
+
+        unsafe impl Allocator for CountingAllocator {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Global.allocate(layout)
             }
 
-            self.decoded = true;
-            return Some(result);
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                Global.deallocate(ptr, layout)
+            }
         }
 
-        if self.received_esi.len() as u32 >= num_extended_symbols {
-            let s = num_ldpc_symbols(self.source_block_symbols) as usize;
-            let h = num_hdpc_symbols(self.source_block_symbols) as usize;
+        static ALLOCATOR: CountingAllocator = CountingAllocator(AtomicUsize::new(0));
 
-            let mut encoded_indices = vec![];
-            // See section 5.3.3.4.2. There are S + H zero symbols to start the D vector
-            let mut d = vec![Symbol::zero(self.symbol_size); s + h];
-            for (i, source) in self.source_symbols.iter().enumerate() {
-                if let Some(symbol) = source {
-                    encoded_indices.push(i as u32);
-                    d.push(symbol.clone());
+        let data = gen_test_data(10_000);
+
+        let encoder = Encoder::with_defaults(&data, 64);
+        let mut packets = encoder.get_encoded_packets(20);
+        // Drop a source symbol, so that decoding requires a matrix solve
+        packets.remove(0);
+        let mut decoder = Decoder::new(encoder.get_config());
+        let result = with_matrix_allocator(&ALLOCATOR, || {
+            let mut result = None;
+            for packet in packets {
+                result = decoder.decode(packet);
+                if result.is_some() {
+                    break;
                 }
             }
+            result
+        });
+        assert_eq!(result, Some(data));
+        assert!(ALLOCATOR.0.load(Ordering::SeqCst) > 0);
+    }
 
-            // Append the extended padding symbols
-            for i in self.source_block_symbols..num_extended_symbols {
-                encoded_indices.push(i);
-                d.push(Symbol::zero(self.symbol_size));
-            }
+    #[test]
+    fn decode_results() {
+        let elements: usize = 10_000;
+        let data = gen_test_data(elements);
 
-            for repair_packet in self.repair_packets.iter() {
-                encoded_indices.push(repair_packet.payload_id.encoding_symbol_id());
-                d.push(Symbol::new(repair_packet.data.clone()));
+        // K = 79 in block 0 and 78 in block 1
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut packets = encoder.get_encoded_packets(20);
+        packets.retain(|packet| packet.payload_id().encoding_symbol_id() % 10 != 0);
+
+        let mut decoder = Decoder::new(config);
+        let mut received = 0;
+        for packet in packets {
+            assert_eq!(decoder.decode_result(), None);
+            let block = packet.payload_id().source_block_number();
+            if decoder.block_state(block) != BlockState::Decoded {
+                received += 1;
+            }
+            if decoder.decode(packet).is_some() {
+                break;
             }
+        }
+        let result = decoder.decode_result().unwrap();
+        assert_eq!(result.source_symbols(), 157);
+        assert_eq!(result.received_symbols(), received);
+        assert_eq!(result.overhead_symbols(), received - 157);
+
+        let mut stats = OverheadStats::new();
+        assert_eq!(stats.recommended_overhead(), None);
+        stats.record(&result);
+        assert_eq!(stats.recommended_overhead(), Some(result.overhead()));
+        for &received_symbols in [100, 101, 104].iter() {
+            stats.record(&DecodeResult {
+                source_symbols: 100,
+                received_symbols,
+                redundant_esis: vec![],
+            });
+        }
+        assert_eq!(stats.decodes(), 4);
+        assert_eq!(stats.min_overhead(), Some(0.0));
+        assert_eq!(stats.max_overhead(), Some(result.overhead().max(0.04)));
+        assert_eq!(stats.recommended_overhead(), stats.max_overhead());
+        let mean = (result.overhead() + 0.05) / 4.0;
+        assert!((stats.mean_overhead().unwrap() - mean).abs() < 1e-12);
+    }
 
-            if extended_source_block_symbols(self.source_block_symbols) >= self.sparse_threshold {
-                let (constraint_matrix, hdpc) = generate_constraint_matrix::<SparseBinaryMatrix>(
-                    self.source_block_symbols,
-                    &encoded_indices,
-                );
-                return self.try_pi_decode(constraint_matrix, hdpc, d);
-            } else {
-                let (constraint_matrix, hdpc) = generate_constraint_matrix::<DenseBinaryMatrix>(
-                    self.source_block_symbols,
-                    &encoded_indices,
-                );
-                return self.try_pi_decode(constraint_matrix, hdpc, d);
+    #[test]
+    fn redundant_esis() {
+        let elements: usize = 10_000;
+        let data = gen_test_data(elements);
+
+        // K = 79 in block 0 and 78 in block 1
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut packets = encoder.get_encoded_packets(20);
+        // Block 0 has to be solved, while block 1 is decoded from its source symbols alone
+        packets.retain(|packet| {
+            let payload_id = packet.payload_id();
+            payload_id.source_block_number() != 0 || payload_id.encoding_symbol_id() % 10 != 0
+        });
+
+        let mut decoder = Decoder::new(config);
+        decoder.set_decode_strategy(DecodeStrategy::Manual);
+        decoder.add_packets(packets.clone());
+        assert_eq!(decoder.try_decode(), Some(data.clone()));
+        let result = decoder.decode_result().unwrap();
+        let redundant = result.redundant_esis();
+        let (block0, block1): (Vec<&PayloadId>, Vec<&PayloadId>) = redundant
+            .iter()
+            .partition(|payload_id| payload_id.source_block_number() == 0);
+        // K' = 84 for both blocks, so 71 source and 18 repair symbols were received for block 0,
+        // 10 more than K. The list is only a lower bound on the redundant symbols, since the
+        // solver may use a received symbol in place of an LDPC or HDPC row
+        assert!(!block0.is_empty());
+        assert!(block0.len() <= 71 + 18 - 79);
+        let repair_esis: Vec<u32> = (84..104).collect();
+        let block1: Vec<u32> = block1
+            .iter()
+            .map(|payload_id| payload_id.encoding_symbol_id())
+            .collect();
+        assert_eq!(block1, repair_esis);
+
+        // The block still decodes without the redundant symbols
+        packets.retain(|packet| !redundant.contains(packet.payload_id()));
+        let mut decoder = Decoder::new(config);
+        assert_eq!(decoder.add_packets(packets), Some(data));
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let elements: usize = 10_000;
+        let data = gen_test_data(elements);
+
+        let path = std::env::temp_dir().join(format!("raptorq-{}-file_round_trip", process::id()));
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        encoder.encode_to_file(&path, 5).unwrap();
+        let serialized = fs::read(&path).unwrap();
+        // 157 source and 10 repair packets, each with a 4 byte length and 4 byte payload ID prefix
+        assert_eq!(serialized.len(), 167 * (8 + 64));
+        assert_eq!(serialized[..4], [0, 0, 0, 68]);
+
+        let mut decoder = Decoder::new(config);
+        assert_eq!(decoder.decode_from_file(&path).unwrap(), Some(data));
+
+        // Only the repair packets of the last block remain, which isn't enough
+        fs::write(&path, &serialized[serialized.len() - 5 * 72..]).unwrap();
+        let mut decoder = Decoder::new(config);
+        assert_eq!(decoder.decode_from_file(&path).unwrap(), None);
+
+        fs::write(&path, &serialized[..10]).unwrap();
+        let mut decoder = Decoder::new(config);
+        let error = decoder.decode_from_file(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        // Malformed packets are rejected, rather than causing a panic or a huge allocation: a too
+        // short length, a length longer than any packet, and a packet for a missing block
+        let mut missing_block = vec![0, 0, 0, 68, 9, 0, 0, 0];
+        missing_block.extend_from_slice(&[0; 64]);
+        let malformed: [&[u8]; 3] = [&[0, 0, 0, 3, 1, 2, 3], &[0xFF; 8], &missing_block];
+        for contents in malformed.iter() {
+            fs::write(&path, contents).unwrap();
+            let mut decoder = Decoder::new(config);
+            let error = decoder.decode_from_file(&path).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn validate_packets() {
+        let data = vec![1; 10_000];
+        // K = 157, K' = 160
+        let config = ObjectTransmissionInformation::new(10_000, 64, 1, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+        let mut packets = encoder.get_encoded_packets(5);
+        assert_eq!(decoder.validate(&packets), Ok(()));
+        packets.push(packets[0].clone());
+        assert_eq!(decoder.validate(&packets), Ok(()));
+
+        let check = |packet: EncodingPacket, decoder: &Decoder| {
+            let mut packets = encoder.get_encoded_packets(0);
+            packets.push(packet);
+            decoder.validate(&packets)
+        };
+        assert_eq!(
+            check(
+                EncodingPacket::new(PayloadId::new(1, 0), vec![0; 64]),
+                &decoder
+            ),
+            Err(ValidationError::InvalidSourceBlock(PayloadId::new(1, 0)))
+        );
+        assert_eq!(
+            check(
+                EncodingPacket::new(PayloadId::new(0, 158), vec![0; 64]),
+                &decoder
+            ),
+            Err(ValidationError::PaddingSymbol(PayloadId::new(0, 158)))
+        );
+        assert_eq!(
+            check(
+                EncodingPacket::new(PayloadId::new(0, 160), vec![0; 60]),
+                &decoder
+            ),
+            Err(ValidationError::InvalidLength {
+                payload_id: PayloadId::new(0, 160),
+                length: 60
+            })
+        );
+        assert_eq!(
+            check(
+                EncodingPacket::new(PayloadId::new(0, 0), vec![1; 80]),
+                &decoder
+            ),
+            Err(ValidationError::InvalidLength {
+                payload_id: PayloadId::new(0, 0),
+                length: 80
+            })
+        );
+        assert_eq!(
+            check(
+                EncodingPacket::new(PayloadId::new(0, 3), vec![0; 64]),
+                &decoder
+            ),
+            Err(ValidationError::ConflictingPayload(PayloadId::new(0, 3)))
+        );
+
+        decoder.set_truncate_oversized_packets(true);
+        assert_eq!(
+            check(
+                EncodingPacket::new(PayloadId::new(0, 160), vec![0; 80]),
+                &decoder
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn short_source_payloads() {
+        let data: Vec<u8> = (0..10_000).map(|i| (i % 251) as u8).collect();
+        // K = 157, and the final source symbol holds 16 bytes followed by 48 bytes of padding
+        let config = ObjectTransmissionInformation::new(10_000, 64, 1, 1, 8);
+        let source = Encoder::new(&data, config).get_block_encoders()[0].source_packets();
+        let decoder = Decoder::new(config);
+        let check = |esi: u32, length: usize| {
+            decoder.validate(&[EncodingPacket::new(PayloadId::new(0, esi), vec![1; length])])
+        };
+        assert_eq!(check(156, 16), Ok(()));
+        for &(esi, length) in [(0, 0), (0, 16), (155, 16), (156, 0), (156, 20)].iter() {
+            assert_eq!(
+                check(esi, length),
+                Err(ValidationError::InvalidLength {
+                    payload_id: PayloadId::new(0, esi),
+                    length
+                })
+            );
+        }
+
+        // A truncated packet is discarded, rather than padded with zeros, so the symbol is only
+        // received once it arrives in full
+        let mut decoder = Decoder::new(config);
+        assert_eq!(
+            decoder.decode(EncodingPacket::new(PayloadId::new(0, 0), vec![])),
+            None
+        );
+        assert_eq!(decoder.invalid_length_ignored(), 1);
+        let mut result = None;
+        for mut packet in source {
+            if packet.payload_id().encoding_symbol_id() == 156 {
+                packet =
+                    EncodingPacket::new(packet.payload_id().clone(), packet.data()[..16].to_vec());
             }
+            result = decoder.decode(packet);
         }
-        None
+        assert_eq!(result, Some(data));
+        assert_eq!(decoder.invalid_length_ignored(), 1);
     }
 
-    fn rebuild_source_symbol(
-        &self,
-        intermediate_symbols: &[Symbol],
-        source_symbol_id: u32,
-        lt_symbols: u32,
-        pi_symbols: u32,
-        sys_index: u32,
-        p1: u32,
-    ) -> Symbol {
-        let mut rebuilt = Symbol::zero(self.symbol_size);
-        let tuple = intermediate_tuple(source_symbol_id, lt_symbols, sys_index, p1);
+    // Simulates a store which is not backed by memory, by returning copies of its symbols
+    struct CopyingSymbolStore {
+        symbols: HashMap<usize, Vec<u8>>,
+    }
 
-        for i in enc_indices(tuple, lt_symbols, pi_symbols, p1) {
-            rebuilt += &intermediate_symbols[i];
+    impl SymbolStore for CopyingSymbolStore {
+        fn put(&mut self, index: usize, data: Vec<u8>) {
+            self.symbols.insert(index, data);
+        }
+
+        fn get(&self, index: usize) -> Option<Cow<'_, [u8]>> {
+            self.symbols.get(&index).map(|x| Cow::Owned(x.clone()))
         }
-        rebuilt
     }
-}
 
-#[cfg(test)]
-mod codec_tests {
-    use crate::SourceBlockEncoder;
-    use crate::{Decoder, SourceBlockEncodingPlan};
-    use crate::{Encoder, EncoderBuilder};
-    use crate::{ObjectTransmissionInformation, SourceBlockDecoder};
-    use rand::seq::SliceRandom;
-    use rand::Rng;
-    use std::sync::Arc;
-    use std::{
-        iter,
-        sync::atomic::{AtomicU32, Ordering},
-    };
+    #[test]
+    fn custom_symbol_store() {
+        let elements: usize = 10_000;
+        let data = gen_test_data(elements);
+
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 2, 2, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut packets = encoder.get_encoded_packets(20);
+        packets.retain(|packet| packet.payload_id().encoding_symbol_id() % 7 != 0);
+
+        let mut stores = vec![];
+        let mut decoder = Decoder::with_symbol_stores(config, |sbn| {
+            stores.push(sbn);
+            CopyingSymbolStore {
+                symbols: HashMap::new(),
+            }
+        });
+        assert_eq!(stores, vec![0, 1]);
+        let mut result = None;
+        for packet in packets {
+            result = decoder.decode(packet);
+        }
+        assert_eq!(result.unwrap(), data);
+    }
 
     #[test]
-    fn random_erasure_dense() {
-        random_erasure(99_999);
+    fn decode_with_crc32() {
+        let elements: usize = 10_000;
+        let data = gen_test_data(elements);
+
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 4, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+        assert_eq!(decoder.get_result_with_hasher(Crc32::new()), None);
+        for packet in encoder.get_encoded_packets(0) {
+            decoder.add_new_packet(packet);
+        }
+
+        let mut expected = Crc32::new();
+        expected.update(&data);
+        let (result, crc) = decoder.get_result_with_hasher(Crc32::new()).unwrap();
+        assert_eq!(result, data);
+        assert_eq!(crc, expected.finish());
     }
 
+    #[cfg(feature = "verify")]
     #[test]
-    fn random_erasure_sparse() {
-        random_erasure(0);
+    fn verify_block() {
+        let elements: usize = 10_000;
+        let data = gen_test_data(elements);
+
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut packets = encoder.get_encoded_packets(20);
+        // Drop a source symbol from each block, so that both need a solve
+        packets.retain(|packet| packet.payload_id().encoding_symbol_id() != 5);
+
+        let mut decoder = Decoder::new(config);
+        assert!(!decoder.verify_block(0));
+        for packet in packets.iter().cloned() {
+            decoder.add_new_packet(packet);
+        }
+        assert_eq!(decoder.get_result(), Some(data.clone()));
+        assert!(decoder.verify_block(0));
+        assert!(decoder.verify_block(1));
+
+        // Corrupt one of the surplus repair symbols, which the solve doesn't depend on
+        let block_encoder = &encoder.get_block_encoders()[0];
+        let mut block_decoder = SourceBlockDecoder::new2(0, &config, 79 * 64);
+        let mut block_packets = block_encoder.source_packets();
+        block_packets.remove(5);
+        let mut repair = block_encoder.repair_packets(0, 20);
+        repair.last_mut().unwrap().data[0] ^= 1;
+        block_packets.extend(repair);
+        assert!(block_decoder.decode(block_packets).is_some());
+        assert!(!block_decoder.verify_block());
+
+        // Nothing to check when every source symbol was received
+        let mut systematic = SourceBlockDecoder::new2(0, &config, 79 * 64);
+        assert!(systematic.decode(block_encoder.source_packets()).is_some());
+        assert!(systematic.verify_block());
     }
 
-    fn random_erasure(sparse_threshold: u32) {
-        let elements: usize = rand::thread_rng().gen_range(1..1_000_000);
+    #[test]
+    fn trailing_zero_bytes() {
+        // The object ends in zero bytes which span the final symbol boundary, and are
+        // indistinguishable from the padding, so only the transfer length says where it ends
+        let elements: usize = 10_000;
         let mut data: Vec<u8> = vec![0; elements];
-        for element in &mut data {
-            *element = rand::thread_rng().gen();
+        for element in &mut data[..(elements - 100)] {
+            *element = rand::thread_rng().gen_range(1..=255);
         }
 
-        // MTU is set to not be too small, otherwise this test may take a very long time
-        let mtu = rand::thread_rng().gen_range(((elements / 100) as u16)..10_000);
-
-        let encoder = Encoder::with_defaults(&data, mtu);
+        for &pad_last_symbol in [true, false].iter() {
+            let mut builder = EncoderBuilder::new();
+            builder.set_max_packet_size(64);
+            builder.set_pad_last_symbol(pad_last_symbol);
+            let encoder = builder.build(&data);
+            let config = encoder.get_config();
+            assert_ne!(config.padding_bytes(), 0);
+
+            let mut packets = encoder.get_encoded_packets(10);
+            // Lose the final source symbols, so that they're rebuilt by the solve
+            let last_esi = config.symbols_in_block(config.source_blocks() - 1) - 1;
+            let last_block = config.source_blocks() - 1;
+            packets.retain(|packet| {
+                let esi = packet.payload_id().encoding_symbol_id();
+                packet.payload_id().source_block_number() != last_block
+                    || esi + 2 < last_esi
+                    || esi > last_esi
+            });
+
+            let mut decoder = Decoder::new(config);
+            let mut result = None;
+            for packet in packets {
+                result = decoder.decode(packet);
+            }
+            let result = result.unwrap();
+            assert_eq!(result.len(), elements);
+            assert_eq!(result, data);
+        }
+    }
 
-        let mut packets = encoder.get_encoded_packets(15);
-        packets.shuffle(&mut rand::thread_rng());
-        // Erase 10 packets at random
-        let length = packets.len();
-        packets.truncate(length - 10);
+    #[test]
+    fn decode_strategies() {
+        let elements: usize = 25 * 64;
+        let data = gen_test_data(elements);
 
-        let mut decoder = Decoder::new(encoder.get_config());
-        decoder.set_sparse_threshold(sparse_threshold);
+        // K = 25, K' = 26
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 1, 1, 8);
+        let encoder = SourceBlockEncoder::new2(0, &config, &data);
+        let repair = encoder.repair_packets(0, 40);
 
+        let mut eager = Decoder::new(config);
+        eager.set_decode_strategy(DecodeStrategy::Eager);
         let mut result = None;
-        while !packets.is_empty() {
-            result = decoder.decode(packets.pop().unwrap());
-            if result != None {
-                break;
-            }
+        for packet in repair[..30].iter().cloned() {
+            result = eager.decode(packet);
         }
+        assert_eq!(result, Some(data.clone()));
 
-        assert_eq!(result.unwrap(), data);
+        let mut threshold = Decoder::new(config);
+        threshold.set_decode_strategy(DecodeStrategy::OnThreshold(5));
+        // No attempt is made until 25 + 5 symbols have been received
+        for packet in repair[..29].iter().cloned() {
+            assert_eq!(threshold.decode(packet), None);
+        }
+        assert_eq!(threshold.decode(repair[29].clone()), Some(data.clone()));
+
+        // Receiving every source symbol is enough, regardless of the margin
+        let mut systematic = Decoder::new(config);
+        systematic.set_decode_strategy(DecodeStrategy::OnThreshold(5));
+        assert_eq!(
+            systematic.add_packets(encoder.source_packets()),
+            Some(data.clone())
+        );
+
+        let mut manual = Decoder::new(config);
+        manual.set_decode_strategy(DecodeStrategy::Manual);
+        assert_eq!(manual.add_packets(encoder.source_packets()), None);
+        assert_eq!(manual.add_packets(repair.clone()), None);
+        assert_eq!(manual.get_result(), None);
+        assert_eq!(manual.try_decode(), Some(data));
     }
 
     #[test]
-    fn sub_block_erasure() {
+    fn add_packets() {
         let elements: usize = 10_000;
-        let mut data: Vec<u8> = vec![0; elements];
-        for element in &mut data {
-            *element = rand::thread_rng().gen();
-        }
+        let data = gen_test_data(elements);
 
-        let mut builder = EncoderBuilder::new();
-        builder.set_decoder_memory_requirement(5000);
-        builder.set_max_packet_size(500);
-        let encoder = builder.build(&data);
-        assert!(encoder.get_config().sub_blocks() > 2);
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut packets = encoder.get_encoded_packets(15);
+        packets.shuffle(&mut rand::thread_rng());
+        let rest = packets.split_off(100);
 
-        // Test round trip
-        let mut decoder = Decoder::new(encoder.get_config());
+        let mut decoder = Decoder::new(config);
+        assert_eq!(decoder.add_packets(packets), None);
+        assert_eq!(decoder.add_packets(vec![]), None);
+        assert_eq!(decoder.add_packets(rest), Some(data.clone()));
+        assert_eq!(decoder.add_packets(vec![]), Some(data));
+    }
+
+    #[test]
+    fn header_packet() {
+        let elements: usize = 10_000;
+        let data = gen_test_data(elements);
+
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let header = encoder.header_packet();
+        assert_eq!(header.len(), 12);
+        let mut decoder = Decoder::from_header_packet(&header).unwrap();
         let mut result = None;
         for packet in encoder.get_encoded_packets(0) {
-            assert_eq!(result, None);
             result = decoder.decode(packet);
         }
-        assert_eq!(result.unwrap(), data);
+        assert_eq!(result, Some(data));
+
+        let mut extended = header.clone();
+        extended.extend_from_slice(b"metadata");
+        assert!(Decoder::from_header_packet(&extended).is_ok());
+        assert_eq!(
+            Decoder::from_header_packet(&header[..11]).err(),
+            Some(ParseError::InvalidLength {
+                expected: 12,
+                actual: 11
+            })
+        );
+        let mut reserved = header;
+        reserved[5] = 1;
+        assert_eq!(
+            Decoder::from_header_packet(&reserved).err(),
+            Some(ParseError::NonZeroReserved(1))
+        );
+    }
 
-        // Test repair
-        let mut packets = encoder.get_encoded_packets(15);
-        packets.shuffle(&mut rand::thread_rng());
-        // Erase 10 packets at random
-        let length = packets.len();
-        packets.truncate(length - 10);
+    #[test]
+    fn decode_range() {
+        let elements: usize = 10_000;
+        let data = gen_test_data(elements);
+
+        // K = 53, 52 and 52, so block 1 covers bytes [3392, 6720)
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 3, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+        for packet in encoder.get_block_encoders()[1].repair_packets(0, 60) {
+            decoder.add_new_packet(packet);
+        }
 
-        let mut decoder = Decoder::new(encoder.get_config());
+        assert_eq!(
+            decoder.decode_range(4000, 5000),
+            Some(data[4000..5000].to_vec())
+        );
+        assert_eq!(
+            decoder.decode_range(3392, 6720),
+            Some(data[3392..6720].to_vec())
+        );
+        assert_eq!(decoder.decode_range(5000, 5000), Some(vec![]));
+        assert_eq!(decoder.decode_range(3391, 4000), None);
+        assert_eq!(decoder.decode_range(6000, 6721), None);
+        assert_eq!(decoder.get_result(), None);
+
+        for packet in encoder.get_block_encoders()[2].source_packets() {
+            decoder.add_new_packet(packet);
+        }
+        assert_eq!(
+            decoder.decode_range(6000, elements as u64),
+            Some(data[6000..].to_vec())
+        );
+    }
 
+    #[test]
+    fn decode_checked() {
+        let elements: usize = 10_000;
+        let data = gen_test_data(elements);
+
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        // The sender publishes a checksum of every source symbol
+        let checksum = |symbol: &[u8]| {
+            let mut crc = Crc32::new();
+            crc.update(symbol);
+            crc.finish()
+        };
+        let mut checksums = HashMap::new();
+        let mut packets = encoder.get_encoded_packets(10);
+        for packet in packets.iter() {
+            let payload_id = packet.payload_id();
+            let esi = payload_id.encoding_symbol_id();
+            if config
+                .symbol_byte_range(payload_id.source_block_number(), esi)
+                .is_some()
+            {
+                checksums.insert(packet.payload_id().clone(), checksum(packet.data()));
+            }
+        }
+
+        packets[3].data[0] ^= 0xFF;
+        let mut decoder = Decoder::new(config);
         let mut result = None;
-        while !packets.is_empty() {
-            result = decoder.decode(packets.pop().unwrap());
-            if result != None {
+        for packet in packets {
+            result = decoder.decode_checked(packet, |packet, range| match range {
+                Some(range) => {
+                    let expected = config.symbol_byte_range(
+                        packet.payload_id().source_block_number(),
+                        packet.payload_id().encoding_symbol_id(),
+                    );
+                    assert_eq!(Some(range), expected);
+                    checksums[packet.payload_id()] == checksum(packet.data())
+                }
+                None => true,
+            });
+            if result.is_some() {
                 break;
             }
         }
+        assert_eq!(decoder.rejected_packets(), 1);
+        assert_eq!(result, Some(data));
+    }
 
-        assert_eq!(result.unwrap(), data);
+    #[test]
+    fn decode_verified() {
+        let elements: usize = 10_000;
+        let data = gen_test_data(elements);
+
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 4, 1, 8);
+        let mut manifest = TransferManifest::with_uniform_repair(config, 0);
+        manifest.set_transfer_hash(&data, Crc32::new());
+        let encoder = Encoder::new(&data, config);
+        let mut decoder = Decoder::new(config);
+        assert_eq!(
+            decoder.get_verified_result(&manifest, Crc32::new()),
+            Err(DecodeError::InsufficientSymbols)
+        );
+        for packet in encoder.get_encoded_packets(0) {
+            decoder.add_new_packet(packet);
+        }
+        assert_eq!(
+            decoder.get_verified_result(&manifest, Crc32::new()),
+            Ok(data.clone())
+        );
+        // Without a hash, only the configuration is checked
+        let unhashed = TransferManifest::with_uniform_repair(config, 0);
+        assert_eq!(
+            decoder.get_verified_result(&unhashed, Crc32::new()),
+            Ok(data.clone())
+        );
+
+        let other_config = ObjectTransmissionInformation::new(elements as u64, 32, 4, 1, 8);
+        let other = TransferManifest::with_uniform_repair(other_config, 0);
+        assert_eq!(
+            decoder.get_verified_result(&other, Crc32::new()),
+            Err(DecodeError::ManifestMismatch)
+        );
+
+        let mut corrupted = data;
+        corrupted[0] ^= 1;
+        let mut corrupted_manifest = TransferManifest::with_uniform_repair(config, 0);
+        corrupted_manifest.set_transfer_hash(&corrupted, Crc32::new());
+        assert_eq!(
+            decoder.get_verified_result(&corrupted_manifest, Crc32::new()),
+            Err(DecodeError::HashMismatch)
+        );
+    }
+
+    #[test]
+    fn received_set_equality() {
+        let elements: usize = 10_000;
+        let data = gen_test_data(elements);
+
+        let config = ObjectTransmissionInformation::new(elements as u64, 64, 4, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut packets = encoder.get_encoded_packets(5);
+        // Keep few enough packets that no block can decode
+        packets.retain(|packet| packet.payload_id().encoding_symbol_id() % 2 == 0);
+
+        let mut decoder1 = Decoder::new(encoder.get_config());
+        let mut decoder2 = Decoder::new(encoder.get_config());
+        assert!(decoder1.received_set_eq(&decoder2));
+        for packet in packets.iter() {
+            decoder1.add_new_packet(packet.clone());
+        }
+        assert!(!decoder1.received_set_eq(&decoder2));
+        packets.shuffle(&mut rand::thread_rng());
+        for packet in packets {
+            decoder2.add_new_packet(packet);
+        }
+        assert!(decoder1.received_set_eq(&decoder2));
+    }
+
+    #[test]
+    fn decode_cost_estimate() {
+        // K' = 10, S = 7, H = 10
+        assert_eq!(estimate_decode_cost(10), 27 * 27);
+        let mut previous = 0;
+        for source_block_symbols in (1..56403).step_by(97) {
+            let cost = estimate_decode_cost(source_block_symbols);
+            assert!(cost >= previous);
+            previous = cost;
+        }
     }
 
     #[test]
@@ -458,10 +3187,7 @@ mod codec_tests {
         let symbol_size = 8;
         for symbol_count in 1..=max_symbols {
             let elements = symbol_size * symbol_count;
-            let mut data: Vec<u8> = vec![0; elements];
-            for element in &mut data {
-                *element = rand::thread_rng().gen();
-            }
+            let data = gen_test_data(elements);
 
             if progress && symbol_count % 100 == 0 {
                 println!("Completed {} symbols", symbol_count)
@@ -542,10 +3268,7 @@ mod codec_tests {
     fn do_repair(symbol_count: usize, sparse_threshold: u32, pre_plan: bool) -> bool {
         let symbol_size = 8;
         let elements = symbol_size * symbol_count;
-        let mut data: Vec<u8> = vec![0; elements];
-        for element in &mut data {
-            *element = rand::thread_rng().gen();
-        }
+        let data = gen_test_data(elements);
 
         let config = ObjectTransmissionInformation::new(0, 8, 0, 1, 1);
         let encoder = if pre_plan {
@@ -559,14 +3282,16 @@ mod codec_tests {
         decoder.set_sparse_threshold(sparse_threshold);
 
         let mut result = None;
-        let mut parsed_packets = 0;
         // This test can theoretically fail with ~1/256^5 probability
-        for packet in encoder.repair_packets(0, (elements / symbol_size + 4) as u32) {
+        for (parsed_packets, packet) in encoder
+            .repair_packets(0, (elements / symbol_size + 4) as u32)
+            .into_iter()
+            .enumerate()
+        {
             if parsed_packets < elements / symbol_size && result.is_some() {
                 return false;
             }
             result = decoder.decode(iter::once(packet));
-            parsed_packets += 1;
         }
 
         return result.unwrap() == data;
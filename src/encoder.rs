@@ -1,11 +1,14 @@
 use crate::base::intermediate_tuple;
 use crate::base::partition;
 use crate::base::EncodingPacket;
+use crate::base::ParseError;
 use crate::base::PayloadId;
+use crate::base::MAX_ESI;
 use crate::constraint_matrix::generate_constraint_matrix;
 use crate::matrix::DenseBinaryMatrix;
 use crate::operation_vector::{perform_op, SymbolOps};
 use crate::pi_solver::fused_inverse_mul_symbols;
+use crate::rng::shuffle;
 use crate::sparse_matrix::SparseBinaryMatrix;
 use crate::symbol::Symbol;
 use crate::systematic_constants::extended_source_block_symbols;
@@ -14,10 +17,24 @@ use crate::systematic_constants::num_intermediate_symbols;
 use crate::systematic_constants::num_ldpc_symbols;
 use crate::systematic_constants::num_lt_symbols;
 use crate::systematic_constants::num_pi_symbols;
+use crate::systematic_constants::SYSTEMATIC_INDICES_AND_PARAMETERS;
 use crate::systematic_constants::{calculate_p1, systematic_index};
+use crate::xor_parity::XorParityPacket;
 use crate::ObjectTransmissionInformation;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cmp::{max, min};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::mem::size_of;
+use std::ops::{Deref, Range};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
 
 pub const SPARSE_MATRIX_THRESHOLD: u32 = 250;
 
@@ -26,6 +43,8 @@ pub const SPARSE_MATRIX_THRESHOLD: u32 = 250;
 pub struct EncoderBuilder {
     decoder_memory_requirement: u64,
     max_packet_size: u16,
+    pad_last_symbol: bool,
+    repair_only: bool,
 }
 
 impl EncoderBuilder {
@@ -33,6 +52,8 @@ impl EncoderBuilder {
         EncoderBuilder {
             decoder_memory_requirement: 10 * 1024 * 1024,
             max_packet_size: 1024,
+            pad_last_symbol: true,
+            repair_only: false,
         }
     }
 
@@ -44,6 +65,26 @@ impl EncoderBuilder {
         self.max_packet_size = bytes;
     }
 
+    /// Controls whether the source packet holding the end of the object is zero padded to the
+    /// full symbol size (the default), or truncated to omit the
+    /// [`padding_bytes()`](ObjectTransmissionInformation::padding_bytes). Padding is always
+    /// transmitted when sub-blocking is in use, since it is then spread across several symbols.
+    pub fn set_pad_last_symbol(&mut self, pad: bool) {
+        self.pad_last_symbol = pad;
+    }
+
+    /// When enabled, the encoder never emits source packets, so the original bytes of the object
+    /// are never transmitted as-is: every packet carries a repair symbol with ESI >= K'. The
+    /// standard [`Decoder`](crate::Decoder) reconstructs the object from these alone.
+    ///
+    /// The reception overhead is the same as for systematic mode, since K repair symbols decode
+    /// with the same probability as any other K symbols. However, the receiver must always solve
+    /// the full constraint matrix, and the sender must compute a repair symbol for every packet.
+    /// Defaults to false.
+    pub fn set_repair_only(&mut self, repair_only: bool) {
+        self.repair_only = repair_only;
+    }
+
     pub fn build(&self, data: &[u8]) -> Encoder {
         let config = ObjectTransmissionInformation::generate_encoding_parameters(
             data.len() as u64,
@@ -51,16 +92,98 @@ impl EncoderBuilder {
             self.decoder_memory_requirement,
         );
 
-        Encoder::new(data, config)
+        let mut encoder = Encoder::new(data, config);
+        encoder.pad_last_symbol = self.pad_last_symbol;
+        encoder.repair_only = self.repair_only;
+        encoder
     }
 }
 
+/// Fraction of transmitted symbols which are source symbols, K / (K + repair), when sending
+/// `repair_symbols` repair symbols in addition to the `source_symbols` source symbols of a block.
+pub fn code_rate(source_symbols: u32, repair_symbols: u32) -> f64 {
+    source_symbols as f64 / (source_symbols as f64 + repair_symbols as f64)
+}
+
+/// Inverse of [`code_rate`]: the minimum number of repair symbols to send with `source_symbols`
+/// source symbols, so that the code rate does not exceed `target_code_rate`.
+pub fn repair_symbols_for_code_rate(source_symbols: u32, target_code_rate: f64) -> u32 {
+    assert!(target_code_rate > 0.0 && target_code_rate <= 1.0);
+    let mut repair_symbols =
+        (source_symbols as f64 / target_code_rate - source_symbols as f64).floor() as u32;
+    while code_rate(source_symbols, repair_symbols) > target_code_rate {
+        repair_symbols += 1;
+    }
+    repair_symbols
+}
+
+/// Recommends a symbol size for sending an object of `transfer_length` bytes over a link with the
+/// given `mtu`, with one symbol per IPv4 UDP datagram.
+///
+/// The largest symbol which fits in a datagram, after the 20 byte IPv4 header, the 8 byte UDP
+/// header and the 4 byte [`PayloadId`](crate::PayloadId), is preferred: it minimizes the number
+/// of packets, and the number of symbols per source block, which dominates decoding time (see
+/// [`estimate_decode_cost`](crate::estimate_decode_cost)). However, objects smaller than 10 such
+/// symbols are instead divided into 10 smaller symbols. A source block is always padded to at
+/// least 10 symbols (the smallest K', see
+/// [section 5.6](https://tools.ietf.org/html/rfc6330#section-5.6)), so fewer, larger symbols
+/// would not reduce the number of packets sent, while smaller symbols reduce both the padding in
+/// the final symbol and the amount of repair data needed to recover each lost packet. The result
+/// is a multiple of 8 bytes, the alignment used by
+/// [`ObjectTransmissionInformation::with_defaults`](crate::ObjectTransmissionInformation::with_defaults).
+/// It is never less than 8 bytes, even if `mtu` leaves no room for a symbol of that size.
+pub fn recommend_symbol_size(transfer_length: u64, mtu: u16) -> u16 {
+    let alignment = 8;
+    let headers = 20 + 8 + 4;
+    let max_symbol_size = max(
+        mtu.saturating_sub(headers) / alignment * alignment,
+        alignment,
+    );
+    let min_symbols = 10;
+    let small_symbol_size = (transfer_length + min_symbols - 1) / min_symbols;
+    let small_symbol_size =
+        (small_symbol_size + alignment as u64 - 1) / alignment as u64 * alignment as u64;
+    min(
+        max_symbol_size as u64,
+        small_symbol_size.max(alignment as u64),
+    ) as u16
+}
+
 // Calculate the splits [start, end) of an object for encoding as blocks.
 // If a block extends past the end of the object, it must be zero padded
 pub fn calculate_block_offsets(
     data: &[u8],
     config: &ObjectTransmissionInformation,
 ) -> Vec<(usize, usize)> {
+    let kt = (config.transfer_length() as f64 / config.symbol_size() as f64).ceil() as u32;
+    let blocks = block_offsets(config);
+    for &(_, end) in blocks.iter() {
+        if end > data.len() {
+            // Should only be possible when Kt * T > F. See third to last paragraph in section 4.4.1.2
+            assert!(kt as usize * config.symbol_size() as usize > data.len());
+        }
+    }
+    blocks
+}
+
+// Copies the bytes [start, end) of the concatenation of `slices`, zero padding past its end
+fn gather_slices(slices: &[&[u8]], start: usize, end: usize) -> Vec<u8> {
+    let mut result = Vec::with_capacity(end - start);
+    let mut slice_start = 0;
+    for slice in slices {
+        let slice_end = slice_start + slice.len();
+        if slice_end > start && slice_start < end {
+            let from = start.max(slice_start) - slice_start;
+            let to = end.min(slice_end) - slice_start;
+            result.extend_from_slice(&slice[from..to]);
+        }
+        slice_start = slice_end;
+    }
+    result.resize(end - start, 0);
+    result
+}
+
+fn block_offsets(config: &ObjectTransmissionInformation) -> Vec<(usize, usize)> {
     let kt = (config.transfer_length() as f64 / config.symbol_size() as f64).ceil() as u32;
     let (kl, ks, zl, zs) = partition(kt, config.source_blocks());
 
@@ -77,13 +200,7 @@ pub fn calculate_block_offsets(
     if zs > 0 {
         for _ in zl..(zl + zs) {
             let offset = ks as usize * config.symbol_size() as usize;
-            if data_index + offset <= data.len() {
-                blocks.push((data_index, (data_index + offset)));
-            } else {
-                // Should only be possible when Kt * T > F. See third to last paragraph in section 4.4.1.2
-                assert!(kt as usize * config.symbol_size() as usize > data.len());
-                blocks.push((data_index, (data_index + offset)));
-            }
+            blocks.push((data_index, (data_index + offset)));
             data_index += offset;
         }
     }
@@ -96,42 +213,119 @@ pub fn calculate_block_offsets(
 pub struct Encoder {
     config: ObjectTransmissionInformation,
     blocks: Vec<SourceBlockEncoder>,
+    pad_last_symbol: bool,
+    repair_only: bool,
+    repair_seed: Option<u64>,
 }
 
 impl Encoder {
     pub fn new(data: &[u8], config: ObjectTransmissionInformation) -> Encoder {
-        let mut block_encoders = vec![];
-        let mut cached_plan: Option<SourceBlockEncodingPlan> = None;
-        for (i, (start, end)) in calculate_block_offsets(data, &config).drain(..).enumerate() {
+        Encoder::with_optional_plan(data, config, None)
+    }
+
+    /// Like [`new`](Self::new), but encodes every source block whose extended source block size,
+    /// K', matches that of `plan` using it, rather than generating a new plan.
+    ///
+    /// See [`SourceBlockEncodingPlan::precompute_for_kprime`]
+    pub fn with_encoding_plan(
+        data: &[u8],
+        config: ObjectTransmissionInformation,
+        plan: &SourceBlockEncodingPlan,
+    ) -> Encoder {
+        Encoder::with_optional_plan(data, config, Some(plan))
+    }
+
+    /// Like [`new`](Self::new), but encodes the logical concatenation of `slices`, such as the
+    /// buffers of a vectored read, without first concatenating them. Symbols may span the
+    /// boundaries between slices.
+    ///
+    /// Each source block is gathered into a temporary buffer while it is encoded, so only one
+    /// block of the object is copied at a time. The total length of the slices must equal the
+    /// transfer length of `config`.
+    pub fn from_slices(slices: &[&[u8]], config: ObjectTransmissionInformation) -> Encoder {
+        let total_length: u64 = slices.iter().map(|slice| slice.len() as u64).sum();
+        assert_eq!(
+            total_length,
+            config.transfer_length(),
+            "Slices must contain exactly the transfer length"
+        );
+        Encoder::from_blocks(config, None, block_offsets(&config), |start, end| {
+            Cow::Owned(gather_slices(slices, start, end))
+        })
+    }
+
+    fn with_optional_plan(
+        data: &[u8],
+        config: ObjectTransmissionInformation,
+        precomputed_plan: Option<&SourceBlockEncodingPlan>,
+    ) -> Encoder {
+        let offsets = calculate_block_offsets(data, &config);
+        Encoder::from_blocks(config, precomputed_plan, offsets, |start, end| {
             // Zero pad if necessary
-            let mut padded;
-            let block: &[u8] = if end > data.len() {
-                padded = Vec::from(&data[start..]);
+            if end > data.len() {
+                let mut padded = Vec::from(&data[start..]);
                 padded.extend(vec![0; end - data.len()]);
-                &padded
+                Cow::Owned(padded)
             } else {
-                &data[start..end]
-            };
+                Cow::Borrowed(&data[start..end])
+            }
+        })
+    }
+
+    // Encodes each block [start, end) of the object, the bytes of which are provided by `block_data`
+    fn from_blocks<'a, F: FnMut(usize, usize) -> Cow<'a, [u8]>>(
+        config: ObjectTransmissionInformation,
+        precomputed_plan: Option<&SourceBlockEncodingPlan>,
+        mut offsets: Vec<(usize, usize)>,
+        mut block_data: F,
+    ) -> Encoder {
+        let mut block_encoders = vec![];
+        let mut cached_plan: Option<SourceBlockEncodingPlan> = None;
+        for (i, (start, end)) in offsets.drain(..).enumerate() {
+            let block = block_data(start, end);
 
             let symbol_count = block.len() / config.symbol_size() as usize;
-            if cached_plan.is_none()
-                || cached_plan.as_ref().unwrap().source_symbol_count != symbol_count as u16
-            {
-                let plan = SourceBlockEncodingPlan::generate(symbol_count as u16);
-                cached_plan = Some(plan);
-            }
+            let plan = match precomputed_plan {
+                Some(plan) if plan.supports(symbol_count) => plan,
+                _ => {
+                    if cached_plan.is_none()
+                        || !cached_plan.as_ref().unwrap().supports(symbol_count)
+                    {
+                        let plan = SourceBlockEncodingPlan::generate(symbol_count as u16);
+                        cached_plan = Some(plan);
+                    }
+                    cached_plan.as_ref().unwrap()
+                }
+            };
             block_encoders.push(SourceBlockEncoder::with_encoding_plan2(
-                i as u8,
-                &config,
-                &block,
-                cached_plan.as_ref().unwrap(),
+                i as u8, &config, &block, plan,
             ));
         }
 
         Encoder {
             config,
             blocks: block_encoders,
+            pad_last_symbol: true,
+            repair_only: false,
+            repair_seed: None,
+        }
+    }
+
+    /// Deserializes the 12 byte OTI, as produced by
+    /// [`ObjectTransmissionInformation::serialize`], and encodes `data` with it.
+    ///
+    /// Returns an error if the OTI is malformed (see
+    /// [`ObjectTransmissionInformation::try_deserialize`]), or if its transfer length does not
+    /// match the length of `data`.
+    pub fn from_oti_bytes(data: &[u8], oti_bytes: &[u8; 12]) -> Result<Encoder, ParseError> {
+        let config = ObjectTransmissionInformation::try_deserialize(oti_bytes)?;
+        if config.transfer_length() != data.len() as u64 {
+            return Err(ParseError::InvalidLength {
+                expected: config.transfer_length() as usize,
+                actual: data.len(),
+            });
         }
+        Ok(Encoder::new(data, config))
     }
 
     pub fn with_defaults(data: &[u8], maximum_transmission_unit: u16) -> Encoder {
@@ -147,18 +341,278 @@ impl Encoder {
         self.config
     }
 
+    /// A packet holding the serialized OTI, to send ahead of the encoded packets when the OTI is
+    /// not communicated out of band. Receivers create their decoder from it with
+    /// [`Decoder::from_header_packet`](crate::Decoder::from_header_packet).
+    pub fn header_packet(&self) -> Vec<u8> {
+        self.config.serialize().to_vec()
+    }
+
+    /// Shuffles the order of each source block's repair packets, so that a burst of losses removes
+    /// symbols which are spread across the ESI space. The source packets are still sent first, in
+    /// order.
+    ///
+    /// The shuffle is a Fisher-Yates shuffle driven by SplitMix64, seeded with `seed` plus the
+    /// source block number (wrapping), so the order only depends on the seed and is the same on
+    /// every platform. Store the seed with
+    /// [`TransferManifest::set_repair_seed`](crate::TransferManifest::set_repair_seed) to let
+    /// receivers predict the order with
+    /// [`TransferManifest::repair_esi_order`](crate::TransferManifest::repair_esi_order).
+    pub fn set_repair_seed(&mut self, seed: u64) {
+        self.repair_seed = Some(seed);
+    }
+
     pub fn get_encoded_packets(&self, repair_packets_per_block: u32) -> Vec<EncodingPacket> {
+        self.encode_with_progress(repair_packets_per_block, |_| {})
+    }
+
+    /// Like [`get_encoded_packets`](Self::get_encoded_packets), but calls `progress` with the
+    /// fraction of source blocks completed, in (0, 1], each time a block's packets have been
+    /// generated.
+    pub fn encode_with_progress(
+        &self,
+        repair_packets_per_block: u32,
+        mut progress: impl FnMut(f32),
+    ) -> Vec<EncodingPacket> {
+        let total = (0..self.blocks.len())
+            .map(|i| self.block_packet_count(i, repair_packets_per_block))
+            .sum();
+        let mut packets = Vec::with_capacity(total);
+        for i in 0..self.blocks.len() {
+            packets.extend(self.block_packets(i, repair_packets_per_block));
+            progress((i + 1) as f32 / self.blocks.len() as f32);
+        }
+        packets
+    }
+
+    /// Writes the packets that [`get_encoded_packets`](Self::get_encoded_packets) would return to
+    /// the file at `path`, replacing it if it exists. Packets are generated and written one source
+    /// block at a time, so that they need not all be held in memory. The file is written
+    /// sequentially through a buffer, rather than memory-mapped, since std has no safe way to map
+    /// a file, and writing each block as it's generated already bounds the memory used.
+    ///
+    /// Each packet is [`serialize`](EncodingPacket::serialize)d and prefixed with its length, as a
+    /// 4-byte big-endian integer. Use [`Decoder::decode_from_file`](crate::Decoder::decode_from_file)
+    /// to read the file back.
+    pub fn encode_to_file(&self, path: &Path, repair_packets_per_block: u32) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for i in 0..self.blocks.len() {
+            for packet in self.block_packets(i, repair_packets_per_block) {
+                let serialized = packet.serialize();
+                writer.write_all(&(serialized.len() as u32).to_be_bytes())?;
+                writer.write_all(&serialized)?;
+            }
+        }
+        writer.flush()
+    }
+
+    /// Generates the source packets and `repair_packets_per_block` repair packets for only the
+    /// given source blocks, in the order listed. Useful for retransmitting only the blocks that a
+    /// receiver reports as lost.
+    pub fn encode_blocks(
+        &self,
+        blocks: &[u8],
+        repair_packets_per_block: u32,
+    ) -> Vec<EncodingPacket> {
+        for &block in blocks {
+            assert!(
+                (block as usize) < self.blocks.len(),
+                "Source block {} out of range",
+                block
+            );
+        }
+        let total = blocks
+            .iter()
+            .map(|&block| self.block_packet_count(block as usize, repair_packets_per_block))
+            .sum();
+        let mut packets = Vec::with_capacity(total);
+        for &block in blocks {
+            packets.extend(self.block_packets(block as usize, repair_packets_per_block));
+        }
+        packets
+    }
+
+    // Number of packets returned by block_packets()
+    fn block_packet_count(&self, block: usize, repair_packets: u32) -> usize {
+        if self.repair_only {
+            repair_packets as usize
+        } else {
+            self.blocks[block].source_symbols.len() + repair_packets as usize
+        }
+    }
+
+    fn block_packets(&self, block: usize, repair_packets: u32) -> Vec<EncodingPacket> {
+        let encoder = &self.blocks[block];
+        if self.repair_only {
+            return self.block_repair_packets(block, repair_packets);
+        }
+        let mut packets = Vec::with_capacity(self.block_packet_count(block, repair_packets));
+        packets.extend(encoder.source_packets());
+        if block == self.blocks.len() - 1 {
+            self.strip_padding(&mut packets);
+        }
+        packets.extend(self.block_repair_packets(block, repair_packets));
+        packets
+    }
+
+    // Generates the block's repair packets, in the order given by the repair seed, if any
+    fn block_repair_packets(&self, block: usize, repair_packets: u32) -> Vec<EncodingPacket> {
+        let mut packets = self.blocks[block].repair_packets(0, repair_packets);
+        if let Some(seed) = self.repair_seed {
+            shuffle(&mut packets, seed.wrapping_add(block as u64));
+        }
+        packets
+    }
+
+    // Removes the zero padding from the final source symbol of the object, if configured
+    fn strip_padding(&self, source_packets: &mut [EncodingPacket]) {
+        if self.pad_last_symbol || self.config.sub_blocks() > 1 {
+            return;
+        }
+        if let Some(last) = source_packets.last_mut() {
+            let padding = self.config.padding_bytes() as usize;
+            let length = last.data.len() - padding;
+            last.data.truncate(length);
+        }
+    }
+
+    /// Chooses `count` repair ESIs spread across the repair ESI space, rather than the contiguous
+    /// ESIs from K' upwards, for multicast senders which want receivers with different loss
+    /// patterns to be unlikely to have lost the same symbols. The ESIs are valid as repair ESIs in
+    /// every source block, and can be generated with [`SourceBlockEncoder::packets_for_esis`].
+    ///
+    /// The heuristic is a base 2 van der Corput sequence: the i-th ESI lies at the fraction of the
+    /// repair space given by reversing the low 23 bits of i. Each ESI lands in the largest gap left
+    /// by the previous ones, so any prefix of the sequence is evenly spread, and the result for a
+    /// smaller `count` is a prefix of the result for a larger one. Panics if `count` exceeds 2^23,
+    /// beyond which the ESIs would no longer be distinct.
+    pub fn diverse_repair_esis(&self, count: u32) -> Vec<u32> {
+        assert!(count <= 1 << 23);
+        let start = self
+            .blocks
+            .iter()
+            .map(|block| block.repair_esi_range().start)
+            .max()
+            .unwrap();
+        // K' is at most 56403, so there are always more than 2^23 repair ESIs
+        let space = (MAX_ESI - start) as u64;
+        (0..count)
+            .map(|i| {
+                let fraction = (i.reverse_bits() >> 9) as u64;
+                start + ((fraction * space) >> 23) as u32
+            })
+            .collect()
+    }
+
+    /// Generates simple XOR parity packets, each protecting `group_size` consecutive source
+    /// symbols of a source block; the last group of each block may be shorter.
+    ///
+    /// This is a lightweight alternative to repair packets for links with very low loss: both
+    /// generating and applying parity is a pass of XORs, without a RaptorQ solve. However, each
+    /// parity packet can only recover a single lost source symbol in its group, using
+    /// [`XorParityPacket::recover`], and does nothing if two or more are lost. Panics if
+    /// `group_size` is zero.
+    pub fn xor_parity(&self, group_size: u32) -> Vec<XorParityPacket> {
+        assert!(group_size > 0);
         let mut packets = vec![];
-        for encoder in self.blocks.iter() {
-            packets.extend(encoder.source_packets());
-            packets.extend(encoder.repair_packets(0, repair_packets_per_block));
+        for block in self.blocks.iter() {
+            for (i, group) in block.source_symbols.chunks(group_size as usize).enumerate() {
+                let mut parity = group[0].clone();
+                for symbol in group[1..].iter() {
+                    parity += symbol;
+                }
+                packets.push(XorParityPacket::new(
+                    PayloadId::new(block.source_block_id, i as u32 * group_size),
+                    group.len() as u16,
+                    parity.into_bytes(),
+                ));
+            }
         }
         packets
     }
 
+    /// Borrows the data of a source symbol, without copying it.
+    ///
+    /// Without sub-blocking, this is the same as the range of the original object given by
+    /// [`ObjectTransmissionInformation::symbol_byte_range`], so the zero padding of the final
+    /// source symbol is excluded. With sub-blocking, each symbol interleaves parts of several
+    /// ranges of the object, and the full padded symbol is returned. Returns `None` for padding
+    /// and repair ESIs, which have no corresponding object data, and for invalid SBNs.
+    pub fn source_symbol_slice(&self, sbn: u8, esi: u32) -> Option<&[u8]> {
+        let encoder = self.blocks.get(sbn as usize)?;
+        let symbol = encoder.source_symbols.get(esi as usize)?.as_bytes();
+        let last_block = sbn as usize == self.blocks.len() - 1;
+        let last_symbol = esi as usize == encoder.source_symbols.len() - 1;
+        if last_block && last_symbol && self.config.sub_blocks() == 1 {
+            let padding = self.config.padding_bytes() as usize;
+            return Some(&symbol[..symbol.len() - padding]);
+        }
+        Some(symbol)
+    }
+
     pub fn get_block_encoders(&self) -> &Vec<SourceBlockEncoder> {
         &self.blocks
     }
+
+    /// Extends the object with `more` bytes, re-encoding only the source blocks whose contents
+    /// change, and returns the new source packets of those blocks. Repair packets for them can
+    /// then be generated with [`encode_blocks`](Self::encode_blocks).
+    ///
+    /// The transfer length is part of the OTI, so receivers must use the updated
+    /// [`get_config`](Self::get_config), and packets generated before the append remain valid only
+    /// for the unchanged blocks. The number of source blocks is kept the same, so an append which
+    /// only fills the padding of the final symbol re-encodes just the last block, whereas one which
+    /// adds symbols changes the partitioning into blocks (see
+    /// [section 4.4.1.2](https://tools.ietf.org/html/rfc6330#section-4.4.1.2)), and re-encodes
+    /// every block from the first one that changed size. No packets are returned if no block
+    /// changed. Panics if the object would exceed the maximum number of symbols per source block.
+    pub fn append(&mut self, more: &[u8]) -> Vec<EncodingPacket> {
+        let old_config = self.config;
+        let config = ObjectTransmissionInformation::new(
+            old_config.transfer_length() + more.len() as u64,
+            old_config.symbol_size(),
+            old_config.source_blocks(),
+            old_config.sub_blocks(),
+            old_config.symbol_alignment(),
+        );
+        let old_offsets = block_offsets(&old_config);
+        let new_offsets = block_offsets(&config);
+        let old_length = old_config.transfer_length() as usize;
+        // Blocks are unchanged if they have the same bounds, and contain no padding
+        let first_changed = old_offsets
+            .iter()
+            .zip(new_offsets.iter())
+            .position(|(old, new)| old != new || old.1 > old_length)
+            .unwrap_or_else(|| old_offsets.len().min(new_offsets.len()));
+        if first_changed == new_offsets.len() {
+            // Nothing was appended to an object without padding
+            return vec![];
+        }
+
+        let start = new_offsets[first_changed].0;
+        let mut data = vec![];
+        for block in self.blocks[first_changed..].iter() {
+            data.extend(block.source_bytes(&old_config));
+        }
+        data.truncate(old_length - start);
+        data.extend_from_slice(more);
+
+        self.blocks.truncate(first_changed);
+        for (i, &(block_start, block_end)) in new_offsets.iter().enumerate().skip(first_changed) {
+            let (block_start, block_end) = (block_start - start, block_end - start);
+            let mut block = Vec::from(&data[block_start..block_end.min(data.len())]);
+            block.resize(block_end - block_start, 0);
+            self.blocks
+                .push(SourceBlockEncoder::new2(i as u8, &config, &block));
+        }
+        self.config = config;
+
+        let mut packets = vec![];
+        for i in first_changed..self.blocks.len() {
+            packets.extend(self.block_packets(i, 0));
+        }
+        packets
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -180,16 +634,92 @@ impl SourceBlockEncodingPlan {
             source_symbol_count: symbol_count,
         }
     }
+
+    /// Generates a plan for the `kprime_index`th supported extended source block size, K', in
+    /// the table of [section 5.6](https://tools.ietf.org/html/rfc6330#section-5.6).
+    ///
+    /// The constraint matrix, and therefore the plan, depends only on K', so the result can be
+    /// reused to encode any source block with the same K', regardless of its symbol size or exact
+    /// number of source symbols. This avoids a matrix solve per block, when many objects of
+    /// similar size are encoded. With the `serde_support` feature, plans can also be persisted and
+    /// reloaded.
+    ///
+    /// A plan stores one operation (of roughly 32 bytes) per row operation of the solve, and the
+    /// number of operations grows faster than linearly with K': a plan for K' = 101 occupies about
+    /// 300KB, and one for K' = 1002 about 2.4MB. See [`size_in_bytes`](Self::size_in_bytes).
+    pub fn precompute_for_kprime(kprime_index: usize) -> SourceBlockEncodingPlan {
+        assert!(kprime_index < SYSTEMATIC_INDICES_AND_PARAMETERS.len());
+        let (kprime, _, _, _, _) = SYSTEMATIC_INDICES_AND_PARAMETERS[kprime_index];
+        SourceBlockEncodingPlan::generate(kprime as u16)
+    }
+
+    /// Approximate memory used by this plan
+    pub fn size_in_bytes(&self) -> usize {
+        let mut size = size_of::<Self>() + self.operations.capacity() * size_of::<SymbolOps>();
+        for op in self.operations.iter() {
+            if let SymbolOps::Reorder { order } = op {
+                size += order.capacity() * size_of::<usize>();
+            }
+        }
+        size
+    }
+
+    // Plans may be used for any source block with the same extended source block size
+    fn supports(&self, source_symbols: usize) -> bool {
+        extended_source_block_symbols(source_symbols as u32)
+            == extended_source_block_symbols(self.source_symbol_count as u32)
+    }
 }
 
+/// Generates the encoding symbols of a single source block.
+///
+/// Every encoding symbol is a pure function of the block's data, the object transmission
+/// information and its ESI, as specified in section 5.3. The only state which changes as symbols
+/// are generated is the count returned by [`symbols_generated`](Self::symbols_generated), which
+/// isn't part of the encoder's value and has no effect on the symbols. A sender which restarts
+/// mid-transfer can therefore construct a new encoder from the same data and configuration, and
+/// regenerate exactly the symbols it sent before, in any order. This makes it safe to retransmit
+/// a symbol with the same ESI after a restart.
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct SourceBlockEncoder {
     source_block_id: u8,
     source_symbols: Vec<Symbol>,
     intermediate_symbols: Vec<Symbol>,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    repair_symbols_generated: SymbolCounter,
+}
+
+// Number of symbols generated, which is updated through shared references to the encoder. It's
+// accounting state, rather than part of the encoder's value, so it's ignored when comparing
+// encoders, and isn't serialized. Atomic, so that encoders remain Sync
+#[derive(Debug, Default)]
+struct SymbolCounter(AtomicU32);
+
+impl SymbolCounter {
+    fn add(&self, symbols: u32) {
+        self.0.fetch_add(symbols, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
 }
 
+impl Clone for SymbolCounter {
+    fn clone(&self) -> SymbolCounter {
+        SymbolCounter(AtomicU32::new(self.get()))
+    }
+}
+
+impl PartialEq for SymbolCounter {
+    fn eq(&self, _: &SymbolCounter) -> bool {
+        true
+    }
+}
+
+impl Eq for SymbolCounter {}
+
 impl SourceBlockEncoder {
     #[deprecated(
         since = "1.3.0",
@@ -211,7 +741,7 @@ impl SourceBlockEncoder {
             // Divide the block into sub-blocks and then concatenate the sub-symbols into symbols
             // See second to last paragraph in section 4.4.1.2.
             let mut offset = 0;
-            for sub_block in 0..(nl + ns) as u32 {
+            for sub_block in 0..(nl + ns) {
                 let bytes = if sub_block < nl {
                     tl as usize * config.symbol_alignment() as usize
                 } else {
@@ -231,6 +761,37 @@ impl SourceBlockEncoder {
         }
     }
 
+    // Inverse of create_symbols()
+    fn source_bytes(&self, config: &ObjectTransmissionInformation) -> Vec<u8> {
+        let mut data =
+            Vec::with_capacity(self.source_symbols.len() * config.symbol_size() as usize);
+        if config.sub_blocks() > 1 {
+            let (tl, ts, nl, ns) = partition(
+                (config.symbol_size() / config.symbol_alignment() as u16) as u32,
+                config.sub_blocks(),
+            );
+            let mut symbol_offset = 0;
+            for sub_block in 0..(nl + ns) {
+                let bytes = if sub_block < nl {
+                    tl as usize * config.symbol_alignment() as usize
+                } else {
+                    ts as usize * config.symbol_alignment() as usize
+                };
+                for symbol in self.source_symbols.iter() {
+                    data.extend_from_slice(
+                        &symbol.as_bytes()[symbol_offset..symbol_offset + bytes],
+                    );
+                }
+                symbol_offset += bytes;
+            }
+        } else {
+            for symbol in self.source_symbols.iter() {
+                data.extend_from_slice(symbol.as_bytes());
+            }
+        }
+        data
+    }
+
     // TODO: rename this to new() in version 2.0
     pub fn new2(
         source_block_id: u8,
@@ -238,7 +799,43 @@ impl SourceBlockEncoder {
         data: &[u8],
     ) -> SourceBlockEncoder {
         let source_symbols = SourceBlockEncoder::create_symbols(config, data);
+        SourceBlockEncoder::from_source_symbols(source_block_id, config, source_symbols)
+    }
 
+    /// Creates an encoder from the source symbols of a block which the caller has already split
+    /// into `symbol_size` chunks, taking ownership of them rather than copying the block. The last
+    /// symbol may be shorter, and is zero padded.
+    ///
+    /// With more than one sub-block, each encoding symbol is made up of sub-symbols from across
+    /// the block (see section 4.4.1.2), so the chunks are concatenated and split as in
+    /// [`new2`](Self::new2).
+    ///
+    /// Panics if a symbol other than the last is not `symbol_size` bytes, or the last is longer.
+    pub fn from_symbols(
+        source_block_id: u8,
+        config: &ObjectTransmissionInformation,
+        mut symbols: Vec<Vec<u8>>,
+    ) -> SourceBlockEncoder {
+        let symbol_size = config.symbol_size() as usize;
+        if let Some((last, rest)) = symbols.split_last_mut() {
+            for symbol in rest.iter() {
+                assert_eq!(symbol.len(), symbol_size);
+            }
+            assert!(last.len() <= symbol_size);
+            last.resize(symbol_size, 0);
+        }
+        if config.sub_blocks() > 1 {
+            return SourceBlockEncoder::new2(source_block_id, config, &symbols.concat());
+        }
+        let source_symbols = symbols.drain(..).map(Symbol::new).collect();
+        SourceBlockEncoder::from_source_symbols(source_block_id, config, source_symbols)
+    }
+
+    fn from_source_symbols(
+        source_block_id: u8,
+        config: &ObjectTransmissionInformation,
+        source_symbols: Vec<Symbol>,
+    ) -> SourceBlockEncoder {
         let (intermediate_symbols, _) = gen_intermediate_symbols(
             &source_symbols,
             config.symbol_size() as usize,
@@ -249,6 +846,7 @@ impl SourceBlockEncoder {
             source_block_id,
             source_symbols,
             intermediate_symbols: intermediate_symbols.unwrap(),
+            repair_symbols_generated: SymbolCounter::default(),
         }
     }
 
@@ -274,8 +872,7 @@ impl SourceBlockEncoder {
         plan: &SourceBlockEncodingPlan,
     ) -> SourceBlockEncoder {
         let source_symbols = SourceBlockEncoder::create_symbols(config, data);
-        // TODO: this could be more lenient and support anything with the same extended symbol count
-        assert_eq!(source_symbols.len(), plan.source_symbol_count as usize);
+        assert!(plan.supports(source_symbols.len()));
 
         let intermediate_symbols = gen_intermediate_symbols_with_plan(
             &source_symbols,
@@ -287,9 +884,61 @@ impl SourceBlockEncoder {
             source_block_id,
             source_symbols,
             intermediate_symbols,
+            repair_symbols_generated: SymbolCounter::default(),
         }
     }
 
+    /// Returns the range of ESIs which identify repair symbols of this block: from K' up to the
+    /// limit of the 24-bit ESI field.
+    pub fn repair_esi_range(&self) -> Range<u32> {
+        self.first_repair_esi()..MAX_ESI
+    }
+
+    /// Returns the ESI of the first repair symbol of this block, K', the number of symbols in the
+    /// extended source block. See section 5.3.1.
+    pub fn first_repair_esi(&self) -> u32 {
+        extended_source_block_symbols(self.source_symbols.len() as u32)
+    }
+
+    /// Returns the ESIs to send for this block: every source symbol, followed by `margin` repair
+    /// symbols. These can be generated with [`packets_for_esis`](Self::packets_for_esis).
+    ///
+    /// This isn't the ESI range `0..(K' + margin)`: the padding ESIs between K and K' are skipped,
+    /// since they are never sent, and the repair ESIs start at K', so the plan has K + `margin`
+    /// ESIs. The margin sets the reliability of the transmission, since each symbol received
+    /// beyond K makes decoding much more likely to succeed (see
+    /// [`DecodeStrategy::OnThreshold`](crate::DecodeStrategy::OnThreshold) for the failure
+    /// probabilities), so the margin should cover the expected loss plus a couple of symbols.
+    pub fn transmission_plan(&self, margin: u32) -> Vec<u32> {
+        let source_symbols = self.source_symbols.len() as u32;
+        let first_repair_esi = self.first_repair_esi();
+        assert!(margin <= MAX_ESI - first_repair_esi);
+        (0..source_symbols)
+            .chain(first_repair_esi..(first_repair_esi + margin))
+            .collect()
+    }
+
+    /// Returns the number of repair symbols this block can generate before the 24-bit ESI space
+    /// is exhausted, i.e. 2^24 - K'.
+    ///
+    /// ESIs never wrap around, as that would silently reuse the ESIs of source symbols and
+    /// earlier repair symbols. Long-running senders should stop, or move on to a new source
+    /// block, once they have generated this many repair symbols.
+    pub fn remaining_esi_space(&self) -> u32 {
+        let repair_esis = self.repair_esi_range();
+        repair_esis.end - repair_esis.start
+    }
+
+    /// Returns the number of repair symbols that this encoder has generated so far, by any of its
+    /// methods, for bandwidth accounting or to resume a transfer. Source symbols are copies of the
+    /// block's data, and are not counted. Repair symbols which are generated more than once, e.g.
+    /// for retransmission, are counted each time.
+    ///
+    /// The count is not part of the encoder's value: it is ignored by `==`, and is not serialized.
+    pub fn symbols_generated(&self) -> u32 {
+        self.repair_symbols_generated.get()
+    }
+
     pub fn source_packets(&self) -> Vec<EncodingPacket> {
         let mut esi: i32 = -1;
         self.source_symbols
@@ -305,27 +954,207 @@ impl SourceBlockEncoder {
     }
 
     // See section 5.3.4
+    //
+    // Panics if the requested repair symbols extend beyond remaining_esi_space()
     pub fn repair_packets(&self, start_repair_symbol_id: u32, packets: u32) -> Vec<EncodingPacket> {
-        let start_encoding_symbol_id = start_repair_symbol_id
-            + extended_source_block_symbols(self.source_symbols.len() as u32);
-        let mut result = vec![];
-        let lt_symbols = num_lt_symbols(self.source_symbols.len() as u32);
-        let sys_index = systematic_index(self.source_symbols.len() as u32);
-        let p1 = calculate_p1(self.source_symbols.len() as u32);
+        assert!(
+            start_repair_symbol_id as u64 + packets as u64 <= self.remaining_esi_space() as u64,
+            "repair symbols {}..{} exceed the 24-bit ESI space",
+            start_repair_symbol_id,
+            start_repair_symbol_id as u64 + packets as u64
+        );
+        let start_encoding_symbol_id = start_repair_symbol_id + self.first_repair_esi();
+        self.repair_symbols_generated.add(packets);
+        let mut result = Vec::with_capacity(packets as usize);
         for i in 0..packets {
-            let tuple = intermediate_tuple(start_encoding_symbol_id + i, lt_symbols, sys_index, p1);
             result.push(EncodingPacket::new(
                 PayloadId::new(self.source_block_id, start_encoding_symbol_id + i),
-                enc(
-                    self.source_symbols.len() as u32,
-                    &self.intermediate_symbols,
-                    tuple,
-                )
-                .into_bytes(),
+                self.repair_symbol(start_encoding_symbol_id + i)
+                    .into_bytes(),
             ));
         }
         result
     }
+
+    /// Like [`repair_packets`](Self::repair_packets), but generates the repair symbols across the
+    /// threads of the rayon thread pool. The intermediate symbols are solved for once, when the
+    /// encoder is constructed, after which each repair symbol is computed independently, so this
+    /// is intended for generating many repair symbols of a single large block, where there are too
+    /// few blocks to parallelize across. Any speedup depends on the number of cores: on a single
+    /// core it's slightly slower than `repair_packets`, due to the overhead of the thread pool.
+    ///
+    /// Requires the `parallel` feature, which has a higher minimum Rust version than the rest of
+    /// the crate. See the README.
+    #[cfg(feature = "parallel")]
+    pub fn par_repair_packets(
+        &self,
+        start_repair_symbol_id: u32,
+        packets: u32,
+    ) -> Vec<EncodingPacket> {
+        assert!(
+            start_repair_symbol_id as u64 + packets as u64 <= self.remaining_esi_space() as u64,
+            "repair symbols {}..{} exceed the 24-bit ESI space",
+            start_repair_symbol_id,
+            start_repair_symbol_id as u64 + packets as u64
+        );
+        let start_encoding_symbol_id = start_repair_symbol_id + self.first_repair_esi();
+        // Counted once for the whole batch, so that the workers don't contend on the counter
+        self.repair_symbols_generated.add(packets);
+        (start_encoding_symbol_id..(start_encoding_symbol_id + packets))
+            .into_par_iter()
+            .map(|esi| {
+                EncodingPacket::new(
+                    PayloadId::new(self.source_block_id, esi),
+                    self.repair_symbol(esi).into_bytes(),
+                )
+            })
+            .collect()
+    }
+
+    /// Lazily generates the encoding symbols of this block, in ESI order, starting at
+    /// `start_esi` and ending with the last valid 24-bit ESI.
+    ///
+    /// ESIs of the padding symbols between K and K' are never transmitted, and are skipped.
+    /// Source symbols borrow from this encoder, so each [`SymbolBuf`] may not outlive it; repair
+    /// symbols are computed as the iterator is advanced.
+    pub fn symbols_from(&self, start_esi: u32) -> impl Iterator<Item = (u32, SymbolBuf<'_>)> {
+        self.symbols_between(start_esi..self.repair_esi_range().end)
+    }
+
+    // Lazily generates the encoding symbols with ESIs in `esis`, skipping the padding ESIs. The
+    // range is bounded before any symbol is generated, so none are computed and thrown away
+    fn symbols_between(&self, esis: Range<u32>) -> impl Iterator<Item = (u32, SymbolBuf<'_>)> {
+        let source_symbols = self.source_symbols.len() as u32;
+        let repair_esis = self.repair_esi_range();
+        esis.filter(move |&esi| esi < source_symbols || esi >= repair_esis.start)
+            .map(move |esi| {
+                let symbol = if esi < source_symbols {
+                    Cow::Borrowed(self.source_symbols[esi as usize].as_bytes())
+                } else {
+                    self.repair_symbols_generated.add(1);
+                    Cow::Owned(self.repair_symbol(esi).into_bytes())
+                };
+                (esi, SymbolBuf { data: symbol })
+            })
+    }
+
+    /// Generates the packets for the ESIs in `range`, skipping the padding ESIs between K and K'.
+    /// Useful for sharding symbol generation across workers, each of which owns a range of ESIs.
+    ///
+    /// Panics if the range extends beyond the 24-bit ESI space.
+    pub fn symbols_in_range(&self, range: Range<u32>) -> impl Iterator<Item = EncodingPacket> + '_ {
+        assert!(range.start <= range.end);
+        assert!(
+            range.end <= self.repair_esi_range().end,
+            "ESI range {:?} exceeds the 24-bit ESI space",
+            range
+        );
+        let source_block_id = self.source_block_id;
+        self.symbols_between(range).map(move |(esi, symbol)| {
+            EncodingPacket::new(PayloadId::new(source_block_id, esi), symbol.into_vec())
+        })
+    }
+
+    /// Generates the packets for an arbitrary mix of source and repair ESIs, in the order given.
+    ///
+    /// The intermediate symbols are solved for once, when the encoder is constructed, so every
+    /// symbol is a copy of a source symbol or a linear combination of the intermediate symbols,
+    /// and no matrix solve is performed here, however many ESIs are requested.
+    ///
+    /// Panics if an ESI is one of the padding ESIs between K and K', or is not a 24-bit ESI.
+    pub fn packets_for_esis(&self, esis: &[u32]) -> Vec<EncodingPacket> {
+        let source_symbols = self.source_symbols.len() as u32;
+        let repair_esis = self.repair_esi_range();
+        let packets = esis
+            .iter()
+            .map(|&esi| {
+                let data = if esi < source_symbols {
+                    self.source_symbols[esi as usize].as_bytes().to_vec()
+                } else {
+                    assert!(
+                        repair_esis.contains(&esi),
+                        "ESI {} is not a source or repair ESI",
+                        esi
+                    );
+                    self.repair_symbol(esi).into_bytes()
+                };
+                EncodingPacket::new(PayloadId::new(self.source_block_id, esi), data)
+            })
+            .collect();
+        self.repair_symbols_generated
+            .add(esis.iter().filter(|&&esi| esi >= source_symbols).count() as u32);
+        packets
+    }
+
+    /// Generates packets for the first `count` ESIs of this block which are not in `already_sent`,
+    /// for retransmitting without repeating symbols that are presumably still in flight.
+    ///
+    /// ESIs are visited in ascending order, so unsent source symbols come first, followed by the
+    /// repair symbols from K' onwards. The padding ESIs between K and K' are never generated.
+    /// Fewer than `count` packets are returned if the 24-bit ESI space is exhausted.
+    pub fn symbols_excluding<'a>(
+        &'a self,
+        already_sent: &'a HashSet<u32>,
+        count: u32,
+    ) -> impl Iterator<Item = EncodingPacket> + 'a {
+        let source_symbols = self.source_symbols.len() as u32;
+        let source_block_id = self.source_block_id;
+        // Filter the ESIs before generating anything, so that the repair symbols which were
+        // already sent aren't computed
+        (0..source_symbols)
+            .chain(self.repair_esi_range())
+            .filter(move |esi| !already_sent.contains(esi))
+            .take(count as usize)
+            .map(move |esi| {
+                let data = if esi < source_symbols {
+                    self.source_symbols[esi as usize].as_bytes().to_vec()
+                } else {
+                    self.repair_symbols_generated.add(1);
+                    self.repair_symbol(esi).into_bytes()
+                };
+                EncodingPacket::new(PayloadId::new(source_block_id, esi), data)
+            })
+    }
+
+    // Callers add the symbols they generate to repair_symbols_generated themselves, once per call
+    // where they can
+    fn repair_symbol(&self, encoding_symbol_id: u32) -> Symbol {
+        let source_symbols = self.source_symbols.len() as u32;
+        let tuple = intermediate_tuple(
+            encoding_symbol_id,
+            num_lt_symbols(source_symbols),
+            systematic_index(source_symbols),
+            calculate_p1(source_symbols),
+        );
+        enc(source_symbols, &self.intermediate_symbols, tuple)
+    }
+}
+
+/// Bytes of a single encoding symbol, which may borrow from the encoder that produced it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymbolBuf<'a> {
+    data: Cow<'a, [u8]>,
+}
+
+impl<'a> SymbolBuf<'a> {
+    /// Returns the symbol as an owned `Vec`, copying it only if borrowed.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data.into_owned()
+    }
+}
+
+impl<'a> Deref for SymbolBuf<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl<'a> AsRef<[u8]> for SymbolBuf<'a> {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
 }
 
 #[allow(non_snake_case)]
@@ -346,7 +1175,7 @@ fn create_d(
         D.push(symbol.clone());
     }
     // Extend the source block with padding. See section 5.3.2
-    for _ in 0..(extended_source_symbols as usize - source_block.len()) {
+    for _ in 0..(extended_source_symbols - source_block.len()) {
         D.push(Symbol::zero(symbol_size));
     }
     assert_eq!(D.len(), L as usize);
@@ -442,9 +1271,13 @@ mod tests {
     use crate::systematic_constants::num_lt_symbols;
     use crate::systematic_constants::num_pi_symbols;
     use crate::systematic_constants::{
-        calculate_p1, num_ldpc_symbols, systematic_index, MAX_SOURCE_SYMBOLS_PER_BLOCK,
+        calculate_p1, extended_source_block_symbols, num_ldpc_symbols, systematic_index,
+        MAX_SOURCE_SYMBOLS_PER_BLOCK,
     };
-    use crate::{Encoder, EncoderBuilder, EncodingPacket, ObjectTransmissionInformation};
+    use crate::{code_rate, recommend_symbol_size, repair_symbols_for_code_rate};
+    use crate::{Decoder, Encoder, EncoderBuilder, EncodingPacket, ObjectTransmissionInformation};
+    use crate::{ParseError, TransferManifest};
+    use crate::{SourceBlockDecoder, SourceBlockEncoder, SourceBlockEncodingPlan};
     use std::collections::HashSet;
 
     const SYMBOL_SIZE: usize = 4;
@@ -452,12 +1285,20 @@ mod tests {
 
     fn gen_test_data(size: usize) -> Vec<u8> {
         let mut data: Vec<u8> = vec![0; size];
-        for i in 0..size {
-            data[i] = rand::thread_rng().gen();
+        for element in &mut data {
+            *element = rand::thread_rng().gen();
         }
         data
     }
 
+    // A single source block encoder with K = 25 and K' = 26, with its data and configuration
+    fn test_block_encoder() -> (Vec<u8>, ObjectTransmissionInformation, SourceBlockEncoder) {
+        let data = gen_test_data(SYMBOL_SIZE * 25);
+        let config = ObjectTransmissionInformation::new(0, SYMBOL_SIZE as u16, 0, 1, 1);
+        let encoder = SourceBlockEncoder::new2(0, &config, &data);
+        (data, config, encoder)
+    }
+
     fn gen_test_symbols() -> Vec<Symbol> {
         let mut source_block: Vec<Symbol> = vec![];
         for _ in 0..NUM_SYMBOLS {
@@ -477,6 +1318,7 @@ mod tests {
         enc_constraint(0);
     }
 
+    #[allow(clippy::needless_range_loop)]
     fn enc_constraint(sparse_threshold: u32) {
         let source_symbols = gen_test_symbols();
 
@@ -505,7 +1347,7 @@ mod tests {
         ldpc_constraint(0);
     }
 
-    #[allow(non_snake_case)]
+    #[allow(non_snake_case, clippy::needless_range_loop)]
     fn ldpc_constraint(sparse_threshold: u32) {
         let (intermediate_symbols, _) =
             gen_intermediate_symbols(&gen_test_symbols(), SYMBOL_SIZE, sparse_threshold);
@@ -590,6 +1432,641 @@ mod tests {
         assert_eq!(data[..], padded_data[..data_size]);
     }
 
+    #[test]
+    fn source_symbol_slices() {
+        let data = gen_test_data(10_000 - 10);
+        // Kt = 157, so block 0 has 79 symbols, and block 1 has 78, the last of which is padded
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        for sbn in 0..2 {
+            for esi in 0..config.symbols_in_block(sbn) {
+                let range = config.symbol_byte_range(sbn, esi).unwrap();
+                assert_eq!(
+                    encoder.source_symbol_slice(sbn, esi),
+                    Some(&data[range.start as usize..range.end as usize])
+                );
+            }
+        }
+        assert_eq!(encoder.source_symbol_slice(1, 77).unwrap().len(), 6);
+        // Padding, repair and invalid blocks
+        assert_eq!(encoder.source_symbol_slice(1, 78), None);
+        assert_eq!(encoder.source_symbol_slice(0, 1000), None);
+        assert_eq!(encoder.source_symbol_slice(2, 0), None);
+
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 2, 8);
+        let encoder = Encoder::new(&data, config);
+        let packets = encoder.get_encoded_packets(0);
+        let last = packets.last().unwrap();
+        assert_eq!(encoder.source_symbol_slice(1, 77), Some(last.data()));
+    }
+
+    #[test]
+    fn recommended_symbol_sizes() {
+        assert_eq!(recommend_symbol_size(1_000_000, 1500), 1464);
+        assert_eq!(recommend_symbol_size(14_640, 1500), 1464);
+        assert_eq!(recommend_symbol_size(14_639, 1500), 1464);
+        assert_eq!(recommend_symbol_size(14_560, 1500), 1456);
+        assert_eq!(recommend_symbol_size(1_000_000, 576), 544);
+        assert_eq!(recommend_symbol_size(100, 1500), 16);
+        assert_eq!(recommend_symbol_size(1, 1500), 8);
+        assert_eq!(recommend_symbol_size(0, 1500), 8);
+        assert_eq!(recommend_symbol_size(1_000_000, 40), 8);
+        assert_eq!(recommend_symbol_size(1_000_000, 39), 8);
+        assert_eq!(recommend_symbol_size(1_000_000, 0), 8);
+    }
+
+    #[test]
+    fn code_rates() {
+        assert_eq!(code_rate(100, 0), 1.0);
+        assert_eq!(code_rate(100, 25), 0.8);
+        assert_eq!(code_rate(10, 10), 0.5);
+        assert_eq!(repair_symbols_for_code_rate(100, 1.0), 0);
+        assert_eq!(repair_symbols_for_code_rate(100, 0.8), 25);
+        assert_eq!(repair_symbols_for_code_rate(100, 0.79), 27);
+        assert_eq!(repair_symbols_for_code_rate(10, 0.5), 10);
+        for source_symbols in 1..200 {
+            for &rate in [0.1, 0.3, 0.5, 0.7, 0.9, 0.95].iter() {
+                let repair = repair_symbols_for_code_rate(source_symbols, rate);
+                assert!(code_rate(source_symbols, repair) <= rate);
+                if repair > 0 {
+                    assert!(code_rate(source_symbols, repair - 1) > rate);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn append() {
+        let data = gen_test_data(20_000);
+        for &(blocks, sub_blocks) in [(1, 1), (4, 1), (4, 2)].iter() {
+            let config = ObjectTransmissionInformation::new(10_000, 64, blocks, sub_blocks, 8);
+            let mut encoder = Encoder::new(&data[..10_000], config);
+            let mut total = 10_000;
+            for &length in [0, 1, 63, 2_000, 7_936].iter() {
+                let packets = encoder.append(&data[total..total + length]);
+                total += length;
+                let expected = Encoder::new(&data[..total], encoder.get_config());
+                assert_eq!(encoder, expected);
+                assert!(!packets.is_empty());
+                let expected_packets = expected.get_encoded_packets(0);
+                assert_eq!(
+                    &packets[..],
+                    &expected_packets[expected_packets.len() - packets.len()..]
+                );
+            }
+        }
+
+        // Growth within the final symbol leaves the other blocks untouched
+        let length = 64 * 40 - 30;
+        let config = ObjectTransmissionInformation::new(length as u64, 64, 4, 1, 8);
+        let mut encoder = Encoder::new(&data[..length], config);
+        let packets = encoder.append(&data[length..length + 10]);
+        assert!(packets
+            .iter()
+            .all(|packet| packet.payload_id().source_block_number() == 3));
+
+        // With no padding, appending nothing changes no block
+        let config = ObjectTransmissionInformation::new(64 * 40, 64, 4, 1, 8);
+        let mut encoder = Encoder::new(&data[..64 * 40], config);
+        assert!(encoder.append(&[]).is_empty());
+        assert_eq!(encoder, Encoder::new(&data[..64 * 40], config));
+    }
+
+    #[test]
+    fn repair_only() {
+        let data = gen_test_data(10_000);
+        let mut builder = EncoderBuilder::new();
+        builder.set_max_packet_size(1024);
+        builder.set_repair_only(true);
+        let encoder = builder.build(&data);
+
+        // K = 10. Decodability depends only on the ESIs received, so this is deterministic
+        let packets = encoder.get_encoded_packets(12);
+        assert_eq!(packets.len(), 12);
+        let repair_esis = encoder.get_block_encoders()[0].repair_esi_range();
+        assert!(packets
+            .iter()
+            .all(|packet| repair_esis.contains(&packet.payload_id().encoding_symbol_id())));
+
+        let mut decoder = Decoder::new(encoder.get_config());
+        let mut result = None;
+        for packet in packets {
+            result = decoder.decode(packet);
+        }
+        assert_eq!(result.unwrap(), data);
+    }
+
+    #[test]
+    fn unpadded_last_symbol() {
+        let data = gen_test_data(10_000);
+        let mut builder = EncoderBuilder::new();
+        builder.set_max_packet_size(1024);
+        let padded = builder.build(&data);
+        builder.set_pad_last_symbol(false);
+        let unpadded = builder.build(&data);
+
+        let config = padded.get_config();
+        assert_eq!(config.padding_bytes(), 240);
+        let padded_packets = padded.get_encoded_packets(0);
+        let unpadded_packets = unpadded.get_encoded_packets(0);
+        assert!(padded_packets
+            .iter()
+            .all(|packet| packet.data().len() == config.symbol_size() as usize));
+        let last = unpadded_packets.last().unwrap();
+        assert_eq!(
+            last.data().len() as u64,
+            config.symbol_size() as u64 - config.padding_bytes()
+        );
+        let total: usize = unpadded_packets.iter().map(|x| x.data().len()).sum();
+        assert_eq!(total, data.len());
+
+        let mut decoder = Decoder::new(config);
+        let mut result = None;
+        for packet in unpadded_packets {
+            result = decoder.decode(packet);
+        }
+        assert_eq!(result.unwrap(), data);
+    }
+
+    #[test]
+    fn encode_subset_of_blocks() {
+        let data = gen_test_data(120);
+        let config = ObjectTransmissionInformation::new(120, 10, 10, 0, 2);
+        let encoder = Encoder::new(&data, config);
+
+        let all = encoder.get_encoded_packets(2);
+        let subset = encoder.encode_blocks(&[5, 9], 2);
+        let expected: Vec<EncodingPacket> = all
+            .iter()
+            .filter(|packet| {
+                let block = packet.payload_id().source_block_number();
+                block == 5 || block == 9
+            })
+            .cloned()
+            .collect();
+        assert_eq!(subset, expected);
+        assert!(encoder.encode_blocks(&[], 2).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn encode_blocks_out_of_range() {
+        let data = gen_test_data(120);
+        let config = ObjectTransmissionInformation::new(120, 10, 10, 0, 2);
+        Encoder::new(&data, config).encode_blocks(&[10], 0);
+    }
+
+    #[test]
+    fn lazy_symbols() {
+        let (_, _, encoder) = test_block_encoder();
+        let mut expected = encoder.source_packets();
+        expected.extend(encoder.repair_packets(0, 5));
+
+        let symbols: Vec<(u32, Vec<u8>)> = encoder
+            .symbols_from(0)
+            .take(30)
+            .map(|(esi, symbol)| (esi, symbol.into_vec()))
+            .collect();
+        for ((esi, symbol), packet) in symbols.iter().zip(expected.iter()) {
+            assert_eq!(*esi, packet.payload_id().encoding_symbol_id());
+            assert_eq!(&symbol[..], packet.data());
+        }
+
+        let (esi, symbol) = encoder.symbols_from(24).nth(1).unwrap();
+        assert_eq!(esi, 26);
+        assert_eq!(&*symbol, expected[25].data());
+        assert_eq!(encoder.symbols_from(16777215).count(), 1);
+    }
+
+    #[test]
+    fn symbols_in_range() {
+        let (_, _, encoder) = test_block_encoder();
+        let mut expected = encoder.source_packets();
+        expected.extend(encoder.repair_packets(0, 24));
+
+        // Shards which split the source symbols, the padding symbol, and the repair symbols
+        let mut sharded = vec![];
+        for &(start, end) in [(0, 10), (10, 25), (25, 26), (26, 40), (40, 50)].iter() {
+            sharded.extend(encoder.symbols_in_range(start..end));
+        }
+        assert_eq!(sharded, expected);
+        // No repair symbol beyond the end of a shard is generated
+        assert_eq!(encoder.symbols_generated(), 24 + 24);
+        assert_eq!(encoder.symbols_in_range(20..20).count(), 0);
+        assert_eq!(encoder.symbols_in_range(16777210..16777216).count(), 6);
+    }
+
+    #[test]
+    fn symbols_generated() {
+        let (data, config, encoder) = test_block_encoder();
+        assert_eq!(encoder.symbols_generated(), 0);
+        encoder.source_packets();
+        assert_eq!(encoder.symbols_generated(), 0);
+        encoder.repair_packets(0, 10);
+        assert_eq!(encoder.symbols_generated(), 10);
+        // K' = 26, so this is one source and two repair symbols
+        encoder.packets_for_esis(&[3, 30, 30]);
+        assert_eq!(encoder.symbols_generated(), 12);
+        // Lazily generated symbols are counted as they're generated
+        let mut symbols = encoder.symbols_from(24);
+        symbols.next();
+        symbols.next();
+        assert_eq!(encoder.symbols_generated(), 13);
+
+        let clone = encoder.clone();
+        assert_eq!(clone.symbols_generated(), 13);
+        assert_eq!(clone, SourceBlockEncoder::new2(0, &config, &data));
+    }
+
+    #[test]
+    fn remaining_esi_space() {
+        let (_, _, encoder) = test_block_encoder();
+        // K' = 26
+        let remaining = encoder.remaining_esi_space();
+        assert_eq!(remaining, 16777216 - 26);
+
+        let last = encoder.repair_packets(remaining - 2, 2);
+        assert_eq!(last[1].payload_id().encoding_symbol_id(), 16777215);
+        assert_eq!(encoder.repair_packets(remaining, 0).len(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn repair_packets_beyond_esi_space() {
+        let (_, _, encoder) = test_block_encoder();
+        encoder.repair_packets(encoder.remaining_esi_space() - 1, 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn repair_packets_esi_wrap() {
+        let (_, _, encoder) = test_block_encoder();
+        // Would wrap around to ESI 0 if the ESI were computed without overflow checks
+        encoder.repair_packets(u32::MAX - 25, 1);
+    }
+
+    #[test]
+    fn packets_for_esis() {
+        let (_, _, encoder) = test_block_encoder();
+        let mut all = encoder.source_packets();
+        all.extend(encoder.repair_packets(0, 10));
+
+        let packets = encoder.packets_for_esis(&[30, 2, 26, 24, 0, 35]);
+        assert_eq!(
+            packets,
+            vec![
+                all[29].clone(),
+                all[2].clone(),
+                all[25].clone(),
+                all[24].clone(),
+                all[0].clone(),
+                all[34].clone()
+            ]
+        );
+        assert!(encoder.packets_for_esis(&[]).is_empty());
+    }
+
+    #[test]
+    fn symbols_are_deterministic() {
+        let data = gen_test_data(10_000);
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        // Encoders constructed independently, as if by a sender before and after a restart
+        let encoder = Encoder::new(&data, config);
+        let restarted = Encoder::new(&data.clone(), config);
+        // K = 79 and 78, K' = 84
+        let esis = [200, 3, 90_000, 0, 77, 84, 16_777_215];
+        for (block, restarted_block) in encoder
+            .get_block_encoders()
+            .iter()
+            .zip(restarted.get_block_encoders())
+        {
+            let packets = block.packets_for_esis(&esis);
+            assert_eq!(packets, block.packets_for_esis(&esis));
+            // The order in which symbols are generated doesn't matter
+            let mut reversed_esis = esis;
+            reversed_esis.reverse();
+            let mut regenerated = restarted_block.packets_for_esis(&reversed_esis);
+            regenerated.reverse();
+            assert_eq!(packets, regenerated);
+            assert_eq!(
+                block.repair_packets(100, 5),
+                restarted_block.repair_packets(100, 5)
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn packets_for_padding_esi() {
+        let (_, _, encoder) = test_block_encoder();
+        encoder.packets_for_esis(&[25]);
+    }
+
+    #[test]
+    fn symbols_excluding() {
+        let (_, _, encoder) = test_block_encoder();
+        let mut all = encoder.source_packets();
+        all.extend(encoder.repair_packets(0, 10));
+
+        let already_sent: HashSet<u32> = (0..24).chain(26..30).collect();
+        let packets: Vec<EncodingPacket> = encoder.symbols_excluding(&already_sent, 4).collect();
+        let esis: Vec<u32> = packets
+            .iter()
+            .map(|packet| packet.payload_id().encoding_symbol_id())
+            .collect();
+        assert_eq!(esis, vec![24, 30, 31, 32]);
+        assert_eq!(packets[0], all[24]);
+        assert_eq!(packets[1..], all[29..32]);
+
+        let nothing_sent = HashSet::new();
+        assert_eq!(
+            encoder
+                .symbols_excluding(&nothing_sent, 35)
+                .collect::<Vec<_>>(),
+            all
+        );
+        assert_eq!(encoder.symbols_excluding(&nothing_sent, 0).count(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn symbols_in_range_beyond_esi_space() {
+        let (_, _, encoder) = test_block_encoder();
+        encoder.symbols_in_range(16777210..16777217).count();
+    }
+
+    #[test]
+    fn precomputed_plan() {
+        // K' = 101 for both 98 and 101 source symbols
+        let plan = SourceBlockEncodingPlan::precompute_for_kprime(22);
+        for &symbols in [98, 101].iter() {
+            let data = gen_test_data(symbols * SYMBOL_SIZE);
+            let config = ObjectTransmissionInformation::new(0, SYMBOL_SIZE as u16, 0, 1, 1);
+            let planned = SourceBlockEncoder::with_encoding_plan2(0, &config, &data, &plan);
+            let unplanned = SourceBlockEncoder::new2(0, &config, &data);
+            assert_eq!(
+                planned.repair_packets(0, 10),
+                unplanned.repair_packets(0, 10)
+            );
+        }
+
+        // Two blocks of 100 source symbols
+        let data = gen_test_data(200 * 64);
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        assert_eq!(
+            Encoder::with_encoding_plan(&data, config, &plan).get_encoded_packets(5),
+            Encoder::new(&data, config).get_encoded_packets(5)
+        );
+    }
+
+    #[test]
+    fn encode_progress() {
+        let data = gen_test_data(10_000);
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 4, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let mut reports = vec![];
+        let packets = encoder.encode_with_progress(2, |fraction| reports.push(fraction));
+        assert_eq!(reports, vec![0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(packets, encoder.get_encoded_packets(2));
+    }
+
+    #[test]
+    fn diverse_repair_esis() {
+        let data = gen_test_data(10_000);
+        // K = 79 and 78, which both extend to K' = 84
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        let encoder = Encoder::new(&data, config);
+        let space = 16777216 - 84;
+        let esis = encoder.diverse_repair_esis(4);
+        assert_eq!(
+            esis,
+            vec![84, 84 + space / 2, 84 + space / 4, 84 + space * 3 / 4]
+        );
+
+        let many = encoder.diverse_repair_esis(10_000);
+        assert_eq!(many[..4], esis[..]);
+        let distinct: HashSet<u32> = many.iter().cloned().collect();
+        assert_eq!(distinct.len(), 10_000);
+        for block in encoder.get_block_encoders() {
+            let repair_esis = block.repair_esi_range();
+            assert!(many.iter().all(|esi| repair_esis.contains(esi)));
+        }
+
+        // The chosen symbols are usable for decoding
+        let mut decoder = Decoder::new(config);
+        let mut result = None;
+        for block in encoder.get_block_encoders() {
+            for packet in block.packets_for_esis(&encoder.diverse_repair_esis(85)) {
+                result = decoder.decode(packet);
+            }
+        }
+        assert_eq!(result, Some(data));
+    }
+
+    #[test]
+    fn from_slices() {
+        let data = gen_test_data(10_000);
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 3, 1, 8);
+        let expected = Encoder::new(&data, config).get_encoded_packets(5);
+
+        // Boundaries inside symbols, on a symbol boundary, and between source blocks, plus an
+        // empty slice
+        let slices: Vec<&[u8]> = vec![
+            &data[..10],
+            &data[10..640],
+            &data[640..640],
+            &data[640..3400],
+            &data[3400..],
+        ];
+        let encoder = Encoder::from_slices(&slices, config);
+        assert_eq!(encoder.get_encoded_packets(5), expected);
+        assert_eq!(
+            Encoder::from_slices(&[&data[..]], config).get_encoded_packets(5),
+            expected
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_slices_wrong_length() {
+        let data = gen_test_data(10_000);
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 3, 1, 8);
+        Encoder::from_slices(&[&data[..9999]], config);
+    }
+
+    #[test]
+    fn from_oti_bytes() {
+        let data = gen_test_data(10_000);
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        let encoder = Encoder::from_oti_bytes(&data, &config.serialize()).unwrap();
+        assert_eq!(encoder.get_config(), config);
+        assert_eq!(
+            encoder.get_encoded_packets(3),
+            Encoder::new(&data, config).get_encoded_packets(3)
+        );
+
+        assert_eq!(
+            Encoder::from_oti_bytes(&data[..100], &config.serialize()).err(),
+            Some(ParseError::InvalidLength {
+                expected: 10_000,
+                actual: 100
+            })
+        );
+        let mut invalid = config.serialize();
+        invalid[11] = 0;
+        assert_eq!(
+            Encoder::from_oti_bytes(&data, &invalid).err(),
+            Some(ParseError::ZeroAlignment)
+        );
+    }
+
+    #[test]
+    fn repair_seed() {
+        let data = gen_test_data(10_000);
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 2, 1, 8);
+        let mut encoder = Encoder::new(&data, config);
+        let unshuffled = encoder.get_encoded_packets(20);
+        encoder.set_repair_seed(7);
+        let packets = encoder.get_encoded_packets(20);
+        assert_ne!(packets, unshuffled);
+        assert_eq!(packets, encoder.get_encoded_packets(20));
+
+        let mut manifest = TransferManifest::with_uniform_repair(config, 20);
+        manifest.set_repair_seed(7);
+        for block in encoder.get_block_encoders().iter() {
+            let sbn = block.source_block_id;
+            let source_symbols = block.source_symbols.len();
+            let block_packets: Vec<&EncodingPacket> = packets
+                .iter()
+                .filter(|packet| packet.payload_id().source_block_number() == sbn)
+                .collect();
+            let esis: Vec<u32> = block_packets
+                .iter()
+                .map(|packet| packet.payload_id().encoding_symbol_id())
+                .collect();
+            assert_eq!(
+                esis[..source_symbols],
+                (0..source_symbols as u32).collect::<Vec<u32>>()[..]
+            );
+            assert_eq!(
+                esis[source_symbols..],
+                manifest.repair_esi_order(sbn).unwrap()[..]
+            );
+        }
+
+        let mut decoder = Decoder::new(config);
+        let mut result = None;
+        for packet in packets {
+            result = decoder.decode(packet);
+        }
+        assert_eq!(result, Some(data));
+    }
+
+    #[test]
+    fn packets_preallocated() {
+        let data = gen_test_data(10_000);
+        let config = ObjectTransmissionInformation::new(data.len() as u64, 64, 4, 1, 8);
+        let mut encoder = Encoder::new(&data, config);
+        // The output is allocated once, with exactly the number of packets generated
+        for &repair_only in [false, true].iter() {
+            encoder.repair_only = repair_only;
+            let packets = encoder.get_encoded_packets(5);
+            assert_eq!(packets.capacity(), packets.len());
+            let packets = encoder.encode_blocks(&[3, 0], 5);
+            assert_eq!(packets.capacity(), packets.len());
+        }
+        let packets = encoder.get_block_encoders()[0].repair_packets(0, 7);
+        assert_eq!(packets.capacity(), 7);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn par_repair_packets() {
+        let data = gen_test_data(SYMBOL_SIZE * 100);
+        let config = ObjectTransmissionInformation::new(0, SYMBOL_SIZE as u16, 0, 1, 1);
+        let encoder = SourceBlockEncoder::new2(0, &config, &data);
+        assert_eq!(
+            encoder.par_repair_packets(5, 1000),
+            encoder.repair_packets(5, 1000)
+        );
+        assert!(encoder.par_repair_packets(0, 0).is_empty());
+        let remaining = encoder.remaining_esi_space();
+        assert_eq!(
+            encoder.par_repair_packets(remaining - 1, 1),
+            encoder.repair_packets(remaining - 1, 1)
+        );
+        assert_eq!(encoder.symbols_generated(), 2 * 1001);
+    }
+
+    #[test]
+    fn from_symbols() {
+        let data = gen_test_data(SYMBOL_SIZE * 24 + 2);
+        let mut padded = data.clone();
+        padded.resize(SYMBOL_SIZE * 25, 0);
+        let symbols: Vec<Vec<u8>> = data.chunks(SYMBOL_SIZE).map(|x| x.to_vec()).collect();
+        for &sub_blocks in [1, 2].iter() {
+            let config =
+                ObjectTransmissionInformation::new(0, SYMBOL_SIZE as u16, 0, sub_blocks, 2);
+            let encoder = SourceBlockEncoder::from_symbols(3, &config, symbols.clone());
+            assert_eq!(encoder, SourceBlockEncoder::new2(3, &config, &padded));
+            assert_eq!(
+                encoder.repair_packets(0, 5),
+                SourceBlockEncoder::new2(3, &config, &padded).repair_packets(0, 5)
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_symbols_wrong_length() {
+        let config = ObjectTransmissionInformation::new(0, SYMBOL_SIZE as u16, 0, 1, 1);
+        let symbols = vec![vec![0; SYMBOL_SIZE - 1], vec![0; SYMBOL_SIZE]];
+        SourceBlockEncoder::from_symbols(0, &config, symbols);
+    }
+
+    #[test]
+    fn transmission_plan() {
+        let (data, config, encoder) = test_block_encoder();
+        let plan = encoder.transmission_plan(3);
+        let expected: Vec<u32> = (0..25).chain(26..29).collect();
+        assert_eq!(plan, expected);
+        assert_eq!(encoder.transmission_plan(0), (0..25).collect::<Vec<u32>>());
+
+        let packets = encoder.packets_for_esis(&plan);
+        let mut decoder = SourceBlockDecoder::new2(0, &config, data.len() as u64);
+        assert_eq!(decoder.decode(packets[3..].to_vec()), Some(data));
+    }
+
+    #[test]
+    fn first_repair_esi() {
+        for &symbols in [1, 10, 25, 79, 1000].iter() {
+            let data = gen_test_data(SYMBOL_SIZE * symbols);
+            let config = ObjectTransmissionInformation::new(0, SYMBOL_SIZE as u16, 0, 1, 1);
+            let encoder = SourceBlockEncoder::new2(0, &config, &data);
+            assert_eq!(
+                encoder.first_repair_esi(),
+                extended_source_block_symbols(symbols as u32)
+            );
+            assert_eq!(encoder.first_repair_esi(), encoder.repair_esi_range().start);
+        }
+    }
+
+    #[test]
+    fn repair_esi_range() {
+        let (_, _, encoder) = test_block_encoder();
+        let range = encoder.repair_esi_range();
+        assert!(!range.contains(&25));
+        assert!(range.contains(&26));
+        assert!(range.contains(&16777215));
+        assert!(!range.contains(&16777216));
+        assert_eq!(
+            encoder.repair_packets(0, 1)[0]
+                .payload_id()
+                .encoding_symbol_id(),
+            range.start
+        );
+    }
+
     #[test]
     fn unique_blocks() {
         let data = gen_test_data(120);
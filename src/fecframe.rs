@@ -0,0 +1,130 @@
+use crate::base::EncodingPacket;
+use crate::base::ParseError;
+use crate::base::PayloadId;
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+const REPAIR_PAYLOAD_ID_BYTES: usize = 6;
+
+/// The payload of an RTP repair packet, for carrying RaptorQ repair symbols of a FECFRAME
+/// protected packet flow, as defined in [RFC 6682](https://tools.ietf.org/html/rfc6682).
+///
+/// Serialized as the Repair FEC Payload ID of the RaptorQ FEC Scheme
+/// ([RFC 6681, section 8.1.3](https://tools.ietf.org/html/rfc6681#section-8.1.3)), followed by the
+/// repair symbol:
+///
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |     SBN       |               Encoding Symbol ID              |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |     Source Block Length       |      Repair symbol ...        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// The Source Block Length is the number of source symbols in the source block, which receivers
+/// need, since FECFRAME source blocks are not described by an OTI. The RTP header itself is
+/// written by the RTP stack, and is not included.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct RtpRepairPayload {
+    packet: EncodingPacket,
+    source_block_length: u16,
+}
+
+impl RtpRepairPayload {
+    pub fn new(packet: EncodingPacket, source_block_length: u16) -> RtpRepairPayload {
+        RtpRepairPayload {
+            packet,
+            source_block_length,
+        }
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<RtpRepairPayload, ParseError> {
+        if data.len() < REPAIR_PAYLOAD_ID_BYTES {
+            return Err(ParseError::InvalidLength {
+                expected: REPAIR_PAYLOAD_ID_BYTES,
+                actual: data.len(),
+            });
+        }
+        let payload_id = PayloadId::deserialize(&[data[0], data[1], data[2], data[3]]);
+        let source_block_length = ((data[4] as u16) << 8) + data[5] as u16;
+        Ok(RtpRepairPayload {
+            packet: EncodingPacket::new(payload_id, data[REPAIR_PAYLOAD_ID_BYTES..].to_vec()),
+            source_block_length,
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut serialized = Vec::with_capacity(REPAIR_PAYLOAD_ID_BYTES + self.packet.data().len());
+        serialized.extend_from_slice(&self.packet.payload_id().serialize());
+        serialized.push((self.source_block_length >> 8) as u8);
+        serialized.push((self.source_block_length & 0xFF) as u8);
+        serialized.extend_from_slice(self.packet.data());
+        serialized
+    }
+
+    pub fn packet(&self) -> &EncodingPacket {
+        &self.packet
+    }
+
+    /// Number of source symbols in the source block that the repair symbol protects
+    pub fn source_block_length(&self) -> u16 {
+        self.source_block_length
+    }
+
+    pub fn into_packet(self) -> EncodingPacket {
+        self.packet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base::ParseError;
+    use crate::{EncodingPacket, PayloadId, RtpRepairPayload, SourceBlockEncoder};
+    use crate::{ObjectTransmissionInformation, SourceBlockDecoder};
+
+    #[test]
+    fn layout() {
+        let packet = EncodingPacket::new(PayloadId::new(3, 0x01_0203), vec![0xAA, 0xBB]);
+        let payload = RtpRepairPayload::new(packet, 0x0405);
+        let serialized = payload.serialize();
+        assert_eq!(serialized, vec![3, 1, 2, 3, 4, 5, 0xAA, 0xBB]);
+        assert_eq!(RtpRepairPayload::deserialize(&serialized), Ok(payload));
+        assert_eq!(
+            RtpRepairPayload::deserialize(&serialized[..5]),
+            Err(ParseError::InvalidLength {
+                expected: 6,
+                actual: 5
+            })
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = vec![7; 1280];
+        let config = ObjectTransmissionInformation::new(0, 64, 0, 1, 1);
+        let encoder = SourceBlockEncoder::new2(1, &config, &data);
+        // K = 20, so the receiver needs at least K repair symbols if every source packet is lost
+        let payloads: Vec<Vec<u8>> = encoder
+            .repair_packets(0, 25)
+            .drain(..)
+            .map(|packet| RtpRepairPayload::new(packet, 20).serialize())
+            .collect();
+
+        let mut decoder = None;
+        let mut result = None;
+        for payload in payloads {
+            let payload = RtpRepairPayload::deserialize(&payload).unwrap();
+            let block_length = payload.source_block_length() as u64 * 64;
+            let decoder =
+                decoder.get_or_insert_with(|| SourceBlockDecoder::new2(1, &config, block_length));
+            result = decoder.decode(vec![payload.into_packet()]);
+            if result.is_some() {
+                break;
+            }
+        }
+        assert_eq!(result, Some(data));
+    }
+}
@@ -0,0 +1,59 @@
+//! Read-only access to the GF(256) arithmetic tables used by the encoder and decoder, enabled by
+//! the `gf256` feature, so that external tools can check against or reuse the same arithmetic.
+//!
+//! The field is the one defined in
+//! [section 5.7.1](https://tools.ietf.org/html/rfc6330#section-5.7.1), generated by the
+//! irreducible polynomial x^8 + x^4 + x^3 + x^2 + 1 (0x11D), with addition being XOR.
+
+use crate::octet::{OCTET_MUL, OCT_EXP, OCT_LOG};
+
+/// The multiplication table: `mul_table()[a][b]` is the product of octets a and b.
+pub fn mul_table() -> &'static [[u8; 256]; 256] {
+    &OCTET_MUL
+}
+
+/// The OCT_EXP table of section 5.7.3: `exp_table()[i]` is alpha^i, where alpha = 2. The table
+/// has 510 entries, so that the sum of two logarithms can index it directly.
+pub fn exp_table() -> &'static [u8; 510] {
+    &OCT_EXP
+}
+
+/// The OCT_LOG table of section 5.7.4, with a zero prepended so that it is indexed directly by
+/// the octet: `log_table()[a]` is the logarithm of a, for a from 1 to 255. `log_table()[0]` is
+/// zero, but 0 has no logarithm.
+pub fn log_table() -> &'static [u8; 256] {
+    &OCT_LOG
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gf256::{exp_table, log_table, mul_table};
+
+    #[test]
+    fn tables_agree() {
+        let mul = mul_table();
+        let exp = exp_table();
+        let log = log_table();
+        for a in 1..256 {
+            assert_eq!(exp[log[a] as usize] as usize, a);
+            for b in 1..256 {
+                assert_eq!(
+                    mul[a][b],
+                    exp[log[a] as usize + log[b] as usize],
+                    "{} * {}",
+                    a,
+                    b
+                );
+            }
+            assert_eq!(mul[a][0], 0);
+            assert_eq!(mul[0][a], 0);
+            assert_eq!(mul[a][1] as usize, a);
+        }
+    }
+
+    #[test]
+    fn field_polynomial() {
+        // x^7 * x = x^8, which reduces to x^4 + x^3 + x^2 + 1
+        assert_eq!(mul_table()[0x80][2], 0x1D);
+    }
+}
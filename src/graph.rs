@@ -66,8 +66,7 @@ impl ConnectedComponentGraph {
         if connected_component == NO_CONNECTED_COMPONENT as usize {
             return;
         }
-        self.connected_component_size
-            .decrement(connected_component as usize);
+        self.connected_component_size.decrement(connected_component);
         self.node_connected_component
             .insert(node, NO_CONNECTED_COMPONENT);
     }
@@ -80,7 +79,7 @@ impl ConnectedComponentGraph {
         let mut max_size = 0;
         let mut largest_connected_component = NO_CONNECTED_COMPONENT as usize;
         for i in 1..=self.num_connected_components {
-            let size = self.connected_component_size.get(i as usize);
+            let size = self.connected_component_size.get(i);
             if size > max_size {
                 max_size = size;
                 largest_connected_component = i;
@@ -2,49 +2,108 @@
 #![cfg_attr(feature = "use_neon", feature(stdsimd))]
 #![cfg_attr(feature = "use_neon", feature(aarch64_target_feature))]
 #![cfg_attr(feature = "use_neon", feature(arm_target_feature))]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 
 mod arraymap;
 mod base;
+mod checksum;
 mod constraint_matrix;
+#[cfg(feature = "debug-tools")]
+pub mod debug;
 mod decoder;
 mod encoder;
+mod fecframe;
 mod gf2;
+#[cfg(feature = "gf256")]
+pub mod gf256;
 mod graph;
 mod iterators;
+mod manifest;
 mod matrix;
+mod negotiate;
 mod octet;
 mod octet_matrix;
 mod octets;
 mod operation_vector;
+mod oti_batch;
 mod pi_solver;
 #[cfg(feature = "python")]
 mod python;
 mod rng;
+mod sliding_window;
 mod sparse_matrix;
 mod sparse_vec;
 mod symbol;
+mod symbol_store;
 mod systematic_constants;
 mod util;
+mod xor_parity;
 
+pub use crate::base::pack_packets;
 pub use crate::base::partition;
+pub use crate::base::unpack_packets;
+pub use crate::base::validate_esi_space;
 pub use crate::base::EncodingPacket;
 pub use crate::base::ObjectTransmissionInformation;
+pub use crate::base::OtiField;
+pub use crate::base::ParameterError;
+pub use crate::base::ParseError;
 pub use crate::base::PayloadId;
+pub use crate::base::SubBlockInfo;
+pub use crate::base::MAX_ESI;
+pub use crate::base::MAX_SOURCE_BLOCKS;
+pub use crate::base::MAX_TRANSFER_LENGTH;
+pub use crate::checksum::Crc32;
+pub use crate::checksum::ObjectHasher;
+pub use crate::checksum::TransferHasher;
+pub use crate::decoder::estimate_decode_cost;
+pub use crate::decoder::BlockState;
+pub use crate::decoder::DecodeError;
+pub use crate::decoder::DecodeResult;
+pub use crate::decoder::DecodeSink;
+pub use crate::decoder::DecodeStrategy;
 #[cfg(not(feature = "python"))]
 pub use crate::decoder::Decoder;
+pub use crate::decoder::OverheadStats;
 pub use crate::decoder::SourceBlockDecoder;
+pub use crate::decoder::ValidationError;
+pub use crate::decoder::VecSink;
 pub use crate::encoder::calculate_block_offsets;
+pub use crate::encoder::code_rate;
+pub use crate::encoder::recommend_symbol_size;
+pub use crate::encoder::repair_symbols_for_code_rate;
 #[cfg(not(feature = "python"))]
 pub use crate::encoder::Encoder;
 pub use crate::encoder::EncoderBuilder;
 pub use crate::encoder::SourceBlockEncoder;
 pub use crate::encoder::SourceBlockEncodingPlan;
+pub use crate::encoder::SymbolBuf;
+pub use crate::fecframe::RtpRepairPayload;
+pub use crate::manifest::TransferManifest;
+#[cfg(feature = "allocator_api")]
+pub use crate::matrix::with_matrix_allocator;
+pub use crate::negotiate::TransferAccept;
+pub use crate::negotiate::TransferOffer;
+pub use crate::oti_batch::OtiBatch;
 #[cfg(feature = "python")]
 pub use crate::python::raptorq;
 #[cfg(feature = "python")]
 pub use crate::python::Decoder;
 #[cfg(feature = "python")]
 pub use crate::python::Encoder;
+pub use crate::sliding_window::SlidingWindowDecoder;
+pub use crate::sliding_window::SlidingWindowEncoder;
+pub use crate::sliding_window::SlidingWindowPacket;
+pub use crate::symbol_store::MemorySymbolStore;
+pub use crate::symbol_store::SymbolStore;
+pub use crate::systematic_constants::calculate_p1;
+pub use crate::systematic_constants::kprime_for;
+pub use crate::systematic_constants::num_hdpc_symbols;
+pub use crate::systematic_constants::num_intermediate_symbols;
+pub use crate::systematic_constants::num_ldpc_symbols;
+pub use crate::systematic_constants::num_lt_symbols;
+pub use crate::systematic_constants::systematic_index;
+pub use crate::xor_parity::XorParityPacket;
 
 #[cfg(feature = "benchmarking")]
 pub use crate::constraint_matrix::generate_constraint_matrix;
@@ -0,0 +1,401 @@
+use crate::base::ObjectTransmissionInformation;
+use crate::base::ParseError;
+use crate::base::MAX_ESI;
+use crate::checksum::TransferHasher;
+use crate::rng::shuffle;
+use crate::systematic_constants::extended_source_block_symbols;
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+const MANIFEST_VERSION: u8 = 1;
+// Version 2 appends a flags byte and the optional fields it marks as present. Manifests without
+// any optional fields are still written as version 1.
+const MANIFEST_VERSION_WITH_FLAGS: u8 = 2;
+const TRANSFER_HASH_FLAG: u8 = 0x01;
+const REPAIR_SEED_FLAG: u8 = 0x02;
+pub(crate) const OTI_BYTES: usize = 12;
+pub(crate) const REPAIR_COUNT_BYTES: usize = 3;
+const REPAIR_SEED_BYTES: usize = 8;
+
+// Repair symbols share the 24-bit ESI space with the source symbols
+pub(crate) fn assert_valid_repair_count(count: u32) {
+    assert!(count < MAX_ESI);
+}
+
+// Repair symbol counts are serialized as 24-bit big-endian integers
+pub(crate) fn serialize_repair_count(count: u32) -> [u8; REPAIR_COUNT_BYTES] {
+    [
+        (count >> 16) as u8,
+        ((count >> 8) & 0xFF) as u8,
+        (count & 0xFF) as u8,
+    ]
+}
+
+pub(crate) fn deserialize_repair_count(data: &[u8]) -> u32 {
+    ((data[0] as u32) << 16) + ((data[1] as u32) << 8) + data[2] as u32
+}
+
+/// Describes a transfer for store-and-forward systems: the object's transmission information,
+/// plus how many repair symbols will be sent for each source block. Optionally, it also carries a
+/// hash of the object for verifying it end-to-end, and the seed that the repair packets were
+/// shuffled with.
+///
+/// Serialized as a version byte, the 12 byte OTI, then the repair symbol count of each source block
+/// as a 24-bit big-endian integer. If there are optional fields, the version is 2 and the counts are
+/// followed by a flags byte. If bit 0 of the flags is set, the length of the transfer hash in bytes
+/// follows, then the hash itself. If bit 1 is set, the repair seed follows as a 64-bit big-endian
+/// integer.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct TransferManifest {
+    config: ObjectTransmissionInformation,
+    repair_symbols: Vec<u32>,
+    transfer_hash: Option<Vec<u8>>,
+    repair_seed: Option<u64>,
+}
+
+impl TransferManifest {
+    /// `repair_symbols` must contain exactly one entry per source block.
+    pub fn new(
+        config: ObjectTransmissionInformation,
+        repair_symbols: Vec<u32>,
+    ) -> TransferManifest {
+        assert_eq!(repair_symbols.len(), config.source_blocks() as usize);
+        for &count in repair_symbols.iter() {
+            assert_valid_repair_count(count);
+        }
+        TransferManifest {
+            config,
+            repair_symbols,
+            transfer_hash: None,
+            repair_seed: None,
+        }
+    }
+
+    /// Creates a manifest sending the same number of repair symbols for every source block
+    pub fn with_uniform_repair(
+        config: ObjectTransmissionInformation,
+        repair_symbols_per_block: u32,
+    ) -> TransferManifest {
+        TransferManifest::new(
+            config,
+            vec![repair_symbols_per_block; config.source_blocks() as usize],
+        )
+    }
+
+    pub fn config(&self) -> ObjectTransmissionInformation {
+        self.config
+    }
+
+    /// Number of repair symbols that will be sent for the given source block
+    pub fn repair_symbols(&self, source_block_number: u8) -> u32 {
+        self.repair_symbols[source_block_number as usize]
+    }
+
+    /// Repair symbol counts for every source block, indexed by source block number
+    pub fn repair_schedule(&self) -> &[u32] {
+        &self.repair_symbols
+    }
+
+    /// Hashes `data`, the object being transferred, with `hasher` and stores the digest in the
+    /// manifest. Receivers check it with
+    /// [`Decoder::get_verified_result`](crate::Decoder::get_verified_result).
+    pub fn set_transfer_hash<H: TransferHasher>(&mut self, data: &[u8], mut hasher: H) {
+        assert_eq!(data.len() as u64, self.config.transfer_length());
+        hasher.update(data);
+        let digest = H::digest_bytes(hasher.finish());
+        assert!(digest.len() <= u8::MAX as usize);
+        self.transfer_hash = Some(digest);
+    }
+
+    /// The digest stored by [`set_transfer_hash`](Self::set_transfer_hash), if any
+    pub fn transfer_hash(&self) -> Option<&[u8]> {
+        self.transfer_hash.as_deref()
+    }
+
+    /// Records the seed passed to [`Encoder::set_repair_seed`](crate::Encoder::set_repair_seed),
+    /// so that receivers can predict the order of the repair packets.
+    pub fn set_repair_seed(&mut self, seed: u64) {
+        self.repair_seed = Some(seed);
+    }
+
+    pub fn repair_seed(&self) -> Option<u64> {
+        self.repair_seed
+    }
+
+    /// The ESIs of the given source block's repair packets, in the order that an
+    /// [`Encoder`](crate::Encoder) with the manifest's repair seed sends them, or `None` if the
+    /// manifest has no repair seed or no source block `source_block_number`.
+    pub fn repair_esi_order(&self, source_block_number: u8) -> Option<Vec<u32>> {
+        let seed = self.repair_seed?;
+        let repair_symbols = *self.repair_symbols.get(source_block_number as usize)?;
+        let first_esi =
+            extended_source_block_symbols(self.config.symbols_in_block(source_block_number));
+        let mut esis: Vec<u32> = (first_esi..(first_esi + repair_symbols)).collect();
+        shuffle(&mut esis, seed.wrapping_add(source_block_number as u64));
+        Some(esis)
+    }
+
+    fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.transfer_hash.is_some() {
+            flags |= TRANSFER_HASH_FLAG;
+        }
+        if self.repair_seed.is_some() {
+            flags |= REPAIR_SEED_FLAG;
+        }
+        flags
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let flags = self.flags();
+        let mut optional_bytes = 0;
+        if flags != 0 {
+            optional_bytes += 1;
+        }
+        if let Some(ref hash) = self.transfer_hash {
+            optional_bytes += 1 + hash.len();
+        }
+        if self.repair_seed.is_some() {
+            optional_bytes += REPAIR_SEED_BYTES;
+        }
+        let mut serialized = Vec::with_capacity(
+            1 + OTI_BYTES + REPAIR_COUNT_BYTES * self.repair_symbols.len() + optional_bytes,
+        );
+        if flags != 0 {
+            serialized.push(MANIFEST_VERSION_WITH_FLAGS);
+        } else {
+            serialized.push(MANIFEST_VERSION);
+        }
+        serialized.extend_from_slice(&self.config.serialize());
+        for &count in self.repair_symbols.iter() {
+            serialized.extend_from_slice(&serialize_repair_count(count));
+        }
+        if flags != 0 {
+            serialized.push(flags);
+        }
+        if let Some(ref hash) = self.transfer_hash {
+            serialized.push(hash.len() as u8);
+            serialized.extend_from_slice(hash);
+        }
+        if let Some(seed) = self.repair_seed {
+            serialized.extend_from_slice(&seed.to_be_bytes());
+        }
+        serialized
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<TransferManifest, ParseError> {
+        if data.len() < 1 + OTI_BYTES {
+            return Err(ParseError::InvalidLength {
+                expected: 1 + OTI_BYTES,
+                actual: data.len(),
+            });
+        }
+        if data[0] != MANIFEST_VERSION && data[0] != MANIFEST_VERSION_WITH_FLAGS {
+            return Err(ParseError::UnsupportedVersion(data[0]));
+        }
+        let config = ObjectTransmissionInformation::try_deserialize(&data[1..(1 + OTI_BYTES)])?;
+
+        let counts_end = 1 + OTI_BYTES + REPAIR_COUNT_BYTES * config.source_blocks() as usize;
+        let mut flags = 0;
+        let mut expected = counts_end;
+        if data[0] == MANIFEST_VERSION_WITH_FLAGS {
+            expected += 1;
+            if data.len() < expected {
+                return Err(ParseError::InvalidLength {
+                    expected,
+                    actual: data.len(),
+                });
+            }
+            flags = data[counts_end];
+            if flags & TRANSFER_HASH_FLAG != 0 {
+                expected += 1;
+                if data.len() < expected {
+                    return Err(ParseError::InvalidLength {
+                        expected,
+                        actual: data.len(),
+                    });
+                }
+                expected += data[expected - 1] as usize;
+            }
+            if flags & REPAIR_SEED_FLAG != 0 {
+                expected += REPAIR_SEED_BYTES;
+            }
+        }
+        if data.len() != expected {
+            return Err(ParseError::InvalidLength {
+                expected,
+                actual: data.len(),
+            });
+        }
+        let repair_symbols = data[(1 + OTI_BYTES)..counts_end]
+            .chunks(REPAIR_COUNT_BYTES)
+            .map(deserialize_repair_count)
+            .collect();
+
+        let mut offset = counts_end + 1;
+        let mut transfer_hash = None;
+        if flags & TRANSFER_HASH_FLAG != 0 {
+            let hash_length = data[offset] as usize;
+            transfer_hash = Some(data[(offset + 1)..(offset + 1 + hash_length)].to_vec());
+            offset += 1 + hash_length;
+        }
+        let mut repair_seed = None;
+        if flags & REPAIR_SEED_FLAG != 0 {
+            let mut seed = [0; REPAIR_SEED_BYTES];
+            seed.copy_from_slice(&data[offset..(offset + REPAIR_SEED_BYTES)]);
+            repair_seed = Some(u64::from_be_bytes(seed));
+        }
+
+        Ok(TransferManifest {
+            config,
+            repair_symbols,
+            transfer_hash,
+            repair_seed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base::ParseError;
+    use crate::{Crc32, ObjectTransmissionInformation, TransferManifest};
+
+    #[test]
+    fn manifest_serialization() {
+        let config = ObjectTransmissionInformation::new(1_000_000, 1000, 3, 1, 8);
+        let manifest = TransferManifest::new(config, vec![0, 15, 16777215]);
+        let serialized = manifest.serialize();
+        assert_eq!(serialized.len(), 1 + 12 + 3 * 3);
+        assert_eq!(
+            TransferManifest::deserialize(&serialized).unwrap(),
+            manifest
+        );
+
+        let uniform = TransferManifest::with_uniform_repair(config, 7);
+        assert_eq!(uniform.repair_schedule(), &[7, 7, 7]);
+        assert_eq!(
+            TransferManifest::deserialize(&uniform.serialize()).unwrap(),
+            uniform
+        );
+    }
+
+    #[test]
+    fn manifest_invalid_oti() {
+        let config = ObjectTransmissionInformation::new(1_000_000, 1000, 3, 1, 8);
+        let mut serialized = TransferManifest::with_uniform_repair(config, 7).serialize();
+        // Symbol size of zero
+        serialized[7] = 0;
+        serialized[8] = 0;
+        assert_eq!(
+            TransferManifest::deserialize(&serialized),
+            Err(ParseError::InvalidSymbolSize(0))
+        );
+        serialized[12] = 0;
+        assert_eq!(
+            TransferManifest::deserialize(&serialized),
+            Err(ParseError::ZeroAlignment)
+        );
+    }
+
+    #[test]
+    fn manifest_transfer_hash() {
+        let config = ObjectTransmissionInformation::new(9, 1000, 3, 1, 8);
+        let mut manifest = TransferManifest::with_uniform_repair(config, 7);
+        assert_eq!(manifest.transfer_hash(), None);
+        assert_eq!(manifest.serialize()[0], 1);
+
+        manifest.set_transfer_hash(b"123456789", Crc32::new());
+        assert_eq!(
+            manifest.transfer_hash(),
+            Some(&[0xCB, 0xF4, 0x39, 0x26][..])
+        );
+        let serialized = manifest.serialize();
+        assert_eq!(serialized.len(), 1 + 12 + 3 * 3 + 1 + 1 + 4);
+        assert_eq!(serialized[0], 2);
+        assert_eq!(serialized[22], 0x01);
+        assert_eq!(
+            TransferManifest::deserialize(&serialized).unwrap(),
+            manifest
+        );
+        assert_eq!(
+            TransferManifest::deserialize(&serialized[..22]),
+            Err(ParseError::InvalidLength {
+                expected: 23,
+                actual: 22
+            })
+        );
+        assert_eq!(
+            TransferManifest::deserialize(&serialized[..23]),
+            Err(ParseError::InvalidLength {
+                expected: 24,
+                actual: 23
+            })
+        );
+        assert_eq!(
+            TransferManifest::deserialize(&serialized[..26]),
+            Err(ParseError::InvalidLength {
+                expected: 28,
+                actual: 26
+            })
+        );
+    }
+
+    #[test]
+    fn manifest_repair_seed() {
+        let config = ObjectTransmissionInformation::new(1600, 64, 1, 1, 8);
+        let mut manifest = TransferManifest::with_uniform_repair(config, 8);
+        assert_eq!(manifest.repair_esi_order(0), None);
+
+        manifest.set_repair_seed(42);
+        // K = 25, so the repair ESIs start from K' = 26
+        assert_eq!(
+            manifest.repair_esi_order(0),
+            Some(vec![29, 27, 32, 28, 30, 26, 33, 31])
+        );
+        assert_eq!(manifest.repair_esi_order(1), None);
+        let serialized = manifest.serialize();
+        assert_eq!(serialized.len(), 1 + 12 + 3 + 1 + 8);
+        assert_eq!(serialized[16], 0x02);
+        assert_eq!(
+            TransferManifest::deserialize(&serialized).unwrap(),
+            manifest
+        );
+
+        manifest.set_transfer_hash(&[0; 1600], Crc32::new());
+        let serialized = manifest.serialize();
+        assert_eq!(serialized.len(), 1 + 12 + 3 + 1 + 1 + 4 + 8);
+        assert_eq!(serialized[16], 0x03);
+        assert_eq!(
+            TransferManifest::deserialize(&serialized).unwrap(),
+            manifest
+        );
+    }
+
+    #[test]
+    fn manifest_malformed() {
+        let config = ObjectTransmissionInformation::new(1_000_000, 1000, 3, 1, 8);
+        let serialized = TransferManifest::with_uniform_repair(config, 7).serialize();
+
+        assert_eq!(
+            TransferManifest::deserialize(&serialized[..5]),
+            Err(ParseError::InvalidLength {
+                expected: 13,
+                actual: 5
+            })
+        );
+        assert_eq!(
+            TransferManifest::deserialize(&serialized[..(serialized.len() - 1)]),
+            Err(ParseError::InvalidLength {
+                expected: 22,
+                actual: 21
+            })
+        );
+        let mut wrong_version = serialized;
+        wrong_version[0] = 0xFF;
+        assert_eq!(
+            TransferManifest::deserialize(&wrong_version),
+            Err(ParseError::UnsupportedVersion(0xFF))
+        );
+    }
+}
@@ -3,8 +3,64 @@ use crate::iterators::OctetIter;
 use crate::octet::Octet;
 use crate::octets::BinaryOctetVec;
 use crate::util::get_both_ranges;
+#[cfg(feature = "allocator_api")]
+use std::alloc::{Allocator, Global};
+#[cfg(feature = "allocator_api")]
+use std::cell::Cell;
 use std::mem::size_of;
 
+// Storage for bit-packed matrix rows. With the allocator_api feature, this is allocated by the
+// allocator installed with with_matrix_allocator()
+#[cfg(not(feature = "allocator_api"))]
+pub type MatrixWords = Vec<u64>;
+#[cfg(feature = "allocator_api")]
+pub type MatrixWords = Vec<u64, &'static (dyn Allocator + Sync)>;
+
+#[cfg(feature = "allocator_api")]
+thread_local! {
+    static MATRIX_ALLOCATOR: Cell<&'static (dyn Allocator + Sync)> = Cell::new(&Global);
+}
+
+/// Runs `f`, allocating the binary constraint matrices built on this thread during the call, by
+/// decoding or encoding, from `allocator` rather than the global allocator.
+///
+/// The bit-packed rows of these matrices are the bulk of the working memory of a decode. Symbols,
+/// and the smaller bookkeeping structures of the solver, still use the global allocator.
+///
+/// Requires the `allocator_api` feature of this crate, which only builds on a nightly compiler,
+/// since it depends on the unstable
+/// [`allocator_api`](https://github.com/rust-lang/rust/issues/32838) language feature.
+#[cfg(feature = "allocator_api")]
+pub fn with_matrix_allocator<R, F: FnOnce() -> R>(
+    allocator: &'static (dyn Allocator + Sync),
+    f: F,
+) -> R {
+    // Restores the previous allocator when dropped, even if f panics
+    struct Restore(&'static (dyn Allocator + Sync));
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            MATRIX_ALLOCATOR.with(|current| current.set(self.0));
+        }
+    }
+
+    let _restore = Restore(MATRIX_ALLOCATOR.with(|current| current.replace(allocator)));
+    f()
+}
+
+// Allocates storage for the given number of words, all zero
+#[cfg(not(feature = "allocator_api"))]
+pub fn zeroed_words(words: usize) -> MatrixWords {
+    vec![0; words]
+}
+
+#[cfg(feature = "allocator_api")]
+pub fn zeroed_words(words: usize) -> MatrixWords {
+    let mut storage = Vec::with_capacity_in(words, MATRIX_ALLOCATOR.with(|current| current.get()));
+    storage.resize(words, 0);
+    storage
+}
+
 // TODO: change this struct to not use the Octet class, since it's binary not GF(256)
 pub trait BinaryMatrix: Clone {
     fn new(height: usize, width: usize, trailing_dense_column_hint: usize) -> Self;
@@ -15,12 +71,13 @@ pub trait BinaryMatrix: Clone {
 
     fn width(&self) -> usize;
 
+    #[allow(dead_code)]
     fn size_in_bytes(&self) -> usize;
 
     fn count_ones(&self, row: usize, start_col: usize, end_col: usize) -> usize;
 
     // Once "impl Trait" is supported in traits, it would be better to return "impl Iterator<...>"
-    fn get_row_iter(&self, row: usize, start_col: usize, end_col: usize) -> OctetIter;
+    fn get_row_iter(&self, row: usize, start_col: usize, end_col: usize) -> OctetIter<'_>;
 
     // An iterator over rows with a 1-valued entry for the given col
     fn get_ones_in_column(&self, col: usize, start_row: usize, end_row: usize) -> Vec<u32>;
@@ -60,7 +117,7 @@ pub struct DenseBinaryMatrix {
     height: usize,
     width: usize,
     // Values are bit-packed into u64
-    elements: Vec<u64>,
+    elements: MatrixWords,
 }
 
 impl DenseBinaryMatrix {
@@ -109,7 +166,7 @@ impl DenseBinaryMatrix {
 
 impl BinaryMatrix for DenseBinaryMatrix {
     fn new(height: usize, width: usize, _: usize) -> DenseBinaryMatrix {
-        let elements = vec![0; height * (width + WORD_WIDTH - 1) / WORD_WIDTH];
+        let elements = zeroed_words(height * (width + WORD_WIDTH - 1) / WORD_WIDTH);
         DenseBinaryMatrix {
             height,
             width,
@@ -136,7 +193,7 @@ impl BinaryMatrix for DenseBinaryMatrix {
 
     fn size_in_bytes(&self) -> usize {
         let mut bytes = size_of::<Self>();
-        bytes += size_of::<Vec<u64>>();
+        bytes += size_of::<MatrixWords>();
         bytes += size_of::<u64>() * self.elements.len();
 
         bytes
@@ -168,7 +225,7 @@ impl BinaryMatrix for DenseBinaryMatrix {
         return ones as usize;
     }
 
-    fn get_row_iter(&self, row: usize, start_col: usize, end_col: usize) -> OctetIter {
+    fn get_row_iter(&self, row: usize, start_col: usize, end_col: usize) -> OctetIter<'_> {
         let (first_word, first_bit) = self.bit_position(row, start_col);
         let (last_word, _) = self.bit_position(row, end_col);
         OctetIter::new_dense_binary(
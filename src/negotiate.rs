@@ -0,0 +1,284 @@
+use crate::base::ObjectTransmissionInformation;
+use crate::base::ParseError;
+use crate::checksum::TransferHasher;
+use crate::manifest::TransferManifest;
+use crate::manifest::{
+    assert_valid_repair_count, deserialize_repair_count, serialize_repair_count,
+};
+use crate::manifest::{OTI_BYTES, REPAIR_COUNT_BYTES};
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+const OFFER_MESSAGE_TYPE: u8 = 1;
+const ACCEPT_MESSAGE_TYPE: u8 = 2;
+
+fn parse_oti(data: &[u8]) -> Result<ObjectTransmissionInformation, ParseError> {
+    ObjectTransmissionInformation::try_deserialize(&data[1..(1 + OTI_BYTES)])
+}
+
+fn check_header(data: &[u8], message_type: u8, minimum_length: usize) -> Result<(), ParseError> {
+    if data.len() < minimum_length {
+        return Err(ParseError::InvalidLength {
+            expected: minimum_length,
+            actual: data.len(),
+        });
+    }
+    if data[0] != message_type {
+        return Err(ParseError::UnexpectedMessageType(data[0]));
+    }
+    Ok(())
+}
+
+/// The first message of a handshake on a control channel, with which the sender proposes the
+/// parameters of a transfer before sending any symbols: the object's transmission information,
+/// which includes the number of source blocks, and a hash of the object. The receiver answers with
+/// a [`TransferAccept`].
+///
+/// Serialized as a message type byte of 1, the 12 byte OTI, the length of the hash in bytes, then
+/// the hash itself.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct TransferOffer {
+    config: ObjectTransmissionInformation,
+    object_hash: Vec<u8>,
+}
+
+impl TransferOffer {
+    /// Creates an offer to send `data` with `config`, hashing it with `hasher`
+    pub fn new<H: TransferHasher>(
+        data: &[u8],
+        config: ObjectTransmissionInformation,
+        mut hasher: H,
+    ) -> TransferOffer {
+        assert_eq!(data.len() as u64, config.transfer_length());
+        hasher.update(data);
+        let object_hash = H::digest_bytes(hasher.finish());
+        assert!(object_hash.len() <= u8::MAX as usize);
+        TransferOffer {
+            config,
+            object_hash,
+        }
+    }
+
+    pub fn config(&self) -> ObjectTransmissionInformation {
+        self.config
+    }
+
+    pub fn source_blocks(&self) -> u8 {
+        self.config.source_blocks()
+    }
+
+    pub fn object_hash(&self) -> &[u8] {
+        &self.object_hash
+    }
+
+    /// Checks a received object against the offer's hash. `hasher` must be of the same kind that
+    /// the offer was created with.
+    pub fn verify<H: TransferHasher>(&self, data: &[u8], mut hasher: H) -> bool {
+        hasher.update(data);
+        data.len() as u64 == self.config.transfer_length()
+            && H::digest_bytes(hasher.finish()) == self.object_hash
+    }
+
+    /// Accepts the offered parameters as they are
+    pub fn accept(&self, repair_symbols_per_block: u32) -> TransferAccept {
+        TransferAccept::new(self.config, repair_symbols_per_block)
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut serialized = Vec::with_capacity(1 + OTI_BYTES + 1 + self.object_hash.len());
+        serialized.push(OFFER_MESSAGE_TYPE);
+        serialized.extend_from_slice(&self.config.serialize());
+        serialized.push(self.object_hash.len() as u8);
+        serialized.extend_from_slice(&self.object_hash);
+        serialized
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<TransferOffer, ParseError> {
+        check_header(data, OFFER_MESSAGE_TYPE, 1 + OTI_BYTES + 1)?;
+        let config = parse_oti(data)?;
+        let expected = 1 + OTI_BYTES + 1 + data[1 + OTI_BYTES] as usize;
+        if data.len() != expected {
+            return Err(ParseError::InvalidLength {
+                expected,
+                actual: data.len(),
+            });
+        }
+        Ok(TransferOffer {
+            config,
+            object_hash: data[(1 + OTI_BYTES + 1)..].to_vec(),
+        })
+    }
+}
+
+/// The receiver's answer to a [`TransferOffer`]: the parameters that it chose for the transfer,
+/// and how many repair symbols it wants for each source block. The receiver may choose a different
+/// OTI than the one offered, for example with a smaller symbol size to fit its MTU, but it must
+/// describe an object of the same length.
+///
+/// Serialized as a message type byte of 2, the 12 byte OTI, then the number of repair symbols per
+/// block as a 24-bit big-endian integer.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct TransferAccept {
+    config: ObjectTransmissionInformation,
+    repair_symbols_per_block: u32,
+}
+
+impl TransferAccept {
+    pub fn new(
+        config: ObjectTransmissionInformation,
+        repair_symbols_per_block: u32,
+    ) -> TransferAccept {
+        assert_valid_repair_count(repair_symbols_per_block);
+        TransferAccept {
+            config,
+            repair_symbols_per_block,
+        }
+    }
+
+    pub fn config(&self) -> ObjectTransmissionInformation {
+        self.config
+    }
+
+    pub fn repair_symbols_per_block(&self) -> u32 {
+        self.repair_symbols_per_block
+    }
+
+    /// Whether the chosen parameters describe the object of `offer`
+    pub fn is_compatible(&self, offer: &TransferOffer) -> bool {
+        self.config.transfer_length() == offer.config.transfer_length()
+    }
+
+    /// The manifest of the agreed transfer
+    pub fn manifest(&self) -> TransferManifest {
+        TransferManifest::with_uniform_repair(self.config, self.repair_symbols_per_block)
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut serialized = Vec::with_capacity(1 + OTI_BYTES + REPAIR_COUNT_BYTES);
+        serialized.push(ACCEPT_MESSAGE_TYPE);
+        serialized.extend_from_slice(&self.config.serialize());
+        serialized.extend_from_slice(&serialize_repair_count(self.repair_symbols_per_block));
+        serialized
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<TransferAccept, ParseError> {
+        let expected = 1 + OTI_BYTES + REPAIR_COUNT_BYTES;
+        check_header(data, ACCEPT_MESSAGE_TYPE, expected)?;
+        if data.len() != expected {
+            return Err(ParseError::InvalidLength {
+                expected,
+                actual: data.len(),
+            });
+        }
+        let config = parse_oti(data)?;
+        Ok(TransferAccept {
+            config,
+            repair_symbols_per_block: deserialize_repair_count(&data[(1 + OTI_BYTES)..]),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base::ParseError;
+    use crate::{Crc32, ObjectTransmissionInformation, TransferAccept, TransferOffer};
+
+    #[test]
+    fn offer_serialization() {
+        let config = ObjectTransmissionInformation::new(9, 8, 1, 1, 8);
+        let offer = TransferOffer::new(b"123456789", config, Crc32::new());
+        assert_eq!(offer.object_hash(), &[0xCB, 0xF4, 0x39, 0x26][..]);
+        assert_eq!(offer.source_blocks(), 1);
+
+        let serialized = offer.serialize();
+        assert_eq!(serialized.len(), 1 + 12 + 1 + 4);
+        assert_eq!(serialized[0], 1);
+        assert_eq!(&serialized[1..13], &config.serialize()[..]);
+        assert_eq!(serialized[13], 4);
+        assert_eq!(TransferOffer::deserialize(&serialized), Ok(offer.clone()));
+
+        assert_eq!(
+            TransferOffer::deserialize(&serialized[..13]),
+            Err(ParseError::InvalidLength {
+                expected: 14,
+                actual: 13
+            })
+        );
+        assert_eq!(
+            TransferOffer::deserialize(&serialized[..17]),
+            Err(ParseError::InvalidLength {
+                expected: 18,
+                actual: 17
+            })
+        );
+        let mut zero_alignment = serialized;
+        zero_alignment[12] = 0;
+        assert_eq!(
+            TransferOffer::deserialize(&zero_alignment),
+            Err(ParseError::ZeroAlignment)
+        );
+
+        assert!(offer.verify(b"123456789", Crc32::new()));
+        assert!(!offer.verify(b"123456780", Crc32::new()));
+        assert!(!offer.verify(b"12345678", Crc32::new()));
+    }
+
+    #[test]
+    fn accept_serialization() {
+        let config = ObjectTransmissionInformation::new(1_000_000, 1000, 3, 1, 8);
+        let accept = TransferAccept::new(config, 0x01_0203);
+        let serialized = accept.serialize();
+        assert_eq!(serialized.len(), 1 + 12 + 3);
+        assert_eq!(serialized[0], 2);
+        assert_eq!(&serialized[13..], &[1, 2, 3]);
+        assert_eq!(TransferAccept::deserialize(&serialized), Ok(accept.clone()));
+
+        assert_eq!(
+            TransferAccept::deserialize(&serialized[..15]),
+            Err(ParseError::InvalidLength {
+                expected: 16,
+                actual: 15
+            })
+        );
+        let mut extended = serialized.clone();
+        extended.push(0);
+        assert_eq!(
+            TransferAccept::deserialize(&extended),
+            Err(ParseError::InvalidLength {
+                expected: 16,
+                actual: 17
+            })
+        );
+        assert_eq!(accept.manifest().repair_schedule(), &[0x01_0203; 3]);
+    }
+
+    #[test]
+    fn handshake() {
+        let data: Vec<u8> = (0..10_000).map(|i| (i % 251) as u8).collect();
+        let offered = ObjectTransmissionInformation::with_defaults(data.len() as u64, 1400);
+        let offer = TransferOffer::new(&data, offered, Crc32::new());
+        let received_offer = TransferOffer::deserialize(&offer.serialize()).unwrap();
+
+        // The receiver asks for smaller packets
+        let chosen = ObjectTransmissionInformation::with_defaults(data.len() as u64, 500);
+        let accept = TransferAccept::new(chosen, 10);
+        assert!(accept.is_compatible(&received_offer));
+        let truncated = ObjectTransmissionInformation::with_defaults(9, 500);
+        assert!(!TransferAccept::new(truncated, 10).is_compatible(&received_offer));
+        let received_accept = TransferAccept::deserialize(&accept.serialize()).unwrap();
+        assert_eq!(received_accept.config(), chosen);
+        assert_eq!(received_offer.accept(4).config(), offered);
+
+        // The two kinds of message can't be mistaken for each other
+        assert_eq!(
+            TransferOffer::deserialize(&accept.serialize()),
+            Err(ParseError::UnexpectedMessageType(2))
+        );
+        assert_eq!(
+            TransferAccept::deserialize(&offer.serialize()[..16]),
+            Err(ParseError::UnexpectedMessageType(1))
+        );
+    }
+}
@@ -8,7 +8,7 @@ use std::ops::Sub;
 
 // As defined in section 5.7.3
 #[rustfmt::skip]
-const OCT_EXP: [u8; 510] = [
+pub(crate) const OCT_EXP: [u8; 510] = [
    1, 2, 4, 8, 16, 32, 64, 128, 29, 58, 116, 232, 205, 135, 19, 38, 76,
    152, 45, 90, 180, 117, 234, 201, 143, 3, 6, 12, 24, 48, 96, 192, 157,
    39, 78, 156, 37, 74, 148, 53, 106, 212, 181, 119, 238, 193, 159, 35,
@@ -47,7 +47,7 @@ const OCT_EXP: [u8; 510] = [
 
 // As defined in section 5.7.4, but with a prepended zero to make this zero indexed
 #[rustfmt::skip]
-const OCT_LOG: [u8; 256] = [
+pub(crate) const OCT_LOG: [u8; 256] = [
    0, 0, 1, 25, 2, 50, 26, 198, 3, 223, 51, 238, 27, 104, 199, 75, 4, 100,
    224, 14, 52, 141, 239, 129, 28, 193, 105, 248, 200, 8, 76, 113, 5,
    138, 101, 47, 225, 36, 15, 33, 53, 147, 142, 218, 240, 18, 130, 69,
@@ -67,7 +67,7 @@ const OCT_LOG: [u8; 256] = [
    79, 174, 213, 233, 230, 231, 173, 232, 116, 214, 244, 234, 168, 80,
    88, 175];
 
-pub const OCTET_MUL: [[u8; 256]; 256] = calculate_octet_mul_table();
+pub static OCTET_MUL: [[u8; 256]; 256] = calculate_octet_mul_table();
 
 // See "Screaming Fast Galois Field Arithmetic Using Intel SIMD Instructions" by Plank et al.
 // Further adapted to AVX2
@@ -175,7 +175,7 @@ impl Add for Octet {
     }
 }
 
-impl<'a, 'b> Add<&'b Octet> for &'a Octet {
+impl<'b> Add<&'b Octet> for &Octet {
     type Output = Octet;
 
     #[allow(clippy::suspicious_arithmetic_impl)]
@@ -221,7 +221,7 @@ impl Mul for Octet {
     }
 }
 
-impl<'a, 'b> Mul<&'b Octet> for &'a Octet {
+impl<'b> Mul<&'b Octet> for &Octet {
     type Output = Octet;
 
     #[allow(clippy::suspicious_arithmetic_impl)]
@@ -251,7 +251,7 @@ impl Div for Octet {
     }
 }
 
-impl<'a, 'b> Div<&'b Octet> for &'a Octet {
+impl<'b> Div<&'b Octet> for &Octet {
     type Output = Octet;
 
     #[allow(clippy::suspicious_arithmetic_impl)]
@@ -882,8 +882,8 @@ mod tests {
         let size = 41;
         let scalar = Octet::new(rand::thread_rng().gen_range(2..255));
         let mut binary_vec: Vec<u64> = vec![0; (size + 63) / 64];
-        for i in 0..binary_vec.len() {
-            binary_vec[i] = rand::thread_rng().gen();
+        for element in &mut binary_vec {
+            *element = rand::thread_rng().gen();
         }
         let binary_octet_vec = BinaryOctetVec::new(binary_vec, size);
         let mut data1: Vec<u8> = vec![0; size];
@@ -15,6 +15,7 @@ pub enum SymbolOps {
         dest: usize,
         scalar: Octet,
     },
+    #[allow(clippy::upper_case_acronyms)]
     FMA {
         dest: usize,
         src: usize,
@@ -68,8 +69,8 @@ mod tests {
 
         for _i in 0..rows {
             let mut symbol_data: Vec<u8> = vec![0; symbol_size];
-            for j in 0..symbol_size {
-                symbol_data[j] = rand::thread_rng().gen();
+            for element in &mut symbol_data {
+                *element = rand::thread_rng().gen();
             }
             let symbol = Symbol::new(symbol_data);
             data.push(symbol);
@@ -100,8 +101,8 @@ mod tests {
 
         for _i in 0..rows {
             let mut symbol_data: Vec<u8> = vec![0; symbol_size];
-            for j in 0..symbol_size {
-                symbol_data[j] = rand::thread_rng().gen();
+            for element in &mut symbol_data {
+                *element = rand::thread_rng().gen();
             }
             let symbol = Symbol::new(symbol_data);
             data.push(symbol);
@@ -136,8 +137,8 @@ mod tests {
 
         for _i in 0..rows {
             let mut symbol_data: Vec<u8> = vec![0; symbol_size];
-            for j in 0..symbol_size {
-                symbol_data[j] = rand::thread_rng().gen();
+            for element in &mut symbol_data {
+                *element = rand::thread_rng().gen();
             }
             let symbol = Symbol::new(symbol_data);
             data.push(symbol);
@@ -168,10 +169,7 @@ mod tests {
         let mut data: Vec<Symbol> = Vec::with_capacity(rows);
 
         for i in 0..rows {
-            let mut symbol_data: Vec<u8> = vec![0; symbol_size];
-            for j in 0..symbol_size {
-                symbol_data[j] = i as u8;
-            }
+            let symbol_data: Vec<u8> = vec![i as u8; symbol_size];
             let symbol = Symbol::new(symbol_data);
             data.push(symbol);
         }
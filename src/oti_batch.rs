@@ -0,0 +1,219 @@
+use crate::base::ObjectTransmissionInformation;
+use crate::base::ParseError;
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+use std::iter::FromIterator;
+
+const COUNT_BYTES: usize = 4;
+// Transfer length (40 bits), symbol size, number of source blocks, number of sub-blocks and
+// alignment. The reserved byte of the 12 byte OTI is not stored.
+const OTI_COLUMN_BYTES: usize = 5 + 2 + 1 + 2 + 1;
+
+/// A batch of OTIs stored column by column, e.g. for telemetry pipelines which log the OTI of many
+/// transfers. Keeping each field together is more cache friendly to scan, and compresses better,
+/// than concatenating the 12 byte serialized OTIs.
+///
+/// Serialized as the number of OTIs as a 32-bit big-endian integer, followed by the transfer
+/// lengths as 40-bit integers, the symbol sizes as 16-bit integers, the numbers of source blocks as
+/// 8-bit integers, the numbers of sub-blocks as 16-bit integers, and the symbol alignments as 8-bit
+/// integers, all big-endian. See section 3.3.2 and 3.3.3.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct OtiBatch {
+    transfer_lengths: Vec<u64>,
+    symbol_sizes: Vec<u16>,
+    source_blocks: Vec<u8>,
+    sub_blocks: Vec<u16>,
+    alignments: Vec<u8>,
+}
+
+impl OtiBatch {
+    pub fn new() -> OtiBatch {
+        OtiBatch::default()
+    }
+
+    pub fn push(&mut self, config: ObjectTransmissionInformation) {
+        self.transfer_lengths.push(config.transfer_length());
+        self.symbol_sizes.push(config.symbol_size());
+        self.source_blocks.push(config.source_blocks());
+        self.sub_blocks.push(config.sub_blocks());
+        self.alignments.push(config.symbol_alignment());
+    }
+
+    pub fn len(&self) -> usize {
+        self.transfer_lengths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transfer_lengths.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<ObjectTransmissionInformation> {
+        if index >= self.len() {
+            return None;
+        }
+        Some(ObjectTransmissionInformation::deserialize(
+            &self.oti_bytes(index),
+        ))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ObjectTransmissionInformation> + '_ {
+        (0..self.len()).map(move |i| ObjectTransmissionInformation::deserialize(&self.oti_bytes(i)))
+    }
+
+    // The serialized form of the OTI at the given index
+    fn oti_bytes(&self, index: usize) -> [u8; 12] {
+        let transfer_length = self.transfer_lengths[index];
+        let symbol_size = self.symbol_sizes[index];
+        let sub_blocks = self.sub_blocks[index];
+        [
+            (transfer_length >> 32) as u8,
+            (transfer_length >> 24) as u8,
+            (transfer_length >> 16) as u8,
+            (transfer_length >> 8) as u8,
+            transfer_length as u8,
+            0, // Reserved
+            (symbol_size >> 8) as u8,
+            symbol_size as u8,
+            self.source_blocks[index],
+            (sub_blocks >> 8) as u8,
+            sub_blocks as u8,
+            self.alignments[index],
+        ]
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut serialized = Vec::with_capacity(COUNT_BYTES + OTI_COLUMN_BYTES * self.len());
+        serialized.extend_from_slice(&(self.len() as u32).to_be_bytes());
+        for &transfer_length in self.transfer_lengths.iter() {
+            serialized.extend_from_slice(&transfer_length.to_be_bytes()[3..]);
+        }
+        for &symbol_size in self.symbol_sizes.iter() {
+            serialized.extend_from_slice(&symbol_size.to_be_bytes());
+        }
+        serialized.extend_from_slice(&self.source_blocks);
+        for &sub_blocks in self.sub_blocks.iter() {
+            serialized.extend_from_slice(&sub_blocks.to_be_bytes());
+        }
+        serialized.extend_from_slice(&self.alignments);
+        serialized
+    }
+
+    /// Deserializes a batch, validating every OTI as
+    /// [`ObjectTransmissionInformation::try_deserialize`] does.
+    pub fn deserialize(data: &[u8]) -> Result<OtiBatch, ParseError> {
+        if data.len() < COUNT_BYTES {
+            return Err(ParseError::InvalidLength {
+                expected: COUNT_BYTES,
+                actual: data.len(),
+            });
+        }
+        let count = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let expected = COUNT_BYTES + OTI_COLUMN_BYTES * count;
+        if data.len() != expected {
+            return Err(ParseError::InvalidLength {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        let transfer_lengths_start = COUNT_BYTES;
+        let symbol_sizes_start = transfer_lengths_start + 5 * count;
+        let source_blocks_start = symbol_sizes_start + 2 * count;
+        let sub_blocks_start = source_blocks_start + count;
+        let alignments_start = sub_blocks_start + 2 * count;
+        let mut batch = OtiBatch::new();
+        for i in 0..count {
+            let length =
+                &data[(transfer_lengths_start + 5 * i)..(transfer_lengths_start + 5 * i + 5)];
+            let symbol_size = &data[(symbol_sizes_start + 2 * i)..(symbol_sizes_start + 2 * i + 2)];
+            let sub_blocks = &data[(sub_blocks_start + 2 * i)..(sub_blocks_start + 2 * i + 2)];
+            let oti = [
+                length[0],
+                length[1],
+                length[2],
+                length[3],
+                length[4],
+                0,
+                symbol_size[0],
+                symbol_size[1],
+                data[source_blocks_start + i],
+                sub_blocks[0],
+                sub_blocks[1],
+                data[alignments_start + i],
+            ];
+            batch.push(ObjectTransmissionInformation::try_deserialize(&oti)?);
+        }
+        Ok(batch)
+    }
+}
+
+impl FromIterator<ObjectTransmissionInformation> for OtiBatch {
+    fn from_iter<T: IntoIterator<Item = ObjectTransmissionInformation>>(iter: T) -> OtiBatch {
+        let mut batch = OtiBatch::new();
+        for config in iter {
+            batch.push(config);
+        }
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base::ParseError;
+    use crate::{ObjectTransmissionInformation, OtiBatch};
+
+    #[test]
+    fn batch_round_trip() {
+        let configs = vec![
+            ObjectTransmissionInformation::new(1_000_000, 1000, 3, 1, 8),
+            ObjectTransmissionInformation::new(942574504275, 65535, 255, 1, 1),
+            ObjectTransmissionInformation::new(10, 64, 1, 2, 8),
+        ];
+        let batch: OtiBatch = configs.iter().cloned().collect();
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch.get(1), Some(configs[1]));
+        assert_eq!(batch.get(3), None);
+        assert_eq!(batch.iter().collect::<Vec<_>>(), configs);
+
+        let serialized = batch.serialize();
+        assert_eq!(serialized.len(), 4 + 11 * 3);
+        // The transfer lengths are stored together, followed by the symbol sizes
+        assert_eq!(&serialized[..4], &[0, 0, 0, 3]);
+        assert_eq!(&serialized[4..9], &configs[0].serialize()[..5]);
+        assert_eq!(&serialized[19..21], &[0x03, 0xE8]);
+        assert_eq!(OtiBatch::deserialize(&serialized), Ok(batch));
+
+        let empty = OtiBatch::new();
+        assert!(empty.is_empty());
+        assert_eq!(OtiBatch::deserialize(&empty.serialize()), Ok(empty));
+    }
+
+    #[test]
+    fn batch_malformed() {
+        let batch: OtiBatch = vec![ObjectTransmissionInformation::new(1_000_000, 1000, 3, 1, 8)]
+            .into_iter()
+            .collect();
+        let serialized = batch.serialize();
+        assert_eq!(
+            OtiBatch::deserialize(&serialized[..3]),
+            Err(ParseError::InvalidLength {
+                expected: 4,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            OtiBatch::deserialize(&serialized[..14]),
+            Err(ParseError::InvalidLength {
+                expected: 15,
+                actual: 14
+            })
+        );
+        let mut zero_alignment = serialized;
+        zero_alignment[14] = 0;
+        assert_eq!(
+            OtiBatch::deserialize(&zero_alignment),
+            Err(ParseError::ZeroAlignment)
+        );
+    }
+}
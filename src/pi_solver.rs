@@ -293,8 +293,7 @@ impl FirstPhaseRowSelectionStats {
             // Pick arbitrary node (column) to start
             node_queue.clear();
             node_queue.push(key);
-            while !node_queue.is_empty() {
-                let node = node_queue.pop().unwrap();
+            while let Some(node) = node_queue.pop() {
                 if self.col_graph.contains(node as usize) {
                     continue;
                 }
@@ -320,7 +319,7 @@ impl FirstPhaseRowSelectionStats {
             .get_node_in_largest_connected_component(self.start_col, self.end_col);
 
         // Find a row with two ones in the given column
-        for row in matrix.get_ones_in_column(node as usize, start_row, end_row) {
+        for row in matrix.get_ones_in_column(node, start_row, end_row) {
             let row = row as usize;
             if self.ones_per_row.get(row) == 2 {
                 return row;
@@ -339,7 +338,7 @@ impl FirstPhaseRowSelectionStats {
         // There's no need for special handling of HDPC rows, since Errata 2 guarantees we won't
         // select any, and they're excluded in the first_phase solver
         let mut chosen = None;
-        let mut chosen_original_degree = std::u16::MAX;
+        let mut chosen_original_degree = u16::MAX;
         // Fast path for r=1, since this is super common
         if r == 1 {
             assert_ne!(0, self.rows_with_single_one.len());
@@ -392,7 +391,7 @@ impl FirstPhaseRowSelectionStats {
             }
         }
 
-        if r == None {
+        if r.is_none() {
             return (None, None);
         }
 
@@ -437,6 +436,11 @@ pub struct IntermediateSymbolDecoder<T: BinaryMatrix> {
     debug_symbol_add_ops: u32,
     debug_symbol_mul_ops_by_phase: Vec<u32>,
     debug_symbol_add_ops_by_phase: Vec<u32>,
+    operation_limit: Option<u64>,
+    operation_limit_exceeded: bool,
+    rank: Option<usize>,
+    // Indexed by original row. See prefer_row()
+    preferred_rows: Vec<bool>,
 }
 
 #[allow(non_snake_case)]
@@ -490,11 +494,18 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
             debug_symbol_add_ops: 0,
             debug_symbol_mul_ops_by_phase: vec![0; 5],
             debug_symbol_add_ops_by_phase: vec![0; 5],
+            operation_limit: None,
+            operation_limit_exceeded: false,
+            rank: None,
+            preferred_rows: vec![false; num_rows],
         };
 
         // Swap the HDPC rows, so that they're the last in the matrix
         let S = num_ldpc_symbols(num_source_symbols) as usize;
         let H = num_hdpc_symbols(num_source_symbols) as usize;
+        for row in 0..(S + H) {
+            temp.preferred_rows[row] = true;
+        }
         // See section 5.3.3.4.2, Figure 5.
         for i in 0..H {
             temp.swap_rows(S + i, num_rows - H + i);
@@ -507,6 +518,46 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
         temp
     }
 
+    // Aborts execute() once more than this many row operations have been recorded. The limit is
+    // checked before each row, or recorded first phase operation, is processed, so it can be
+    // exceeded by the operations on a single row, which is at most about L
+    pub fn set_operation_limit(&mut self, limit: u64) {
+        self.operation_limit = Some(limit);
+    }
+
+    // True if execute() was aborted due to the operation limit
+    pub fn operation_limit_exceeded(&self) -> bool {
+        self.operation_limit_exceeded
+    }
+
+    // The rank of the constraint matrix, if execute() failed in the second phase because the
+    // matrix is singular
+    pub fn rank(&self) -> Option<usize> {
+        self.rank
+    }
+
+    // The original indices of the rows which were not needed to solve the matrix, because they
+    // were linearly dependent on the L rows that were used. Only valid after execute() succeeds
+    pub fn redundant_rows(&self) -> &[usize] {
+        &self.d[self.L..]
+    }
+
+    // Marks a row, by its original index, to be picked as a pivot ahead of the other rows in the
+    // second phase where possible, so that redundant_rows() leaves it out if it can. The LDPC and
+    // HDPC rows are always preferred
+    pub fn prefer_row(&mut self, row: usize) {
+        self.preferred_rows[row] = true;
+    }
+
+    fn check_operation_limit(&mut self) -> bool {
+        if let Some(limit) = self.operation_limit {
+            if self.deferred_D_ops.len() as u64 > limit {
+                self.operation_limit_exceeded = true;
+            }
+        }
+        self.operation_limit_exceeded
+    }
+
     #[inline(never)]
     fn apply_deferred_symbol_ops(&mut self) {
         for op in self.deferred_D_ops.iter() {
@@ -520,7 +571,7 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
                 }
                 SymbolOps::FMA { dest, src, scalar } => {
                     let (dest, temp) = get_both_indices(&mut self.D, *dest, *src);
-                    dest.fused_addassign_mul_scalar(&temp, scalar);
+                    dest.fused_addassign_mul_scalar(temp, scalar);
                 }
                 SymbolOps::Reorder { order: _order } => {}
             }
@@ -670,6 +721,9 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
         let mut row_ops = vec![];
 
         while self.i + self.u < self.L {
+            if self.check_operation_limit() {
+                return None;
+            }
             // Calculate r
             // "Let r be the minimum integer such that at least one row of A has
             // exactly r nonzeros in V."
@@ -680,10 +734,7 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
                 &self.A,
             );
 
-            if r == None {
-                return None;
-            }
-            let r = r.unwrap();
+            let r = r?;
             let chosen_row = chosen_row.unwrap();
             assert!(chosen_row >= self.i);
 
@@ -836,7 +887,9 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
         let hdpc_rows = self.A_hdpc_rows.take().unwrap();
         if let Some(submatrix) = self.record_reduce_to_row_echelon(hdpc_rows, temp, temp, size) {
             // Perform backwards elimination
-            self.backwards_elimination(submatrix, temp, temp, size);
+            if !self.backwards_elimination(submatrix, temp, temp, size) {
+                return false;
+            }
         } else {
             return false;
         }
@@ -881,12 +934,16 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
     // Third phase (section 5.4.2.4)
     #[allow(non_snake_case)]
     #[inline(never)]
-    fn third_phase(&mut self, x_elimination_ops: &[RowOp]) {
+    // Returns false if aborted due to the operation limit
+    fn third_phase(&mut self, x_elimination_ops: &[RowOp]) -> bool {
         #[cfg(debug_assertions)]
         self.third_phase_verify();
 
         // Perform A[0..i][..] = X * A[0..i][..] by applying Errata 10
         for op in x_elimination_ops.iter().rev() {
+            if self.check_operation_limit() {
+                return false;
+            }
             match op {
                 RowOp::AddAssign { src, dest } => {
                     #[cfg(debug_assertions)]
@@ -903,6 +960,7 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
 
         #[cfg(debug_assertions)]
         self.third_phase_verify_end();
+        true
     }
 
     #[inline(never)]
@@ -937,8 +995,12 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
     // Fourth phase (section 5.4.2.5)
     #[allow(non_snake_case)]
     #[inline(never)]
-    fn fourth_phase(&mut self) {
+    // Returns false if aborted due to the operation limit
+    fn fourth_phase(&mut self) -> bool {
         for i in 0..self.i {
+            if self.check_operation_limit() {
+                return false;
+            }
             for j in self.A.query_non_zero_columns(i, self.i) {
                 #[cfg(debug_assertions)]
                 self.fma_rows(j, i, Octet::one(), 0);
@@ -952,6 +1014,7 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
 
         #[cfg(debug_assertions)]
         self.fourth_phase_verify();
+        true
     }
 
     #[inline(never)]
@@ -986,9 +1049,13 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
     // Fifth phase (section 5.4.2.6)
     #[allow(non_snake_case)]
     #[inline(never)]
-    fn fifth_phase(&mut self, x_elimination_ops: &[RowOp]) {
+    // Returns false if aborted due to the operation limit
+    fn fifth_phase(&mut self, x_elimination_ops: &[RowOp]) -> bool {
         // Use the saved operations from first phase: Errata 9
         for op in x_elimination_ops {
+            if self.check_operation_limit() {
+                return false;
+            }
             match op {
                 RowOp::AddAssign { src, dest } => {
                     #[cfg(debug_assertions)]
@@ -1005,6 +1072,7 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
 
         #[cfg(debug_assertions)]
         self.fifth_phase_verify();
+        true
     }
 
     #[inline(never)]
@@ -1059,19 +1127,26 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
         }
 
         for i in 0..size {
-            // Swap a row with leading coefficient i into place
-            for j in i..submatrix.height() {
-                if submatrix.get(j, i) != Octet::zero() {
-                    submatrix.swap_rows(i, j);
-                    // Record the swap, in addition to swapping in the working submatrix
-                    // TODO: optimize to not perform op on A
-                    self.swap_rows(row_offset + i, j + row_offset);
-                    break;
-                }
+            if self.check_operation_limit() {
+                return None;
+            }
+            // Swap a row with leading coefficient i into place, picking a preferred row if there
+            // is one
+            let pivot = (i..submatrix.height())
+                .filter(|&j| submatrix.get(j, i) != Octet::zero())
+                .min_by_key(|&j| !self.preferred_rows[self.d[row_offset + j]]);
+            if let Some(j) = pivot {
+                submatrix.swap_rows(i, j);
+                // Record the swap, in addition to swapping in the working submatrix
+                // TODO: optimize to not perform op on A
+                self.swap_rows(row_offset + i, j + row_offset);
             }
 
             if submatrix.get(i, i) == Octet::zero() {
-                // If all following rows are zero in this column, then matrix is singular
+                // If all following rows are zero in this column, then matrix is singular. The
+                // rows before row_offset, and the first i rows of the submatrix, each have a
+                // pivot, and the remaining rows are zero in the columns before i + 1
+                self.rank = Some(row_offset + i + dense_rank(&mut submatrix, i, i + 1, size));
                 return None;
             }
 
@@ -1102,6 +1177,7 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
     //
     // Applies the submatrix to the size-by-size lower right of A, and performs backwards
     // elimination on it. "submatrix" must be in row echelon form.
+    // Returns false if aborted due to the operation limit
     #[inline(never)]
     fn backwards_elimination(
         &mut self,
@@ -1109,9 +1185,12 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
         row_offset: usize,
         col_offset: usize,
         size: usize,
-    ) {
+    ) -> bool {
         // Perform backwards elimination
         for i in (0..size).rev() {
+            if self.check_operation_limit() {
+                return false;
+            }
             // Zero out all preceding elements in i'th column
             for j in 0..i {
                 if submatrix.get(j, i) != Octet::zero() {
@@ -1133,6 +1212,7 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
                 }
             }
         }
+        true
     }
 
     #[allow(dead_code)]
@@ -1277,13 +1357,14 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
         if let Some(x_elimination_ops) = self.first_phase() {
             self.A.disable_column_access_acceleration();
 
-            if !self.second_phase(&x_elimination_ops) {
+            if !self.second_phase(&x_elimination_ops)
+                || !self.third_phase(&x_elimination_ops)
+                || !self.fourth_phase()
+                || !self.fifth_phase(&x_elimination_ops)
+                || self.check_operation_limit()
+            {
                 return (None, None);
             }
-
-            self.third_phase(&x_elimination_ops);
-            self.fourth_phase();
-            self.fifth_phase(&x_elimination_ops);
         } else {
             return (None, None);
         }
@@ -1312,12 +1393,44 @@ impl<T: BinaryMatrix> IntermediateSymbolDecoder<T> {
             reorder.push(*i);
         }
 
-        let mut operation_vector = std::mem::replace(&mut self.deferred_D_ops, vec![]);
+        let mut operation_vector = std::mem::take(&mut self.deferred_D_ops);
         operation_vector.push(SymbolOps::Reorder { order: reorder });
         return (Some(result), Some(operation_vector));
     }
 }
 
+// The rank of the rows of the matrix from first_row onward, restricted to the columns in
+// first_col..width. The matrix is left in row echelon form.
+fn dense_rank(
+    matrix: &mut DenseOctetMatrix,
+    first_row: usize,
+    first_col: usize,
+    width: usize,
+) -> usize {
+    let mut rank = 0;
+    for col in first_col..width {
+        let pivot_row = first_row + rank;
+        if pivot_row == matrix.height() {
+            break;
+        }
+        if let Some(row) =
+            (pivot_row..matrix.height()).find(|&row| matrix.get(row, col) != Octet::zero())
+        {
+            matrix.swap_rows(pivot_row, row);
+            let inverse = Octet::one() / matrix.get(pivot_row, col);
+            matrix.mul_assign_row(pivot_row, &inverse);
+            for row in (pivot_row + 1)..matrix.height() {
+                let scalar = matrix.get(row, col);
+                if scalar != Octet::zero() {
+                    matrix.fma_rows(row, pivot_row, &scalar);
+                }
+            }
+            rank += 1;
+        }
+    }
+    rank
+}
+
 // Fused implementation for self.inverse().mul_symbols(symbols)
 // See section 5.4.2.1
 pub fn fused_inverse_mul_symbols<T: BinaryMatrix>(
@@ -240,3 +240,40 @@ where
 
     (V0[x0 as usize] ^ V1[x1 as usize] ^ V2[x2 as usize] ^ V3[x3 as usize]) % m
 }
+
+// SplitMix64 (Steele, Lea & Flood, "Fast Splittable Pseudorandom Number Generators", 2014).
+// Only uses wrapping 64-bit arithmetic, so the sequence for a given seed is the same on every
+// platform.
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+// Deterministically shuffles items with a Fisher-Yates shuffle, from the last item to the first,
+// swapping item i with item j = next_u64() % (i + 1), where next_u64() is the SplitMix64 sequence
+// for the given seed
+pub fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut rng = SplitMix64 { state: seed };
+    for i in (1..items.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+// Deterministically generates count non-zero GF(256) coefficients from the SplitMix64 sequence
+// for the given seed, as next_u64() % 255 + 1
+pub fn nonzero_octets(seed: u64, count: usize) -> Vec<u8> {
+    let mut rng = SplitMix64 { state: seed };
+    (0..count)
+        .map(|_| (rng.next_u64() % 255 + 1) as u8)
+        .collect()
+}
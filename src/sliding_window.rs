@@ -0,0 +1,542 @@
+use crate::base::ParseError;
+use crate::octet::Octet;
+use crate::octets::{add_assign, fused_addassign_mul_scalar, mulassign_scalar};
+use crate::rng::nonzero_octets;
+use crate::symbol::Symbol;
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+const HEADER_BYTES: usize = 6;
+const LENGTH_PREFIX_BYTES: usize = 2;
+
+/// A packet of a sliding window protected stream, as produced by [`SlidingWindowEncoder`].
+///
+/// Serialized as the sequence number of the source packet (or of the last source packet that
+/// the repair packet protects) as a 32-bit big-endian integer, followed by the number of source
+/// packets that a repair packet protects as a 16-bit big-endian integer, which is zero for source
+/// packets, followed by the payload.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub enum SlidingWindowPacket {
+    /// A source packet, carried unmodified
+    Source { sequence: u32, data: Vec<u8> },
+    /// A repair packet, protecting the `window` source packets up to and including
+    /// `last_sequence`
+    Repair {
+        last_sequence: u32,
+        window: u16,
+        data: Vec<u8>,
+    },
+}
+
+impl SlidingWindowPacket {
+    pub fn deserialize(data: &[u8]) -> Result<SlidingWindowPacket, ParseError> {
+        if data.len() < HEADER_BYTES {
+            return Err(ParseError::InvalidLength {
+                expected: HEADER_BYTES,
+                actual: data.len(),
+            });
+        }
+        let sequence = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        let window = u16::from_be_bytes([data[4], data[5]]);
+        let payload = data[HEADER_BYTES..].to_vec();
+        if window == 0 {
+            Ok(SlidingWindowPacket::Source {
+                sequence,
+                data: payload,
+            })
+        } else {
+            Ok(SlidingWindowPacket::Repair {
+                last_sequence: sequence,
+                window,
+                data: payload,
+            })
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let (sequence, window, data) = match self {
+            SlidingWindowPacket::Source { sequence, data } => (*sequence, 0, data),
+            SlidingWindowPacket::Repair {
+                last_sequence,
+                window,
+                data,
+            } => (*last_sequence, *window, data),
+        };
+        let mut serialized = Vec::with_capacity(HEADER_BYTES + data.len());
+        serialized.extend_from_slice(&sequence.to_be_bytes());
+        serialized.extend_from_slice(&window.to_be_bytes());
+        serialized.extend_from_slice(data);
+        serialized
+    }
+}
+
+/// Sliding window FEC for real-time streams, where waiting for a whole object to be buffered
+/// before encoding it adds too much latency.
+///
+/// Each source packet is sent as soon as it is added, and after every `repair_interval` source
+/// packets a repair packet is sent, which is a random linear combination over GF(256) of the last
+/// `window_size` source packets. A lost source packet can be recovered by
+/// [`SlidingWindowDecoder`] once enough repair packets covering it have arrived: a single loss is
+/// recovered by the next repair packet, so at most `repair_interval` source packets later, and a
+/// burst of losses by as many repair packets as packets were lost, provided that they all arrive
+/// while the lost packets are still in their window. A packet which is still missing
+/// `window_size` source packets after it was sent can no longer be recovered, so the window size
+/// bounds both the memory used and the longest burst that can be repaired, while the interval
+/// sets the overhead, of one repair packet per `repair_interval` source packets.
+///
+/// Source packets may have any length up to `symbol_size`. Repair packets are `symbol_size + 2`
+/// bytes, since the length of each source packet is protected along with its data. Fewer than 2^32
+/// source packets can be sent, since sequence numbers do not wrap.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct SlidingWindowEncoder {
+    symbol_size: u16,
+    window_size: u16,
+    repair_interval: u16,
+    // Source symbols of the last window_size source packets, oldest first
+    window: VecDeque<Symbol>,
+    next_sequence: u32,
+    // Number of source packets added since the last repair packet
+    unprotected: u16,
+}
+
+impl SlidingWindowEncoder {
+    pub fn new(symbol_size: u16, window_size: u16, repair_interval: u16) -> SlidingWindowEncoder {
+        assert!(window_size > 0);
+        // Otherwise some source packets would never be protected
+        assert!(repair_interval > 0 && repair_interval <= window_size);
+        SlidingWindowEncoder {
+            symbol_size,
+            window_size,
+            repair_interval,
+            window: VecDeque::with_capacity(window_size as usize),
+            next_sequence: 0,
+            unprotected: 0,
+        }
+    }
+
+    /// Adds the next source packet of the stream, and returns the packets to send: the source
+    /// packet, followed by a repair packet every `repair_interval` source packets.
+    pub fn add_source_packet(&mut self, data: &[u8]) -> Vec<SlidingWindowPacket> {
+        assert!(data.len() <= self.symbol_size as usize);
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        if self.window.len() == self.window_size as usize {
+            self.window.pop_front();
+        }
+        self.window.push_back(to_symbol(data, self.symbol_size));
+        self.unprotected += 1;
+
+        let mut packets = vec![SlidingWindowPacket::Source {
+            sequence,
+            data: data.to_vec(),
+        }];
+        if self.unprotected == self.repair_interval {
+            packets.push(self.repair_packet());
+        }
+        packets
+    }
+
+    /// Generates a repair packet for the source packets added since the last one, if any, so that
+    /// the end of the stream, or of a burst of packets, can be recovered without waiting for
+    /// `repair_interval` more source packets.
+    pub fn flush(&mut self) -> Option<SlidingWindowPacket> {
+        if self.unprotected == 0 {
+            None
+        } else {
+            Some(self.repair_packet())
+        }
+    }
+
+    fn repair_packet(&mut self) -> SlidingWindowPacket {
+        self.unprotected = 0;
+        let last_sequence = self.next_sequence - 1;
+        let window = self.window.len() as u16;
+        let mut repair = Symbol::zero(LENGTH_PREFIX_BYTES + self.symbol_size as usize);
+        for (symbol, &coefficient) in self
+            .window
+            .iter()
+            .zip(repair_coefficients(last_sequence, window).iter())
+        {
+            add_scaled_symbol(&mut repair, symbol, &Octet::new(coefficient));
+        }
+        SlidingWindowPacket::Repair {
+            last_sequence,
+            window,
+            data: repair.into_bytes(),
+        }
+    }
+}
+
+/// Recovers the source packets lost from a stream encoded by [`SlidingWindowEncoder`], with the
+/// same `symbol_size` and `window_size`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct SlidingWindowDecoder {
+    symbol_size: u16,
+    window_size: u16,
+    // Source symbols which have been received or recovered, by sequence number
+    known: BTreeMap<u32, Symbol>,
+    // Repair packets which protect at least one missing source packet, as
+    // (last_sequence, window, symbol)
+    repairs: Vec<(u32, u16, Symbol)>,
+    highest_sequence: u32,
+}
+
+impl SlidingWindowDecoder {
+    pub fn new(symbol_size: u16, window_size: u16) -> SlidingWindowDecoder {
+        assert!(window_size > 0);
+        SlidingWindowDecoder {
+            symbol_size,
+            window_size,
+            known: BTreeMap::new(),
+            repairs: vec![],
+            highest_sequence: 0,
+        }
+    }
+
+    /// Adds a received packet, and returns the (sequence number, data) of the source packets
+    /// which it allowed to be recovered, in sequence order. Received source packets are not
+    /// returned, and packets which are too old to be recovered are ignored.
+    ///
+    /// Repair packets which don't match the symbol size, or claim to protect packets before the
+    /// start of the stream, are ignored.
+    pub fn add_packet(&mut self, packet: SlidingWindowPacket) -> Vec<(u32, Vec<u8>)> {
+        match packet {
+            SlidingWindowPacket::Source { sequence, data } => {
+                if data.len() > self.symbol_size as usize || self.is_expired(sequence) {
+                    return vec![];
+                }
+                self.highest_sequence = self.highest_sequence.max(sequence);
+                let symbol_size = self.symbol_size;
+                self.known
+                    .entry(sequence)
+                    .or_insert_with(|| to_symbol(&data, symbol_size));
+            }
+            SlidingWindowPacket::Repair {
+                last_sequence,
+                window,
+                data,
+            } => {
+                if data.len() != LENGTH_PREFIX_BYTES + self.symbol_size as usize
+                    || window == 0
+                    || window as u64 > last_sequence as u64 + 1
+                    || self.is_expired(last_sequence)
+                {
+                    return vec![];
+                }
+                self.highest_sequence = self.highest_sequence.max(last_sequence);
+                self.repairs
+                    .push((last_sequence, window, Symbol::new(data)));
+            }
+        }
+        let recovered = self.recover();
+        self.prune();
+        recovered
+    }
+
+    // True if the source packet with the given sequence number is too old to be protected by any
+    // repair packet which could still be used
+    fn is_expired(&self, sequence: u32) -> bool {
+        sequence as u64 + 2 * self.window_size as u64 <= self.highest_sequence as u64
+    }
+
+    // Discards source symbols and repair packets which can no longer help recover a packet
+    fn prune(&mut self) {
+        let horizon = self
+            .highest_sequence
+            .saturating_sub(2 * self.window_size as u32 - 1);
+        self.known = self.known.split_off(&horizon);
+        let window_size = self.window_size as u64;
+        let highest_sequence = self.highest_sequence as u64;
+        self.repairs
+            .retain(|&(last_sequence, _, _)| last_sequence as u64 + window_size > highest_sequence);
+    }
+
+    // Solves the linear system formed by the pending repair packets for the missing source
+    // packets, and returns those which it determines
+    fn recover(&mut self) -> Vec<(u32, Vec<u8>)> {
+        let known = &self.known;
+        self.repairs.retain(|&(last_sequence, window, _)| {
+            protected_sequences(last_sequence, window)
+                .any(|sequence| !known.contains_key(&sequence))
+        });
+        let mut unknowns: Vec<u32> = self
+            .repairs
+            .iter()
+            .flat_map(|&(last_sequence, window, _)| protected_sequences(last_sequence, window))
+            .filter(|sequence| !known.contains_key(sequence))
+            .collect();
+        unknowns.sort_unstable();
+        unknowns.dedup();
+        if unknowns.is_empty() {
+            return vec![];
+        }
+
+        // Each repair packet gives one equation in the missing source symbols, once the received
+        // source symbols have been subtracted from it
+        let mut rows: Vec<(Vec<u8>, Symbol)> = Vec::with_capacity(self.repairs.len());
+        for &(last_sequence, window, ref symbol) in self.repairs.iter() {
+            let mut coefficients = vec![0; unknowns.len()];
+            let mut value = symbol.clone();
+            for (sequence, &coefficient) in protected_sequences(last_sequence, window)
+                .zip(repair_coefficients(last_sequence, window).iter())
+            {
+                match known.get(&sequence) {
+                    Some(source) => add_scaled_symbol(&mut value, source, &Octet::new(coefficient)),
+                    None => {
+                        coefficients[unknowns.binary_search(&sequence).unwrap()] = coefficient;
+                    }
+                }
+            }
+            rows.push((coefficients, value));
+        }
+
+        // Reduce to reduced row echelon form. A missing symbol is determined if the row of its
+        // pivot has no other non-zero coefficient
+        let mut pivots = vec![];
+        for column in 0..unknowns.len() {
+            let pivot_row = pivots.len();
+            let row = match (pivot_row..rows.len()).find(|&row| rows[row].0[column] != 0) {
+                Some(row) => row,
+                None => continue,
+            };
+            rows.swap(pivot_row, row);
+            let inverse = Octet::one() / Octet::new(rows[pivot_row].0[column]);
+            mulassign_scalar(&mut rows[pivot_row].0, &inverse);
+            rows[pivot_row].1.mulassign_scalar(&inverse);
+            let (pivot_coefficients, pivot_value) = rows[pivot_row].clone();
+            for (row, (coefficients, value)) in rows.iter_mut().enumerate() {
+                if row != pivot_row && coefficients[column] != 0 {
+                    let scalar = Octet::new(coefficients[column]);
+                    add_scaled_row(coefficients, &pivot_coefficients, &scalar);
+                    add_scaled_symbol(value, &pivot_value, &scalar);
+                }
+            }
+            pivots.push(column);
+        }
+
+        let mut recovered = vec![];
+        for ((coefficients, value), column) in rows.into_iter().zip(pivots) {
+            if coefficients
+                .iter()
+                .enumerate()
+                .all(|(i, &coefficient)| i == column || coefficient == 0)
+            {
+                recovered.push((unknowns[column], from_symbol(&value)));
+                self.known.insert(unknowns[column], value);
+            }
+        }
+        recovered.sort_unstable_by_key(|&(sequence, _)| sequence);
+        recovered
+    }
+}
+
+// Sequence numbers of the source packets protected by a repair packet, oldest first
+fn protected_sequences(last_sequence: u32, window: u16) -> impl Iterator<Item = u32> {
+    (last_sequence - (window as u32 - 1))..=last_sequence
+}
+
+// Coefficients of the source symbols in a repair symbol, oldest first
+fn repair_coefficients(last_sequence: u32, window: u16) -> Vec<u8> {
+    nonzero_octets(last_sequence as u64, window as usize)
+}
+
+// Prefixes the packet with its length and pads it to the symbol size, so that packets of different
+// lengths can be combined, and recovered with their original length
+fn to_symbol(data: &[u8], symbol_size: u16) -> Symbol {
+    let mut symbol = Vec::with_capacity(LENGTH_PREFIX_BYTES + symbol_size as usize);
+    symbol.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    symbol.extend_from_slice(data);
+    symbol.resize(LENGTH_PREFIX_BYTES + symbol_size as usize, 0);
+    Symbol::new(symbol)
+}
+
+fn from_symbol(symbol: &Symbol) -> Vec<u8> {
+    let bytes = symbol.as_bytes();
+    let length = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+    // A corrupted repair packet may give an invalid length
+    let end = (LENGTH_PREFIX_BYTES + length).min(bytes.len());
+    bytes[LENGTH_PREFIX_BYTES..end].to_vec()
+}
+
+fn add_scaled_row(row: &mut [u8], other: &[u8], scalar: &Octet) {
+    if *scalar == Octet::one() {
+        add_assign(row, other);
+    } else {
+        fused_addassign_mul_scalar(row, other, scalar);
+    }
+}
+
+fn add_scaled_symbol(symbol: &mut Symbol, other: &Symbol, scalar: &Octet) {
+    if *scalar == Octet::one() {
+        *symbol += other;
+    } else {
+        symbol.fused_addassign_mul_scalar(other, scalar);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base::ParseError;
+    use crate::{SlidingWindowDecoder, SlidingWindowEncoder, SlidingWindowPacket};
+    use rand::Rng;
+
+    fn gen_packets(count: usize) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|_| {
+                let length = rand::thread_rng().gen_range(0..=100);
+                (0..length).map(|_| rand::thread_rng().gen()).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn layout() {
+        let source = SlidingWindowPacket::Source {
+            sequence: 0x0102_0304,
+            data: vec![0xAA],
+        };
+        assert_eq!(source.serialize(), vec![1, 2, 3, 4, 0, 0, 0xAA]);
+        assert_eq!(
+            SlidingWindowPacket::deserialize(&source.serialize()),
+            Ok(source)
+        );
+        let repair = SlidingWindowPacket::Repair {
+            last_sequence: 7,
+            window: 0x0506,
+            data: vec![0xBB, 0xCC],
+        };
+        assert_eq!(repair.serialize(), vec![0, 0, 0, 7, 5, 6, 0xBB, 0xCC]);
+        assert_eq!(
+            SlidingWindowPacket::deserialize(&repair.serialize()),
+            Ok(repair)
+        );
+        assert_eq!(
+            SlidingWindowPacket::deserialize(&[0; 5]),
+            Err(ParseError::InvalidLength {
+                expected: 6,
+                actual: 5
+            })
+        );
+    }
+
+    #[test]
+    fn repair_schedule() {
+        let mut encoder = SlidingWindowEncoder::new(100, 8, 3);
+        let mut repairs = vec![];
+        for (i, data) in gen_packets(20).iter().enumerate() {
+            let packets = encoder.add_source_packet(data);
+            assert_eq!(
+                packets[0],
+                SlidingWindowPacket::Source {
+                    sequence: i as u32,
+                    data: data.clone()
+                }
+            );
+            if let Some(SlidingWindowPacket::Repair {
+                last_sequence,
+                window,
+                data,
+            }) = packets.get(1)
+            {
+                assert_eq!(data.len(), 102);
+                repairs.push((*last_sequence, *window));
+            } else {
+                assert_eq!(packets.len(), 1);
+            }
+        }
+        assert_eq!(
+            repairs,
+            vec![(2, 3), (5, 6), (8, 8), (11, 8), (14, 8), (17, 8)]
+        );
+        match encoder.flush() {
+            Some(SlidingWindowPacket::Repair {
+                last_sequence,
+                window,
+                ..
+            }) => assert_eq!((last_sequence, window), (19, 8)),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(encoder.flush(), None);
+    }
+
+    #[test]
+    fn recover_single_losses() {
+        let source = gen_packets(100);
+        let mut encoder = SlidingWindowEncoder::new(100, 8, 4);
+        let mut decoder = SlidingWindowDecoder::new(100, 8);
+        let mut delivered = vec![None; source.len()];
+        for (i, data) in source.iter().enumerate() {
+            for packet in encoder.add_source_packet(data) {
+                // Drop one source packet in every repair interval
+                if let SlidingWindowPacket::Source { sequence, data } = &packet {
+                    if sequence % 4 == 1 {
+                        continue;
+                    }
+                    delivered[*sequence as usize] = Some(data.clone());
+                }
+                for (sequence, data) in decoder.add_packet(packet) {
+                    assert!(sequence as usize <= i);
+                    assert!(i - (sequence as usize) < 4);
+                    assert_eq!(delivered[sequence as usize], None);
+                    delivered[sequence as usize] = Some(data);
+                }
+            }
+        }
+        let delivered: Vec<Vec<u8>> = delivered.into_iter().map(Option::unwrap).collect();
+        assert_eq!(delivered, source);
+    }
+
+    #[test]
+    fn recover_burst_loss() {
+        let source = gen_packets(40);
+        let mut encoder = SlidingWindowEncoder::new(100, 16, 2);
+        let mut decoder = SlidingWindowDecoder::new(100, 16);
+        let mut recovered = vec![];
+        for data in source.iter() {
+            for packet in encoder.add_source_packet(data) {
+                if let SlidingWindowPacket::Source { sequence, .. } = packet {
+                    // A burst of 5 lost source packets
+                    if (10..15).contains(&sequence) {
+                        continue;
+                    }
+                }
+                // Serialize, as if sent over the network
+                let packet = SlidingWindowPacket::deserialize(&packet.serialize()).unwrap();
+                recovered.extend(decoder.add_packet(packet));
+            }
+        }
+        let expected: Vec<(u32, Vec<u8>)> =
+            (10..15).map(|i| (i, source[i as usize].clone())).collect();
+        assert_eq!(recovered, expected);
+    }
+
+    #[test]
+    fn unrecoverable_loss() {
+        let source = gen_packets(40);
+        let mut encoder = SlidingWindowEncoder::new(100, 4, 4);
+        let mut decoder = SlidingWindowDecoder::new(100, 4);
+        let mut recovered = vec![];
+        for data in source.iter() {
+            for packet in encoder.add_source_packet(data) {
+                match packet {
+                    // Two losses in a window protected by a single repair packet
+                    SlidingWindowPacket::Source { sequence: 5, .. }
+                    | SlidingWindowPacket::Source { sequence: 6, .. } => {}
+                    // A loss whose only repair packet is lost too
+                    SlidingWindowPacket::Source { sequence: 20, .. }
+                    | SlidingWindowPacket::Repair {
+                        last_sequence: 23, ..
+                    } => {}
+                    packet => recovered.extend(decoder.add_packet(packet)),
+                }
+            }
+        }
+        assert!(recovered.is_empty());
+        assert!(decoder.known.len() <= 8);
+        assert!(decoder.repairs.is_empty());
+    }
+}
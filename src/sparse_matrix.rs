@@ -1,6 +1,6 @@
 use crate::arraymap::{ImmutableListMap, ImmutableListMapBuilder};
 use crate::iterators::OctetIter;
-use crate::matrix::BinaryMatrix;
+use crate::matrix::{zeroed_words, BinaryMatrix, MatrixWords};
 use crate::octet::Octet;
 use crate::octets::BinaryOctetVec;
 use crate::sparse_vec::SparseBinaryVec;
@@ -21,7 +21,7 @@ pub struct SparseBinaryMatrix {
     sparse_elements: Vec<SparseBinaryVec>,
     // Note these are stored right aligned, so that the right most element is always at
     // dense_elements[x] & (1 << 63)
-    dense_elements: Vec<u64>,
+    dense_elements: MatrixWords,
     // Columnar storage of values. Only stores rows that have a 1-valued entry in the given column
     sparse_columnar_values: Option<ImmutableListMap>,
     // Mapping of logical row numbers to index in sparse_elements, dense_elements, and sparse_column_index
@@ -114,9 +114,9 @@ impl BinaryMatrix for SparseBinaryMatrix {
             col_mapping[i] = i as u16;
         }
         let dense_elements = if trailing_dense_column_hint > 0 {
-            vec![0; height * ((trailing_dense_column_hint - 1) / WORD_WIDTH + 1)]
+            zeroed_words(height * ((trailing_dense_column_hint - 1) / WORD_WIDTH + 1))
         } else {
-            vec![]
+            zeroed_words(0)
         };
         SparseBinaryMatrix {
             height,
@@ -243,7 +243,7 @@ impl BinaryMatrix for SparseBinaryMatrix {
         }
     }
 
-    fn get_row_iter(&self, row: usize, start_col: usize, end_col: usize) -> OctetIter {
+    fn get_row_iter(&self, row: usize, start_col: usize, end_col: usize) -> OctetIter<'_> {
         if end_col > self.width - self.num_dense_columns {
             unimplemented!("It was assumed that this wouldn't be needed, because the method would only be called on the V section of matrix A");
         }
@@ -258,7 +258,7 @@ impl BinaryMatrix for SparseBinaryMatrix {
     }
 
     fn get_ones_in_column(&self, col: usize, start_row: usize, end_row: usize) -> Vec<u32> {
-        assert_eq!(self.column_index_disabled, false);
+        assert!(!self.column_index_disabled);
         #[cfg(debug_assertions)]
         debug_assert!(self.debug_indexed_column_valid[col]);
         let physical_col = self.logical_col_to_physical[col];
@@ -321,7 +321,7 @@ impl BinaryMatrix for SparseBinaryMatrix {
             i,
             "Can only freeze the last sparse column"
         );
-        assert_eq!(self.column_index_disabled, false);
+        assert!(!self.column_index_disabled);
         self.num_dense_columns += 1;
         let (last_word, _) = self.bit_position(self.height - 1, self.num_dense_columns - 1);
         // If this is in a new word
@@ -426,7 +426,7 @@ impl BinaryMatrix for SparseBinaryMatrix {
 
         if columns_to_remove == 0 && self.num_dense_columns > 0 {
             // TODO: optimize to not allocate this extra vec
-            let mut new_dense = vec![0; new_height * self.row_word_width()];
+            let mut new_dense = zeroed_words(new_height * self.row_word_width());
             for logical_row in 0..new_height {
                 let physical_row = self.logical_row_to_physical[logical_row] as usize;
                 for word in 0..self.row_word_width() {
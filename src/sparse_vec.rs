@@ -23,6 +23,7 @@ impl SparseBinaryVec {
         self.elements.binary_search(&i)
     }
 
+    #[allow(dead_code)]
     pub fn size_in_bytes(&self) -> usize {
         size_of::<Self>() + size_of::<u16>() * self.elements.len()
     }
@@ -63,7 +64,7 @@ impl SparseBinaryVec {
         loop {
             if let Some(self_index) = self_next {
                 if let Some(other_index) = other_next {
-                    match self_index.cmp(&other_index) {
+                    match self_index.cmp(other_index) {
                         Ordering::Less => {
                             result.push(*self_index);
                             self_next = self_iter.next();
@@ -29,6 +29,7 @@ impl Symbol {
     }
 
     #[cfg(feature = "benchmarking")]
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         self.value.len()
     }
@@ -0,0 +1,76 @@
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Storage for the symbols received by a [`SourceBlockDecoder`](crate::SourceBlockDecoder),
+/// addressed by slot index.
+///
+/// Implement this to hold received symbols outside of the heap, for example in a file, when
+/// decoding objects larger than the available RAM. Source symbols are stored in the slot equal
+/// to their ESI, and repair symbols in consecutive slots from K onwards, in the order received.
+///
+/// Every decoding attempt reads back all the stored symbols, and the matrix solve still holds
+/// the L intermediate symbols of the block in memory, so only the buffering of received symbols
+/// is moved out of core. A decoding attempt is made for each packet received once at least K'
+/// symbols are available, and stores which return [`Cow::Owned`] data pay for a copy of every
+/// symbol on each attempt.
+pub trait SymbolStore {
+    /// Stores `data` in the given slot, replacing any previous contents.
+    fn put(&mut self, index: usize, data: Vec<u8>);
+
+    /// Retrieves the symbol in the given slot, or `None` if nothing was stored there.
+    fn get(&self, index: usize) -> Option<Cow<'_, [u8]>>;
+
+    /// Hints that `additional` more symbols are expected to be stored.
+    fn reserve(&mut self, _additional: usize) {}
+}
+
+/// The default [`SymbolStore`], which keeps every symbol on the heap.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct MemorySymbolStore {
+    symbols: Vec<Option<Vec<u8>>>,
+}
+
+impl MemorySymbolStore {
+    pub fn new() -> MemorySymbolStore {
+        MemorySymbolStore { symbols: vec![] }
+    }
+}
+
+impl SymbolStore for MemorySymbolStore {
+    fn put(&mut self, index: usize, data: Vec<u8>) {
+        if index >= self.symbols.len() {
+            self.symbols.resize(index + 1, None);
+        }
+        self.symbols[index] = Some(data);
+    }
+
+    fn get(&self, index: usize) -> Option<Cow<'_, [u8]>> {
+        self.symbols
+            .get(index)
+            .and_then(|symbol| symbol.as_ref())
+            .map(|symbol| Cow::Borrowed(&symbol[..]))
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.symbols.reserve(additional);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemorySymbolStore, SymbolStore};
+
+    #[test]
+    fn memory_store() {
+        let mut store = MemorySymbolStore::new();
+        assert_eq!(store.get(0), None);
+        store.put(3, vec![1, 2, 3]);
+        assert_eq!(store.get(3).unwrap()[..], [1, 2, 3]);
+        assert_eq!(store.get(2), None);
+        assert_eq!(store.get(4), None);
+        store.put(3, vec![4]);
+        assert_eq!(store.get(3).unwrap()[..], [4]);
+    }
+}
@@ -965,17 +965,27 @@ const P1_TABLE: [(u32, u32); 477] = [
 // Calculates, K', the extended source block size, in symbols, for a given source block size
 // See section 5.3.1
 pub fn extended_source_block_symbols(source_block_symbols: u32) -> u32 {
-    assert!(source_block_symbols <= MAX_SOURCE_SYMBOLS_PER_BLOCK);
-    for &(block_size, _, _, _, _) in SYSTEMATIC_INDICES_AND_PARAMETERS.iter() {
-        if block_size >= source_block_symbols {
-            return block_size;
-        }
+    kprime_for(source_block_symbols)
+}
+
+/// Finds K', the smallest supported number of source symbols which is at least `k`.
+///
+/// A source block of K symbols is padded with K' - K zero symbols before encoding, as described
+/// in [section 5.3.1](https://tools.ietf.org/html/rfc6330#section-5.3.1).
+///
+/// The other parameters of a source block, J(K'), S(K'), H(K') and W(K'), are read from the row of
+/// Table 2 in [section 5.6](https://tools.ietf.org/html/rfc6330#section-5.6) for this K'.
+pub fn kprime_for(k: u32) -> u32 {
+    assert!(k <= MAX_SOURCE_SYMBOLS_PER_BLOCK);
+    match SYSTEMATIC_INDICES_AND_PARAMETERS.binary_search_by_key(&k, |&(kprime, _, _, _, _)| kprime)
+    {
+        Ok(index) | Err(index) => SYSTEMATIC_INDICES_AND_PARAMETERS[index].0,
     }
-    unreachable!();
 }
 
-// Calculates, J(K'), the systematic index, for a given number of source block symbols
-// See section 5.6
+/// Calculates, J(K'), the systematic index, for a given number of source block symbols.
+///
+/// See section 5.6, and [`kprime_for`] for how K' is chosen.
 pub fn systematic_index(source_block_symbols: u32) -> u32 {
     assert!(source_block_symbols <= MAX_SOURCE_SYMBOLS_PER_BLOCK);
     for &(block_size, systematic_index, _, _, _) in SYSTEMATIC_INDICES_AND_PARAMETERS.iter() {
@@ -986,8 +996,9 @@ pub fn systematic_index(source_block_symbols: u32) -> u32 {
     unreachable!();
 }
 
-// Calculates, H(K'), the number of HDPC symbols, for a given number of source block symbols
-// See section 5.6
+/// Calculates, H(K'), the number of HDPC symbols, for a given number of source block symbols.
+///
+/// See section 5.6, and [`kprime_for`] for how K' is chosen.
 pub fn num_hdpc_symbols(source_block_symbols: u32) -> u32 {
     assert!(source_block_symbols <= MAX_SOURCE_SYMBOLS_PER_BLOCK);
     for &(block_size, _, _, hdpc_symbols, _) in SYSTEMATIC_INDICES_AND_PARAMETERS.iter() {
@@ -998,8 +1009,9 @@ pub fn num_hdpc_symbols(source_block_symbols: u32) -> u32 {
     unreachable!();
 }
 
-// Calculates, S(K'), the number of LDPC symbols, for a given number of source block symbols
-// See section 5.6
+/// Calculates, S(K'), the number of LDPC symbols, for a given number of source block symbols.
+///
+/// See section 5.6, and [`kprime_for`] for how K' is chosen.
 pub fn num_ldpc_symbols(source_block_symbols: u32) -> u32 {
     assert!(source_block_symbols <= MAX_SOURCE_SYMBOLS_PER_BLOCK);
     for &(block_size, _, ldpc_symbols, _, _) in SYSTEMATIC_INDICES_AND_PARAMETERS.iter() {
@@ -1010,8 +1022,9 @@ pub fn num_ldpc_symbols(source_block_symbols: u32) -> u32 {
     unreachable!();
 }
 
-// Calculates, W(K'), the number of LT symbols, for a given number of source block symbols
-// See section 5.6
+/// Calculates, W(K'), the number of LT symbols, for a given number of source block symbols.
+///
+/// See section 5.6, and [`kprime_for`] for how K' is chosen.
 pub fn num_lt_symbols(source_block_symbols: u32) -> u32 {
     assert!(source_block_symbols <= MAX_SOURCE_SYMBOLS_PER_BLOCK);
     for &(block_size, _, _, _, lt_symbols) in SYSTEMATIC_INDICES_AND_PARAMETERS.iter() {
@@ -1022,8 +1035,11 @@ pub fn num_lt_symbols(source_block_symbols: u32) -> u32 {
     unreachable!();
 }
 
-// Calculates, L, the number of intermediate symbols, for a given number of source block symbols
-// See section 5.3.3.3
+/// Calculates, L = K' + S + H, the number of intermediate symbols, for a given number of source
+/// block symbols.
+///
+/// As defined in section [5.3.3.3](https://tools.ietf.org/html/rfc6330#section-5.3.3.3). This is
+/// the number of columns of the constraint matrix.
 pub fn num_intermediate_symbols(source_block_symbols: u32) -> u32 {
     extended_source_block_symbols(source_block_symbols)
         + num_ldpc_symbols(source_block_symbols)
@@ -1036,7 +1052,10 @@ pub fn num_pi_symbols(source_block_symbols: u32) -> u32 {
     num_intermediate_symbols(source_block_symbols) - num_lt_symbols(source_block_symbols)
 }
 
-// Calculates P1, smallest prime greater than P. See 5.3.3.3
+/// Calculates P1, the smallest prime greater than or equal to P, the number of PI symbols, for a
+/// given number of source block symbols.
+///
+/// As defined in section [5.3.3.3](https://tools.ietf.org/html/rfc6330#section-5.3.3.3).
 pub fn calculate_p1(source_block_symbols: u32) -> u32 {
     assert!(source_block_symbols <= MAX_SOURCE_SYMBOLS_PER_BLOCK);
     for &(block_size, p1) in P1_TABLE.iter() {
@@ -1052,6 +1071,48 @@ mod tests {
     use crate::systematic_constants::num_ldpc_symbols;
     use crate::systematic_constants::num_lt_symbols;
     use crate::systematic_constants::{calculate_p1, num_pi_symbols, MAX_SOURCE_SYMBOLS_PER_BLOCK};
+    use crate::systematic_constants::{kprime_for, SYSTEMATIC_INDICES_AND_PARAMETERS};
+    use crate::systematic_constants::{num_hdpc_symbols, num_intermediate_symbols};
+
+    #[test]
+    fn kprime_boundaries() {
+        assert_eq!(kprime_for(0), 10);
+        assert_eq!(kprime_for(10), 10);
+        assert_eq!(kprime_for(11), 12);
+        assert_eq!(kprime_for(12), 12);
+        assert_eq!(kprime_for(13), 18);
+        assert_eq!(kprime_for(56402), 56403);
+        assert_eq!(kprime_for(56403), 56403);
+        for window in SYSTEMATIC_INDICES_AND_PARAMETERS.windows(2) {
+            let (previous, next) = (window[0].0, window[1].0);
+            assert_eq!(kprime_for(previous), previous);
+            assert_eq!(kprime_for(previous + 1), next);
+            if next - 1 > previous {
+                assert_eq!(kprime_for(next - 1), next);
+            }
+        }
+    }
+
+    #[test]
+    fn intermediate_symbols() {
+        // (K', S, H) from Table 2 of section 5.6
+        let rows = [
+            (10, 7, 10),
+            (12, 7, 10),
+            (18, 11, 10),
+            (101, 17, 10),
+            (1002, 59, 10),
+            (56403, 907, 16),
+        ];
+        for &(kprime, ldpc, hdpc) in rows.iter() {
+            assert_eq!(num_ldpc_symbols(kprime), ldpc);
+            assert_eq!(num_hdpc_symbols(kprime), hdpc);
+            assert_eq!(num_intermediate_symbols(kprime), kprime + ldpc + hdpc);
+        }
+        // Source blocks which are not a K' of the table are padded to the next one
+        assert_eq!(num_ldpc_symbols(13), 11);
+        assert_eq!(num_intermediate_symbols(13), 18 + 11 + 10);
+    }
 
     #[test]
     fn all_prime() {
@@ -0,0 +1,222 @@
+use crate::base::EncodingPacket;
+use crate::base::ParseError;
+use crate::base::PayloadId;
+use crate::octets::add_assign;
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+const HEADER_BYTES: usize = 6;
+
+/// A simple parity symbol: the XOR of a group of consecutive source symbols of one source block,
+/// as generated by [`Encoder::xor_parity`](crate::Encoder::xor_parity).
+///
+/// This is a much cheaper, and much weaker, alternative to RaptorQ repair symbols for links with
+/// very low loss. Generating and applying it is a single pass of XORs, but it can only recover a
+/// group in which exactly one source symbol was lost; if two or more are lost, it is useless.
+///
+/// Serialized as the Payload ID of the first source symbol of the group, followed by the number
+/// of symbols in the group as a 16-bit big-endian integer, and the parity data.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct XorParityPacket {
+    first_symbol: PayloadId,
+    group_length: u16,
+    data: Vec<u8>,
+}
+
+impl XorParityPacket {
+    pub fn new(first_symbol: PayloadId, group_length: u16, data: Vec<u8>) -> XorParityPacket {
+        XorParityPacket {
+            first_symbol,
+            group_length,
+            data,
+        }
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<XorParityPacket, ParseError> {
+        if data.len() < HEADER_BYTES {
+            return Err(ParseError::InvalidLength {
+                expected: HEADER_BYTES,
+                actual: data.len(),
+            });
+        }
+        Ok(XorParityPacket {
+            first_symbol: PayloadId::deserialize(&[data[0], data[1], data[2], data[3]]),
+            group_length: ((data[4] as u16) << 8) + data[5] as u16,
+            data: data[HEADER_BYTES..].to_vec(),
+        })
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut serialized = Vec::with_capacity(HEADER_BYTES + self.data.len());
+        serialized.extend_from_slice(&self.first_symbol.serialize());
+        serialized.push((self.group_length >> 8) as u8);
+        serialized.push((self.group_length & 0xFF) as u8);
+        serialized.extend_from_slice(&self.data);
+        serialized
+    }
+
+    pub fn source_block_number(&self) -> u8 {
+        self.first_symbol.source_block_number()
+    }
+
+    /// ESIs of the source symbols protected by this packet
+    pub fn esis(&self) -> Range<u32> {
+        let first = self.first_symbol.encoding_symbol_id();
+        first..(first + self.group_length as u32)
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Recovers the source symbol lost from this packet's group, if exactly one of them is
+    /// missing from `received`. Packets for other source blocks, or outside the group, are
+    /// ignored. The final symbol of the group may have been sent without its zero padding (see
+    /// [`EncoderBuilder::set_pad_last_symbol`](crate::EncoderBuilder::set_pad_last_symbol)); if it
+    /// is the one recovered, it is returned with the padding.
+    ///
+    /// Returns `None` if no symbol of the group is missing, if more than one is, or if a packet of
+    /// the group has a different length than the parity data.
+    pub fn recover(&self, received: &[EncodingPacket]) -> Option<EncodingPacket> {
+        let esis = self.esis();
+        let mut seen = vec![false; esis.len()];
+        let mut recovered = self.data.clone();
+        for packet in received {
+            let payload_id = packet.payload_id();
+            let esi = payload_id.encoding_symbol_id();
+            if payload_id.source_block_number() != self.source_block_number()
+                || !esis.contains(&esi)
+                || seen[(esi - esis.start) as usize]
+            {
+                continue;
+            }
+            let data = packet.data();
+            if data.len() > recovered.len() || (data.len() < recovered.len() && esi + 1 != esis.end)
+            {
+                return None;
+            }
+            seen[(esi - esis.start) as usize] = true;
+            add_assign(&mut recovered[..data.len()], data);
+        }
+
+        let mut missing = seen.iter().enumerate().filter(|(_, seen)| !**seen);
+        let (index, _) = missing.next()?;
+        if missing.next().is_some() {
+            return None;
+        }
+        Some(EncodingPacket::new(
+            PayloadId::new(self.source_block_number(), esis.start + index as u32),
+            recovered,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base::ParseError;
+    use crate::XorParityPacket;
+    use crate::{Decoder, Encoder, EncoderBuilder, EncodingPacket};
+    use crate::{ObjectTransmissionInformation, PayloadId};
+
+    #[test]
+    fn layout() {
+        let packet = XorParityPacket::new(PayloadId::new(3, 0x01_0203), 0x0405, vec![0xAA, 0xBB]);
+        let serialized = packet.serialize();
+        assert_eq!(serialized, vec![3, 1, 2, 3, 4, 5, 0xAA, 0xBB]);
+        assert_eq!(XorParityPacket::deserialize(&serialized), Ok(packet));
+        assert_eq!(
+            XorParityPacket::deserialize(&serialized[..5]),
+            Err(ParseError::InvalidLength {
+                expected: 6,
+                actual: 5
+            })
+        );
+    }
+
+    #[test]
+    fn recover_single_loss() {
+        let data: Vec<u8> = (0..1000).map(|i| (i * 7 % 251) as u8).collect();
+        // K = 63, so the last of the groups of 10 has only 3 symbols
+        let config = ObjectTransmissionInformation::new(1000, 16, 1, 1, 1);
+        let encoder = Encoder::new(&data, config);
+        let parity = encoder.xor_parity(10);
+        assert_eq!(parity.len(), 7);
+        assert_eq!(parity[6].esis(), 60..63);
+
+        let source = encoder.get_block_encoders()[0].source_packets();
+        let lost = [4, 17, 61];
+        let received: Vec<EncodingPacket> = source
+            .iter()
+            .filter(|packet| !lost.contains(&packet.payload_id().encoding_symbol_id()))
+            .cloned()
+            .collect();
+
+        let mut recovered = received.clone();
+        for packet in parity.iter() {
+            if let Some(packet) = packet.recover(&received) {
+                recovered.push(packet);
+            }
+        }
+        recovered.sort_by_key(|packet| packet.payload_id().encoding_symbol_id());
+        assert_eq!(recovered, source);
+
+        let mut decoder = Decoder::new(config);
+        assert_eq!(decoder.add_packets(recovered), Some(data));
+    }
+
+    #[test]
+    fn recover_needs_exactly_one_loss() {
+        let data = vec![5; 640];
+        let config = ObjectTransmissionInformation::new(640, 64, 1, 1, 1);
+        let encoder = Encoder::new(&data, config);
+        let parity = &encoder.xor_parity(5)[0];
+        let source = encoder.get_block_encoders()[0].source_packets();
+
+        assert_eq!(parity.recover(&source), None);
+        assert_eq!(parity.recover(&source[1..]), Some(source[0].clone()));
+        assert_eq!(parity.recover(&source[2..]), None);
+    }
+
+    #[test]
+    fn recover_unpadded_last_symbol() {
+        let data: Vec<u8> = (0..1000).map(|i| (i * 7 % 251) as u8).collect();
+        let mut builder = EncoderBuilder::new();
+        builder.set_max_packet_size(64);
+        builder.set_pad_last_symbol(false);
+        let encoder = builder.build(&data);
+        // 16 symbols of 64 bytes, with only 40 bytes of the last one sent
+        assert_eq!(encoder.get_config().symbol_size(), 64);
+        let source = encoder.get_encoded_packets(0);
+        assert_eq!(source.len(), 16);
+        assert_eq!(source[15].data().len(), 40);
+        let parity = &encoder.xor_parity(4)[3];
+        assert_eq!(parity.esis(), 12..16);
+
+        let without = |esi: usize| -> Vec<EncodingPacket> {
+            source[12..]
+                .iter()
+                .filter(|packet| packet.payload_id().encoding_symbol_id() != esi as u32)
+                .cloned()
+                .collect()
+        };
+        assert_eq!(parity.recover(&without(13)), Some(source[13].clone()));
+        let last = parity.recover(&without(15)).unwrap();
+        assert_eq!(&last.data()[..40], source[15].data());
+        assert_eq!(&last.data()[40..], &[0; 24][..]);
+
+        let mut decoder = Decoder::new(encoder.get_config());
+        let mut received = source[..15].to_vec();
+        received.push(last);
+        assert_eq!(decoder.add_packets(received), Some(data));
+
+        // Only the final symbol of the group may be short
+        let mut truncated = without(13);
+        truncated[0] = EncodingPacket::new(PayloadId::new(0, 12), source[12].data()[..40].to_vec());
+        assert_eq!(parity.recover(&truncated), None);
+        let mut extended = without(13);
+        extended[1] = EncodingPacket::new(PayloadId::new(0, 14), vec![0; 65]);
+        assert_eq!(parity.recover(&extended), None);
+    }
+}
@@ -0,0 +1,82 @@
+// Helpers shared by the integration tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use raptorq::EncodingPacket;
+
+// A model of which packets a lossy channel drops
+#[derive(Clone, Copy, Debug)]
+pub enum LossModel {
+    // Each packet is lost independently, with probability loss_rate
+    Uniform {
+        loss_rate: f64,
+    },
+    // The Gilbert-Elliott model of bursty loss: the channel is a two state Markov chain, which
+    // moves from the good to the bad state with probability good_to_bad after each packet, and
+    // back with probability bad_to_good. Packets are lost with probability good_loss_rate in the
+    // good state, and bad_loss_rate in the bad state.
+    GilbertElliott {
+        good_to_bad: f64,
+        bad_to_good: f64,
+        good_loss_rate: f64,
+        bad_loss_rate: f64,
+    },
+}
+
+impl LossModel {
+    // The long run fraction of packets lost
+    pub fn mean_loss_rate(&self) -> f64 {
+        match *self {
+            LossModel::Uniform { loss_rate } => loss_rate,
+            LossModel::GilbertElliott {
+                good_to_bad,
+                bad_to_good,
+                good_loss_rate,
+                bad_loss_rate,
+            } => {
+                let bad = good_to_bad / (good_to_bad + bad_to_good);
+                (1.0 - bad) * good_loss_rate + bad * bad_loss_rate
+            }
+        }
+    }
+
+    // Returns the packets which make it through the channel, in the order they were sent. The
+    // same seed always drops the same packets, so a longer list with the same seed loses the same
+    // packets from its start. The Gilbert-Elliott channel starts in the good state.
+    pub fn transmit(&self, packets: Vec<EncodingPacket>, seed: u64) -> Vec<EncodingPacket> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut bad_state = false;
+        packets
+            .into_iter()
+            .filter(|_| match *self {
+                LossModel::Uniform { loss_rate } => !rng.gen_bool(loss_rate),
+                LossModel::GilbertElliott {
+                    good_to_bad,
+                    bad_to_good,
+                    good_loss_rate,
+                    bad_loss_rate,
+                } => {
+                    let lost = if bad_state {
+                        rng.gen_bool(bad_loss_rate)
+                    } else {
+                        rng.gen_bool(good_loss_rate)
+                    };
+                    bad_state = if bad_state {
+                        !rng.gen_bool(bad_to_good)
+                    } else {
+                        rng.gen_bool(good_to_bad)
+                    };
+                    !lost
+                }
+            })
+            .collect()
+    }
+
+    // The number of repair packets per block to send with `source_symbols` source packets, so
+    // that on average `source_symbols + overhead` packets arrive
+    pub fn repair_packets_for(&self, source_symbols: u32, overhead: u32) -> u32 {
+        let sent = ((source_symbols + overhead) as f64 / (1.0 - self.mean_loss_rate())).ceil();
+        sent as u32 - source_symbols
+    }
+}
@@ -0,0 +1,85 @@
+//! Regression snapshot of the encoding symbols generated by this crate.
+//!
+//! The expected symbols were produced by this crate itself, so these tests only detect changes
+//! to symbol generation; they do not show that the symbols match RFC 6330 or any other
+//! implementation. They also only cover a symbol size of 4 bytes.
+//!
+//! Conformance checking against vectors generated by an independent RaptorQ implementation is
+//! still to be done. No such implementation was available to generate them, so this snapshot
+//! doesn't replace that check.
+
+use raptorq::{ObjectTransmissionInformation, SourceBlockEncoder};
+
+const SYMBOL_SIZE: u16 = 4;
+
+// Snapshot of encoding symbols, one per line: "K' ESI symbol", with the symbol in hex. Each source
+// block consists of K' symbols, where byte i of the block is i mod 251.
+const SNAPSHOT: &str = include_str!("vectors/encoding_symbols_snapshot.txt");
+
+fn source_block(kprime: u32) -> Vec<u8> {
+    (0..kprime as usize * SYMBOL_SIZE as usize)
+        .map(|i| (i % 251) as u8)
+        .collect()
+}
+
+fn parse_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+// Checks all the snapshot symbols with K' <= max_kprime
+fn check_snapshot(max_kprime: u32) {
+    let config = ObjectTransmissionInformation::new(0, SYMBOL_SIZE, 0, 1, 1);
+    let mut encoder: Option<(u32, SourceBlockEncoder)> = None;
+    let mut checked = 0;
+    for line in SNAPSHOT.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        assert_eq!(fields.len(), 3, "Malformed snapshot line: {}", line);
+        let kprime: u32 = fields[0].parse().unwrap();
+        if kprime > max_kprime {
+            continue;
+        }
+        let esi: u32 = fields[1].parse().unwrap();
+        let expected = parse_hex(fields[2]);
+
+        if encoder.as_ref().map(|(k, _)| *k) != Some(kprime) {
+            let data = source_block(kprime);
+            encoder = Some((kprime, SourceBlockEncoder::new2(0, &config, &data)));
+        }
+        let block_encoder = &encoder.as_ref().unwrap().1;
+        let packet = if esi < kprime {
+            block_encoder.source_packets().swap_remove(esi as usize)
+        } else {
+            block_encoder.repair_packets(esi - kprime, 1).swap_remove(0)
+        };
+        assert_eq!(packet.payload_id().encoding_symbol_id(), esi);
+        assert_eq!(
+            packet.data(),
+            &expected[..],
+            "K' = {}, ESI = {}",
+            kprime,
+            esi
+        );
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
+
+#[test]
+fn encoding_symbol_snapshot() {
+    check_snapshot(101);
+}
+
+// Large blocks are slow to encode without optimizations, so these are only run with the extended
+// tests
+#[test]
+#[ignore]
+fn encoding_symbol_snapshot_large_blocks() {
+    check_snapshot(u32::MAX);
+}
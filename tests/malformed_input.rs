@@ -0,0 +1,66 @@
+use rand::Rng;
+use raptorq::{Decoder, Encoder, EncodingPacket, ObjectTransmissionInformation};
+
+// Random bytes, or with probability 1/2 a header with a zero reserved byte and an alignment of
+// one, which is more likely to describe a valid OTI
+fn random_header<R: Rng>(rng: &mut R) -> Vec<u8> {
+    let length = rng.gen_range(0..20);
+    let mut header: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+    if header.len() >= 12 && rng.gen() {
+        header[5] = 0;
+        header[11] = 1;
+    }
+    header
+}
+
+// Random bytes, or with probability 1/2 a packet for one of the first few ESIs of the first few
+// source blocks, which is more likely to be accepted
+fn random_packet<R: Rng>(rng: &mut R) -> Vec<u8> {
+    let length = rng.gen_range(0..100);
+    let mut packet: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+    if packet.len() >= 4 && rng.gen() {
+        packet[0] = rng.gen_range(0..3);
+        packet[1] = 0;
+        packet[2] = 0;
+        packet[3] = rng.gen_range(0..100);
+    }
+    packet
+}
+
+#[test]
+fn random_input_does_not_panic() {
+    let mut rng = rand::thread_rng();
+    let data: Vec<u8> = (0..1000).map(|_| rng.gen()).collect();
+    let config = ObjectTransmissionInformation::new(data.len() as u64, 32, 2, 1, 8);
+    let encoder = Encoder::new(&data, config);
+
+    let mut decoder = Decoder::new(config);
+    let mut header_decoder: Option<Decoder> = None;
+    let mut accepted = 0;
+    for i in 0..10_000 {
+        if i % 10 == 0 {
+            let header = random_header(&mut rng);
+            header_decoder = Decoder::from_header_packet(&header).ok();
+            let _ = ObjectTransmissionInformation::try_deserialize(&header);
+        }
+        let packet = match EncodingPacket::try_deserialize(&random_packet(&mut rng)) {
+            Ok(packet) => packet,
+            Err(_) => continue,
+        };
+        if let Some(ref mut header_decoder) = header_decoder {
+            let _ = header_decoder.try_decode_packet(packet.clone());
+        }
+        if decoder.try_decode_packet(packet).is_ok() {
+            accepted += 1;
+        }
+    }
+    assert!(accepted > 0);
+
+    // The decoder still works after receiving garbage, although the object is likely corrupted
+    for packet in encoder.get_encoded_packets(10) {
+        let bytes = packet.serialize();
+        let packet = EncodingPacket::try_deserialize(&bytes).unwrap();
+        decoder.try_decode_packet(packet).unwrap();
+    }
+    assert!(decoder.get_result().is_some());
+}
@@ -0,0 +1,205 @@
+use raptorq::{
+    BlockState, Decoder, Encoder, EncodingPacket, ObjectTransmissionInformation, PayloadId,
+};
+
+mod common;
+use common::LossModel;
+
+const UNIFORM: LossModel = LossModel::Uniform { loss_rate: 0.1 };
+
+// Bursts of about 5 packets, of which 80% are lost, roughly every 50 packets
+const BURSTY: LossModel = LossModel::GilbertElliott {
+    good_to_bad: 0.02,
+    bad_to_good: 0.2,
+    good_loss_rate: 0.01,
+    bad_loss_rate: 0.8,
+};
+
+fn test_data(length: usize) -> Vec<u8> {
+    (0..length).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+// Sends `data` over the channel, with enough repair packets that on average the decoder receives
+// `margin` more packets than source symbols for each block, and feeds them to the decoder one at
+// a time. Returns the number of packets received for each block at the point it was decoded, or
+// None if the channel lost too many.
+fn transfer(
+    data: &[u8],
+    config: ObjectTransmissionInformation,
+    model: LossModel,
+    margin: u32,
+    seed: u64,
+) -> Vec<Option<u32>> {
+    let encoder = Encoder::new(data, config);
+    let mut decoder = Decoder::new(config);
+    let mut received = vec![];
+    for block in encoder.get_block_encoders() {
+        let source = block.source_packets();
+        let repair = model.repair_packets_for(source.len() as u32, margin);
+        let mut packets = source;
+        packets.extend(block.repair_packets(0, repair));
+        let sbn = packets[0].payload_id().source_block_number();
+
+        let mut count = 0;
+        let mut decoded_at = None;
+        for packet in model.transmit(packets, seed + sbn as u64) {
+            count += 1;
+            decoder.add_new_packet(packet);
+            if decoder.block_state(sbn) == BlockState::Decoded {
+                decoded_at = Some(count);
+                break;
+            }
+        }
+        received.push(decoded_at);
+    }
+    if received.iter().all(Option::is_some) {
+        assert_eq!(decoder.get_result().unwrap(), data);
+    }
+    received
+}
+
+// Empty packets with consecutive ESIs, for measuring the loss models
+fn numbered_packets(count: u32) -> Vec<EncodingPacket> {
+    (0..count)
+        .map(|esi| EncodingPacket::new(PayloadId::new(0, esi), vec![]))
+        .collect()
+}
+
+// The source symbols of each block of `config`
+fn block_symbols(data_length: u64, config: ObjectTransmissionInformation) -> Vec<u32> {
+    Encoder::new(&test_data(data_length as usize), config)
+        .get_block_encoders()
+        .iter()
+        .map(|block| block.source_packets().len() as u32)
+        .collect()
+}
+
+#[test]
+fn loss_models_match_mean_loss_rate() {
+    let packets = numbered_packets(100_000);
+    for model in [UNIFORM, BURSTY].iter() {
+        let received = model.transmit(packets.clone(), 1).len();
+        let loss = 1.0 - received as f64 / packets.len() as f64;
+        assert!(
+            (loss - model.mean_loss_rate()).abs() < 0.02,
+            "{:?} lost {}",
+            model,
+            loss
+        );
+    }
+}
+
+#[test]
+fn bursty_loss_is_correlated() {
+    // Same mean loss rate as BURSTY, but independent
+    let uniform = LossModel::Uniform {
+        loss_rate: BURSTY.mean_loss_rate(),
+    };
+    let packets = numbered_packets(100_000);
+    let longest_burst = |model: LossModel| {
+        let received: Vec<u32> = model
+            .transmit(packets.clone(), 2)
+            .iter()
+            .map(|packet| packet.payload_id().encoding_symbol_id())
+            .collect();
+        received.windows(2).map(|w| w[1] - w[0] - 1).max().unwrap()
+    };
+    assert!(longest_burst(BURSTY) > 2 * longest_burst(uniform));
+}
+
+// RaptorQ decodes from K received symbols most of the time, and from K + 2 with overwhelming
+// probability, regardless of which symbols were lost
+fn check_overhead(model: LossModel) {
+    let length = 20_000;
+    let data = test_data(length);
+    let config = ObjectTransmissionInformation::new(length as u64, 128, 3, 1, 8);
+    let symbols = block_symbols(length as u64, config);
+    for seed in 0..5 {
+        // The margin makes it unlikely that the channel loses so many packets that the decoder
+        // never receives K + 2
+        let received = transfer(&data, config, model, 30, seed * 7);
+        for (k, received) in symbols.iter().zip(received) {
+            let received = received.expect("channel lost too many packets");
+            assert!(received >= *k);
+            assert!(
+                received <= k + 2,
+                "needed {} symbols for K = {}",
+                received,
+                k
+            );
+        }
+    }
+}
+
+#[test]
+fn uniform_loss_overhead() {
+    check_overhead(UNIFORM);
+}
+
+#[test]
+fn bursty_loss_overhead() {
+    check_overhead(BURSTY);
+}
+
+#[test]
+fn heavy_bursty_loss() {
+    // Long outages, losing half of all packets
+    let model = LossModel::GilbertElliott {
+        good_to_bad: 0.05,
+        bad_to_good: 0.05,
+        good_loss_rate: 0.0,
+        bad_loss_rate: 1.0,
+    };
+    let length = 20_000;
+    let data = test_data(length);
+    let config = ObjectTransmissionInformation::new(length as u64, 128, 1, 1, 8);
+    // The margin covers the variance of the loss, since outages are long
+    let received = transfer(&data, config, model, 60, 3);
+    assert!(received[0].unwrap() <= block_symbols(length as u64, config)[0] + 2);
+}
+
+#[cfg(feature = "debug-tools")]
+#[test]
+fn min_overhead() {
+    use raptorq::debug::{decodes_with, simulate_min_overhead};
+
+    let length = 5_000;
+    let data = test_data(length);
+    let config = ObjectTransmissionInformation::new(length as u64, 64, 2, 1, 8);
+    let encoder = Encoder::new(&data, config);
+    // Each block of each trial gets its own seed, so that sending more packets for one block
+    // doesn't change which packets are lost from the others
+    let channel = |model: LossModel| {
+        move |trial: u32, block: u8, packets| {
+            model.transmit(packets, ((trial as u64) << 8) | block as u64)
+        }
+    };
+    let lossless = LossModel::Uniform { loss_rate: 0.0 };
+    assert_eq!(simulate_min_overhead(&encoder, 3, channel(lossless)), 0);
+
+    let trials = 5;
+    let overhead = simulate_min_overhead(&encoder, trials, channel(UNIFORM));
+    // Each trial loses about 10% of the packets, so the repair packets must at least make up for
+    // that, but more than double it is implausible
+    let k = block_symbols(length as u64, config)[0];
+    let expected = UNIFORM.repair_packets_for(k, 0);
+    assert!(
+        overhead >= expected / 2 && overhead <= 2 * expected + 4,
+        "{}",
+        overhead
+    );
+
+    // Minimal: every trial decodes with this many, but not one fewer
+    assert!(decodes_with(
+        &encoder,
+        trials,
+        overhead,
+        &mut channel(UNIFORM)
+    ));
+    assert!(!decodes_with(
+        &encoder,
+        trials,
+        overhead - 1,
+        &mut channel(UNIFORM)
+    ));
+}